@@ -1,15 +1,20 @@
 use derivative::Derivative;
-use std::{collections::HashMap, fmt::Debug, hash::Hash};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+};
 
 use crate::term::{
     alloc::{Allocator, Reallocate, System, Zero},
-    debug_reference, EqualityCache, Index, None, NormalizationError, Primitives, Show, Term,
+    debug_reference, fingerprint, EqualityCache, Equivalence, Index, Mismatch, None,
+    NormalizationError, Primitives, Show, Term, TermHash,
 };
 
 #[derive(Derivative)]
 #[derivative(Debug(bound = "T: Show, U: Show"))]
 pub enum AnalysisError<T, U: Primitives<T> = None, A: Allocator<T, U> = System> {
-    NormalizationError(NormalizationError),
+    NormalizationError(NormalizationError<T, U, A>),
     NonFunctionLambda {
         term: Term<T, U, A>,
         ty: Term<T, U, A>,
@@ -17,6 +22,7 @@ pub enum AnalysisError<T, U: Primitives<T> = None, A: Allocator<T, U> = System>
     TypeError {
         expected: Term<T, U, A>,
         got: Term<T, U, A>,
+        mismatch: Option<Mismatch<T, U, A>>,
     },
     ErasureMismatch {
         lambda: Term<T, U, A>,
@@ -39,8 +45,10 @@ pub enum AnalysisError<T, U: Primitives<T> = None, A: Allocator<T, U> = System>
     },
 }
 
-impl<T, U: Primitives<T>, A: Allocator<T, U>> From<NormalizationError> for AnalysisError<T, U, A> {
-    fn from(e: NormalizationError) -> Self {
+impl<T, U: Primitives<T>, A: Allocator<T, U>> From<NormalizationError<T, U, A>>
+    for AnalysisError<T, U, A>
+{
+    fn from(e: NormalizationError<T, U, A>) -> Self {
         AnalysisError::NormalizationError(e)
     }
 }
@@ -59,8 +67,32 @@ impl<'a, T> DefinitionResult<'a, T> {
     }
 }
 
+// The result of a jet that accepted its arguments.
+type JetApplication<T, U, A> = Option<Result<Term<T, U, A>, NormalizationError<T, U, A>>>;
+
 pub trait Definitions<T, U: Primitives<T> = None, A: Allocator<T, U> = System> {
     fn get(&self, name: &T) -> Option<DefinitionResult<Term<T, U, A>>>;
+
+    // The number of non-erased arguments taken by the jet attached to a definition, if it has one.
+    fn jet_arity(&self, _: &T) -> Option<usize> {
+        None
+    }
+
+    // Applies the jet attached to a definition to the erased normal forms of its non-erased
+    // arguments. `None` means the jet can't handle these arguments, and the definition is unfolded
+    // as usual.
+    fn apply_jet<B: Reallocate<T, U, A>>(
+        &self,
+        _: &T,
+        _: Vec<Term<T, U, B>>,
+        _: &B,
+    ) -> JetApplication<T, U, B>
+    where
+        T: Clone,
+        U: Clone,
+    {
+        None
+    }
 }
 
 pub struct Empty;
@@ -73,6 +105,127 @@ impl<T, U: Primitives<T>, A: Allocator<T, U>> TypedDefinitions<T, U, A> for Empt
 
 pub trait TypedDefinitions<T, U: Primitives<T> = None, A: Allocator<T, U> = System> {
     fn get_typed(&self, name: &T) -> Option<DefinitionResult<(Term<T, U, A>, Term<T, U, A>)>>;
+
+    fn is_opaque(&self, _: &T) -> bool {
+        false
+    }
+
+    // As for `Definitions`, which forwards to these.
+    fn jet_arity(&self, _: &T) -> Option<usize> {
+        None
+    }
+
+    fn apply_jet<B: Reallocate<T, U, A>>(
+        &self,
+        _: &T,
+        _: Vec<Term<T, U, B>>,
+        _: &B,
+    ) -> JetApplication<T, U, B>
+    where
+        T: Clone,
+        U: Clone,
+    {
+        None
+    }
+}
+
+pub struct OpaqueDefinitions<T, D> {
+    pub definitions: D,
+    pub opaque: HashSet<T>,
+}
+
+impl<T, D> OpaqueDefinitions<T, D> {
+    pub fn new(definitions: D, opaque: HashSet<T>) -> Self {
+        OpaqueDefinitions {
+            definitions,
+            opaque,
+        }
+    }
+}
+
+impl<T: Hash + Eq, U: Primitives<T> + Hash, A: Allocator<T, U>>
+    OpaqueDefinitions<T, HashMap<T, (Term<T, U, A>, Term<T, U, A>)>>
+{
+    // Changes whenever a definition is added, removed or altered, or made opaque or transparent.
+    // Whatever is cached against one set of definitions, like equalities, only carries over to a
+    // set with the same fingerprint.
+    pub fn fingerprint(&self) -> TermHash {
+        fingerprint(
+            self.definitions
+                .iter()
+                .map(|(name, definition)| (name, definition, self.opaque.contains(name))),
+        )
+    }
+}
+
+impl<T: Hash + Eq, U: Primitives<T>, A: Allocator<T, U>, D: TypedDefinitions<T, U, A>>
+    TypedDefinitions<T, U, A> for OpaqueDefinitions<T, D>
+{
+    fn get_typed(&self, name: &T) -> Option<DefinitionResult<'_, (Term<T, U, A>, Term<T, U, A>)>> {
+        self.definitions.get_typed(name)
+    }
+
+    fn is_opaque(&self, name: &T) -> bool {
+        self.opaque.contains(name) || self.definitions.is_opaque(name)
+    }
+
+    fn jet_arity(&self, name: &T) -> Option<usize> {
+        self.definitions.jet_arity(name)
+    }
+
+    fn apply_jet<B: Reallocate<T, U, A>>(
+        &self,
+        name: &T,
+        arguments: Vec<Term<T, U, B>>,
+        alloc: &B,
+    ) -> JetApplication<T, U, B>
+    where
+        T: Clone,
+        U: Clone,
+    {
+        self.definitions.apply_jet(name, arguments, alloc)
+    }
+}
+
+pub(crate) struct Transparent<'a, D>(&'a D);
+
+impl<'a, T, U: Primitives<T>, A: Allocator<T, U>, D: TypedDefinitions<T, U, A>>
+    TypedDefinitions<T, U, A> for Transparent<'a, D>
+{
+    fn get_typed(&self, name: &T) -> Option<DefinitionResult<'_, (Term<T, U, A>, Term<T, U, A>)>> {
+        if self.0.is_opaque(name) {
+            None
+        } else {
+            self.0.get_typed(name)
+        }
+    }
+
+    fn is_opaque(&self, name: &T) -> bool {
+        self.0.is_opaque(name)
+    }
+
+    // Jets run during checking as they do in normalization, except on opaque definitions, whose
+    // values a jet would otherwise reveal.
+    fn jet_arity(&self, name: &T) -> Option<usize> {
+        if self.0.is_opaque(name) {
+            None
+        } else {
+            self.0.jet_arity(name)
+        }
+    }
+
+    fn apply_jet<B: Reallocate<T, U, A>>(
+        &self,
+        name: &T,
+        arguments: Vec<Term<T, U, B>>,
+        alloc: &B,
+    ) -> JetApplication<T, U, B>
+    where
+        T: Clone,
+        U: Clone,
+    {
+        self.0.apply_jet(name, arguments, alloc)
+    }
 }
 
 impl<T: Hash + Eq, U: Primitives<T>, A: Allocator<T, U>> TypedDefinitions<T, U, A>
@@ -93,6 +246,23 @@ impl<U, V: Primitives<U>, T: TypedDefinitions<U, V, A>, A: Allocator<U, V>> Defi
             Some(DefinitionResult::Owned((_, a))) => Some(DefinitionResult::Owned(a)),
         }
     }
+
+    fn jet_arity(&self, name: &U) -> Option<usize> {
+        TypedDefinitions::jet_arity(self, name)
+    }
+
+    fn apply_jet<B: Reallocate<U, V, A>>(
+        &self,
+        name: &U,
+        arguments: Vec<Term<U, V, B>>,
+        alloc: &B,
+    ) -> JetApplication<U, V, B>
+    where
+        U: Clone,
+        V: Clone,
+    {
+        TypedDefinitions::apply_jet(self, name, arguments, alloc)
+    }
 }
 
 impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
@@ -105,23 +275,25 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
     ) -> Result<(), AnalysisError<T, V, A>>
     where
         T: Show + Clone + PartialEq + Hash,
-        V: Show + Clone + Hash,
+        V: Show + Clone + Hash + PartialEq,
         A: Reallocate<T, V, B>,
     {
         use Term::*;
 
+        let transparent = Transparent(definitions);
+
         let mut reduced = alloc.copy(ty);
-        reduced.weak_normalize_in(definitions, alloc)?;
+        reduced.weak_normalize_in(&transparent, alloc)?;
 
         Ok(match self {
             Lambda { body, erased } => {
                 if let Function {
                     argument_type,
-                    mut return_type,
+                    return_type,
                     erased: function_erased,
-                } = reduced
+                } = &mut reduced
                 {
-                    if *erased != function_erased {
+                    if erased != function_erased {
                         Err(AnalysisError::ErasureMismatch {
                             lambda: alloc.copy(self),
                             ty: alloc.copy(ty),
@@ -134,7 +306,7 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
                     };
                     let mut argument_annotation = Term::Annotation {
                         checked: true,
-                        ty: argument_type,
+                        ty: alloc.alloc(argument_type.take()),
                         expression: alloc.alloc(Term::Variable(Index::top())),
                     };
 
@@ -162,9 +334,9 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
             }
             Duplicate { expression, body } => {
                 let mut expression_ty = expression.infer_in(definitions, alloc, &mut *cache)?;
-                expression_ty.weak_normalize_in(definitions, alloc)?;
-                let expression_ty = if let Wrap(term) = expression_ty {
-                    term
+                expression_ty.weak_normalize_in(&transparent, alloc)?;
+                let expression_ty = if let Wrap(term) = &mut expression_ty {
+                    alloc.alloc(term.take())
                 } else {
                     Err(AnalysisError::UnboxedDuplication {
                         term: alloc.copy(self),
@@ -181,8 +353,8 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
                 body.check_in(&reduced, definitions, alloc, cache)?;
             }
             Put(term) => {
-                if let Wrap(ty) = reduced {
-                    term.check_in(&ty, definitions, alloc, cache)?;
+                if let Wrap(ty) = &reduced {
+                    term.check_in(ty, definitions, alloc, cache)?;
                 } else {
                     Err(AnalysisError::ExpectedWrap {
                         term: alloc.copy(self),
@@ -192,8 +364,11 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
             }
             _ => {
                 let inferred = self.infer_in(definitions, alloc, &mut *cache)?;
-                if !inferred.equivalent_in(&reduced, definitions, alloc, cache)? {
+                if let Equivalence::Unequal(mismatch) =
+                    inferred.equivalence_in(&reduced, &transparent, alloc, cache)?
+                {
                     Err(AnalysisError::TypeError {
+                        mismatch,
                         expected: alloc.copy(ty),
                         got: inferred,
                     })?;
@@ -210,11 +385,13 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
     ) -> Result<Term<T, V, A>, AnalysisError<T, V, A>>
     where
         T: Show + Clone + PartialEq + Hash,
-        V: Show + Clone + Hash,
+        V: Show + Clone + Hash + PartialEq,
         A: Reallocate<T, V, B>,
     {
         use Term::*;
 
+        let transparent = Transparent(definitions);
+
         Ok(match self {
             Universe => Universe,
             Annotation {
@@ -261,7 +438,7 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
                 erased,
             } => {
                 let mut function_type = function.infer_in(definitions, alloc, &mut *cache)?;
-                function_type.weak_normalize_in(definitions, alloc)?;
+                function_type.weak_normalize_in(&transparent, alloc)?;
                 if let Function {
                     argument_type,
                     return_type,
@@ -292,7 +469,7 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
                         &argument_annotation,
                         alloc,
                     );
-                    return_type.weak_normalize_in(definitions, alloc)?;
+                    return_type.weak_normalize_in(&transparent, alloc)?;
                     return_type
                 } else {
                     Err(AnalysisError::NonFunctionApplication(alloc.copy(function)))?
@@ -328,7 +505,7 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
     ) -> Result<(), AnalysisError<T, V, A>>
     where
         T: Show + Clone + PartialEq + Debug + Hash,
-        V: Show + Clone + Hash,
+        V: Show + Clone + Hash + PartialEq,
         A: Zero + Reallocate<T, V, A>,
     {
         let alloc = A::zero();
@@ -344,7 +521,7 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
     where
         A: Zero + Reallocate<T, V, A>,
         T: Clone + PartialEq + Show + Debug + Hash,
-        V: Clone + Show + Hash,
+        V: Clone + Show + Hash + PartialEq,
     {
         let alloc = A::zero();
 
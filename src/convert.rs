@@ -2,7 +2,10 @@ use derivative::Derivative;
 
 use crate::{
     net::{AgentExt, AgentType, NetBuilder, PortExt, Slot, VisitNet},
-    term::{alloc::Allocator, Definitions, Index, None, Primitives, Show, Stratified, Term},
+    term::{
+        alloc::{Allocator, Reallocate},
+        Definitions, Index, None, NormalizationError, Primitives, Show, Stratified, Term,
+    },
 };
 
 use std::mem::replace;
@@ -11,6 +14,122 @@ use std::mem::replace;
 #[derivative(Debug(bound = "T: Show, V: Show"))]
 pub enum NetError<T, V: Primitives<T>, A: Allocator<T, V>> {
     TypedTerm(Term<T, V, A>),
+    NormalizationError(NormalizationError<T, V, A>),
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> From<NormalizationError<T, V, A>>
+    for NetError<T, V, A>
+{
+    fn from(e: NormalizationError<T, V, A>) -> Self {
+        NetError::NormalizationError(e)
+    }
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+    // Whether this is an application that saturates the jet of the definition at its head.
+    fn saturates_jet<U: Definitions<T, V, A>>(&self, definitions: &U) -> bool {
+        let mut arguments = 0;
+        let mut term = self;
+
+        loop {
+            match term {
+                Term::Apply {
+                    function, erased, ..
+                } => {
+                    if !*erased {
+                        arguments += 1;
+                    }
+                    term = function;
+                }
+                Term::Reference(name) => {
+                    return arguments > 0 && definitions.jet_arity(name) == Some(arguments)
+                }
+                _ => return false,
+            }
+        }
+    }
+
+    // What to build in place of this application when it saturates a jet. The net has no agent
+    // for a jet, so one only runs while the net is built, and only takes the place of the
+    // application when that changes nothing but the cost: the arguments must be closed, so the
+    // jet is given their values, the jet must accept them, and its answer must be stratified as
+    // it stands. A jet answers in erased normal form, which has no boxes left to share what a
+    // non-linear result uses more than once, so anything else is built from the definition.
+    pub(crate) fn jet_result<U: Definitions<T, V, A>>(
+        &self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<Option<Self>, NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, A>,
+    {
+        if !self.saturates_jet(definitions) || !self.is_closed() {
+            return Ok(Option::None);
+        }
+
+        let mut arguments = vec![];
+        let mut term = self;
+        while let Term::Apply {
+            function,
+            argument,
+            erased,
+        } = term
+        {
+            if !*erased {
+                let mut argument = alloc.copy(argument);
+                argument.normalize_in(definitions, alloc)?;
+                arguments.push(argument);
+            }
+            term = function;
+        }
+        arguments.reverse();
+
+        let name = match term {
+            Term::Reference(name) => name,
+            _ => unreachable!(),
+        };
+        Ok(match definitions.apply_jet(name, arguments, alloc) {
+            Some(result) => Some(result?).filter(|result| result.is_stratified().is_ok()),
+            Option::None => Option::None,
+        })
+    }
+
+    // Whether every variable in this term is bound within it.
+    pub(crate) fn is_closed(&self) -> bool {
+        use Term::*;
+
+        let mut stack = vec![(self, 0)];
+        while let Some((term, bound)) = stack.pop() {
+            match term {
+                Variable(Index(index)) => {
+                    if *index >= bound {
+                        return false;
+                    }
+                }
+                Lambda { body, .. } => stack.push((body, bound + 1)),
+                Apply {
+                    function, argument, ..
+                } => stack.extend([(&**function, bound), (&**argument, bound)]),
+                Put(term) | Wrap(term) => stack.push((term, bound)),
+                Duplicate { expression, body } => {
+                    stack.extend([(&**expression, bound), (&**body, bound + 1)])
+                }
+                Function {
+                    argument_type,
+                    return_type,
+                    ..
+                } => stack.extend([(&**argument_type, bound), (&**return_type, bound + 2)]),
+                Annotation { expression, ty, .. } => {
+                    stack.extend([(&**expression, bound), (&**ty, bound)])
+                }
+                Reference(_) | Primitive(_) | Universe => {}
+            }
+        }
+
+        true
+    }
 }
 
 impl<T, A: Allocator<T, None>> Term<T, None, A> {
@@ -25,9 +144,14 @@ impl<T, A: Allocator<T, None>> Term<T, None, A> {
     where
         T: Clone,
         N::Port: PartialEq + Clone,
+        A: Reallocate<T, None, A>,
     {
         use Term::*;
 
+        if let Some(result) = self.jet_result(definitions, alloc)? {
+            return result.build_net_in(net, definitions, var_ptrs, idx, alloc);
+        }
+
         Ok(match self {
             Variable(symbol) => {
                 let ptr = var_ptrs.iter().rev().nth(symbol.0).unwrap().clone();
@@ -43,6 +167,7 @@ impl<T, A: Allocator<T, None>> Term<T, None, A> {
                 }
             }
             Put(term) => term.build_net_in(net, definitions, var_ptrs, idx, alloc)?,
+            // `stratified_in` has checked that every reference that reaches the net is defined.
             Reference(name) => definitions.get(name).unwrap().as_ref().build_net_in(
                 net,
                 definitions,
@@ -114,7 +239,7 @@ pub trait NetBuilderExt<T, U: Definitions<T, V, A>, V: Primitives<T>, A: Allocat
         Self: Sized;
 }
 
-impl<S: NetBuilder, T: Clone, U: Definitions<T, None, A>, A: Allocator<T, None>>
+impl<S: NetBuilder, T: Clone, U: Definitions<T, None, A>, A: Reallocate<T, None, A>>
     NetBuilderExt<T, U, None, A> for S
 where
     S::Port: PartialEq + Clone,
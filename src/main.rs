@@ -1,36 +1,144 @@
-use std::{collections::HashMap, fmt::Debug, fs::read_to_string, io, process::exit};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    fs::read_to_string,
+    io,
+    process::exit,
+};
 #[cfg(any(feature = "graphviz", feature = "accelerated"))]
 use welkin_core::net::{Index, Net, VisitNetExt};
-use welkin_core::term::{alloc::System, typed::Definitions, NullCache, ParseError, Term};
+use welkin_core::term::{
+    alloc::System, typed::Definitions, BinderKind, EqualityCache, FileCache, NullCache,
+    OpaqueDefinitions, ParseError, Term,
+};
 
 fn e<E: Debug>(e: E) -> String {
     format!("{:?}", e)
 }
 
-fn entry(buffer: String, term: String) -> Result<(), String> {
+// A row for every binder in `term`, with the box depth of each use. Erased uses are bracketed.
+fn usage_table(term: &Term<String>) -> String {
+    let usage = term.usage();
+    let width = usage
+        .iter()
+        .map(|usage| usage.binder.to_string().len())
+        .chain(Some("binder".len()))
+        .max()
+        .unwrap();
+
+    let mut table = format!(
+        "{:<width$}  {:<13}  {:>4}  {:>6}  depths",
+        "binder",
+        "kind",
+        "uses",
+        "erased",
+        width = width
+    );
+    for usage in usage {
+        let depths: Vec<_> = usage
+            .uses
+            .iter()
+            .map(|usage| {
+                if usage.erased {
+                    format!("[{}]", usage.depth)
+                } else {
+                    usage.depth.to_string()
+                }
+            })
+            .collect();
+        let row = format!(
+            "\n{:<width$}  {:<13}  {:>4}  {:>6}  {}",
+            usage.binder.to_string(),
+            match usage.kind {
+                BinderKind::Lambda => "lambda",
+                BinderKind::ErasedLambda => "erased lambda",
+                BinderKind::Duplication => "duplication",
+            },
+            usage.computational_uses(),
+            usage.erased_uses(),
+            depths.join(", "),
+            width = width
+        );
+        table.push_str(row.trim_end());
+    }
+    table
+}
+
+#[derive(Default)]
+struct Options {
+    statistics: bool,
+    trace: bool,
+    complexity: bool,
+    usage: bool,
+}
+
+type Loaded = OpaqueDefinitions<String, HashMap<String, (Term<String>, Term<String>)>>;
+
+fn load(buffer: String) -> Result<Loaded, String> {
     let definitions: Definitions = buffer.parse().map_err(|e: ParseError| e.to_string())?;
 
+    let opaque: HashSet<_> = definitions.opaque.into_iter().collect();
     let definitions: HashMap<_, _> = definitions.terms.into_iter().collect();
-    for (name, def) in &definitions {
-        def.1.is_stratified().map_err(e)?;
-        if def.0.is_recursive_in(&definitions, &System, &System) {
+    Ok(OpaqueDefinitions::new(definitions, opaque))
+}
+
+fn entry(
+    definitions: &Loaded,
+    term: String,
+    cache: &mut impl EqualityCache,
+    options: &Options,
+) -> Result<(), String> {
+    for (name, def) in &definitions.definitions {
+        if def.0.is_recursive_in(definitions, &System, &System) {
             Err(format!("{} is defined recursively", name))?;
         }
-        if def.1.is_recursive_in(&definitions, &System, &System) {
+        if def.1.is_recursive_in(definitions, &System, &System) {
             Err(format!("{} is defined recursively", name))?;
         }
-        def.0
-            .check(&Term::Universe, &definitions, &mut NullCache)
-            .map_err(e)?;
         def.1
-            .check(&def.0, &definitions, &mut NullCache)
+            .is_stratified_across(definitions)
+            .map_err(|e| format!("{} isn't stratified: {}", name, e))?;
+        def.0
+            .check(&Term::Universe, definitions, &mut *cache)
             .map_err(e)?;
+        def.1.check(&def.0, definitions, &mut *cache).map_err(e)?;
     }
 
     let entry = Term::Reference(term.clone())
-        .stratified(&definitions)
+        .stratified(definitions)
         .map_err(e)?;
 
+    if options.usage {
+        if let Some((_, definition)) = definitions.definitions.get(&term) {
+            println!("{}", usage_table(definition));
+        }
+    }
+
+    if options.complexity {
+        println!("{}", entry.complexity());
+    }
+
+    if options.trace {
+        let mut term: Term<String> = Term::Reference(term.clone());
+        println!("{}", term.named());
+        let mut steps = 0;
+        while let Some(reduction) = term.step(definitions).map_err(|e| e.to_string())? {
+            steps += 1;
+            println!("{}. {} at {}", steps, reduction.rule, reduction.path);
+            println!("    {}", term.named());
+        }
+    }
+
+    if options.statistics {
+        let mut entry = entry.clone();
+        println!(
+            "{}",
+            entry
+                .normalize_with_statistics()
+                .map_err(|e| e.to_string())?
+        );
+    }
+
     #[cfg(any(feature = "graphviz", feature = "accelerated"))]
     let entry = entry.into_net::<Net<u32>>().unwrap();
 
@@ -73,18 +181,52 @@ fn entry(buffer: String, term: String) -> Result<(), String> {
 
 fn main() -> io::Result<()> {
     let mut args = std::env::args().skip(1);
+    let mut positional = vec![];
+    let mut cache_path = None;
+    let mut options = Options::default();
 
-    if let (Some(file), Some(term)) = (args.next(), args.next()) {
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cache" => cache_path = args.next(),
+            "--statistics" => options.statistics = true,
+            "--trace" => options.trace = true,
+            "--complexity" => options.complexity = true,
+            "--usage" => options.usage = true,
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+
+    if let (Some(file), Some(term)) = (positional.next(), positional.next()) {
         let buffer = read_to_string(file)?;
-        if let Err(e) = entry(buffer, term) {
+        let result = load(buffer).and_then(|definitions| match cache_path {
+            Some(path) => {
+                let mut cache = FileCache::load(path, definitions.fingerprint()).map_err(e)?;
+                let result = entry(&definitions, term, &mut cache, &options);
+                cache.save().map_err(e)?;
+                result
+            }
+            None => entry(&definitions, term, &mut NullCache, &options),
+        });
+        if let Err(e) = result {
             eprintln!("{}", e);
             exit(1);
         }
     } else {
         eprintln!(
-            r#"Usage: welkin-core <FILE> <TERM>
+            r#"Usage: welkin-core [--cache <PATH>] [--statistics] [--trace] [--complexity] [--usage] <FILE> <TERM>
+
+Typecheck FILE as welkin-core definitions and print the normalization of TERM
 
-Typecheck FILE as welkin-core definitions and print the normalization of TERM"#
+Options:
+    --cache <PATH>    Load and persist type equality results in the file at PATH, which is
+                      discarded when the definitions in FILE change
+    --statistics      Print reduction statistics for term-level normalization of TERM
+    --trace           Print each reduction step of term-level normalization of TERM
+    --complexity      Print the box depth and size of TERM and the bound they give on net
+                      reduction
+    --usage           Print how each binder in the definition of TERM uses its variable"#
         )
     }
 
@@ -1,4 +1,4 @@
-use std::ops::DerefMut;
+use std::{mem::replace, ops::DerefMut};
 
 use super::{Primitives, Term};
 
@@ -94,51 +94,229 @@ impl<T, U: Primitives<T>> Allocator<T, U> for System {
 
 impl<T: Clone, U: Primitives<T> + Clone, A: Allocator<T, U> + Zero> Clone for Term<T, U, A> {
     fn clone(&self) -> Self {
-        use Term::*;
         let alloc = A::zero();
 
+        let mut root = self.clone_node(&alloc);
+        let mut stack = vec![(&mut root, self)];
+
+        while let Some((target, source)) = stack.pop() {
+            use Term::*;
+
+            match (target, source) {
+                (Lambda { body, .. }, Lambda { body: source, .. })
+                | (Put(body), Put(source))
+                | (Wrap(body), Wrap(source)) => {
+                    **body = source.clone_node(&alloc);
+                    stack.push((body, source));
+                }
+                (
+                    Apply {
+                        function, argument, ..
+                    },
+                    Apply {
+                        function: source_function,
+                        argument: source_argument,
+                        ..
+                    },
+                )
+                | (
+                    Duplicate {
+                        expression: function,
+                        body: argument,
+                    },
+                    Duplicate {
+                        expression: source_function,
+                        body: source_argument,
+                    },
+                )
+                | (
+                    Function {
+                        argument_type: function,
+                        return_type: argument,
+                        ..
+                    },
+                    Function {
+                        argument_type: source_function,
+                        return_type: source_argument,
+                        ..
+                    },
+                )
+                | (
+                    Annotation {
+                        expression: function,
+                        ty: argument,
+                        ..
+                    },
+                    Annotation {
+                        expression: source_function,
+                        ty: source_argument,
+                        ..
+                    },
+                ) => {
+                    **function = source_function.clone_node(&alloc);
+                    **argument = source_argument.clone_node(&alloc);
+                    stack.push((function, source_function));
+                    stack.push((argument, source_argument));
+                }
+                _ => {}
+            }
+        }
+
+        root
+    }
+}
+
+impl<T: Clone, U: Primitives<T> + Clone, A: Allocator<T, U>> Term<T, U, A> {
+    // Copies a single node, leaving its children as placeholders to be filled in by the caller.
+    fn clone_node(&self, alloc: &A) -> Self {
+        use Term::*;
+
         match self {
-            Variable(index) => Term::Variable(index.clone()),
-            Lambda { body, erased } => Term::Lambda {
-                body: alloc.copy_boxed(body),
+            Variable(index) => Variable(*index),
+            Lambda { erased, .. } => Lambda {
+                body: alloc.alloc(Universe),
                 erased: *erased,
             },
-            Apply {
-                function,
-                argument,
-                erased,
-            } => Term::Apply {
-                function: alloc.copy_boxed(function),
-                argument: alloc.copy_boxed(argument),
+            Apply { erased, .. } => Apply {
+                function: alloc.alloc(Universe),
+                argument: alloc.alloc(Universe),
                 erased: *erased,
             },
-            Put(term) => Term::Put(alloc.copy_boxed(term)),
-            Duplicate { expression, body } => Term::Duplicate {
-                expression: alloc.copy_boxed(expression),
-                body: alloc.copy_boxed(body),
+            Put(_) => Put(alloc.alloc(Universe)),
+            Duplicate { .. } => Duplicate {
+                expression: alloc.alloc(Universe),
+                body: alloc.alloc(Universe),
             },
-            Reference(reference) => Term::Reference(reference.clone()),
-            Primitive(prim) => Term::Primitive(prim.clone()),
-            Term::Universe => Term::Universe,
-            Term::Function {
-                argument_type,
-                return_type,
-                erased,
-            } => Term::Function {
+            Reference(reference) => Reference(reference.clone()),
+            Primitive(prim) => Primitive(prim.clone()),
+            Universe => Universe,
+            Function { erased, .. } => Function {
                 erased: *erased,
-                argument_type: alloc.copy_boxed(argument_type),
-                return_type: alloc.copy_boxed(return_type),
+                argument_type: alloc.alloc(Universe),
+                return_type: alloc.alloc(Universe),
             },
-            Term::Annotation {
-                checked,
-                expression,
-                ty,
-            } => Term::Annotation {
+            Annotation { checked, .. } => Annotation {
                 checked: *checked,
-                expression: alloc.copy_boxed(expression),
-                ty: alloc.copy_boxed(ty),
+                expression: alloc.alloc(Universe),
+                ty: alloc.alloc(Universe),
             },
-            Term::Wrap(term) => Term::Wrap(alloc.copy_boxed(term)),
+            Wrap(_) => Wrap(alloc.alloc(Universe)),
+        }
+    }
+}
+
+// A term waiting to be mapped, or one whose children have been mapped and are waiting to be
+// reassembled into it.
+pub(crate) enum Frame<T> {
+    Map(T),
+    Rebuild(T),
+}
+
+pub(crate) enum Leaf<T, U> {
+    Reference(T),
+    Primitive(U),
+}
+
+impl<T, U: Primitives<T>, A: Allocator<T, U>> Term<T, U, A> {
+    pub(crate) fn take(&mut self) -> Self {
+        replace(self, Term::Universe)
+    }
+
+    // Terms free their children through `Drop` without recursing, which rules out moving a
+    // payload out of one, so a leaf's payload is cloned instead.
+    pub(crate) fn into_leaf(self) -> Result<Leaf<T, U>, Self>
+    where
+        T: Clone,
+        U: Clone,
+    {
+        match &self {
+            Term::Reference(reference) => Ok(Leaf::Reference(reference.clone())),
+            Term::Primitive(primitive) => Ok(Leaf::Primitive(primitive.clone())),
+            _ => Err(self),
+        }
+    }
+
+    fn take_children(&mut self, stack: &mut Vec<Self>) {
+        use Term::*;
+
+        let mut push = |term: &mut Self| {
+            if term.has_children() {
+                stack.push(term.take());
+            }
+        };
+
+        match self {
+            Variable(_) | Reference(_) | Primitive(_) | Universe => {}
+            Lambda { body, .. } => push(body),
+            Put(term) | Wrap(term) => push(term),
+            Apply {
+                function, argument, ..
+            } => {
+                push(function);
+                push(argument);
+            }
+            Duplicate { expression, body } => {
+                push(expression);
+                push(body);
+            }
+            Function {
+                argument_type,
+                return_type,
+                ..
+            } => {
+                push(argument_type);
+                push(return_type);
+            }
+            Annotation { expression, ty, .. } => {
+                push(expression);
+                push(ty);
+            }
+        }
+    }
+
+    // Takes every child, in the order the fields are declared, leaving placeholders behind.
+    pub(crate) fn take_children_in_order(&mut self) -> Vec<Self> {
+        use Term::*;
+
+        match self {
+            Variable(_) | Reference(_) | Primitive(_) | Universe => vec![],
+            Lambda { body: term, .. } | Put(term) | Wrap(term) => vec![term.take()],
+            Apply {
+                function: first,
+                argument: second,
+                ..
+            }
+            | Duplicate {
+                expression: first,
+                body: second,
+            }
+            | Function {
+                argument_type: first,
+                return_type: second,
+                ..
+            }
+            | Annotation {
+                expression: first,
+                ty: second,
+                ..
+            } => vec![first.take(), second.take()],
+        }
+    }
+
+    fn has_children(&self) -> bool {
+        !matches!(
+            self,
+            Term::Variable(_) | Term::Reference(_) | Term::Primitive(_) | Term::Universe
+        )
+    }
+}
+
+impl<T, U: Primitives<T>, A: Allocator<T, U>> Drop for Term<T, U, A> {
+    fn drop(&mut self) {
+        let mut stack = vec![];
+        self.take_children(&mut stack);
+        while let Some(mut term) = stack.pop() {
+            term.take_children(&mut stack);
         }
     }
 }
@@ -0,0 +1,200 @@
+use super::{
+    alloc::{Allocator, Zero},
+    Index, Path, Primitives, StratificationError, Term,
+};
+
+// How a variable in scope of a candidate box was bound.
+#[derive(Clone, Copy)]
+enum Binder {
+    Lambda,
+    // A duplication that isn't inside a box at `depth`.
+    Duplicate { depth: usize },
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+    // Reads this term as an ordinary lambda term and inserts the duplications and boxes that
+    // stratify it. A lambda whose variable is used more than once, or inside a box, duplicates
+    // it instead, and the uses of every duplicated variable are boxed together with as much of
+    // their surroundings as can go in a box: no lambda variable bound outside it, and not applied
+    // as a function. Boxes and duplications already in the term are kept, and erasure gives the
+    // original term back.
+    //
+    // When that leaves the term unstratified, the error explains what's left: an erased variable
+    // used computationally, a use that would need two boxes, or one that can't be boxed at all
+    // because it's applied to a lambda variable.
+    pub fn infer_boxes_in(&mut self, alloc: &A) -> Result<(), StratificationError<T>>
+    where
+        T: Clone,
+    {
+        self.insert_boxes(&mut vec![], 0, alloc);
+        self.is_stratified()
+    }
+
+    pub fn infer_boxes(&mut self) -> Result<(), StratificationError<T>>
+    where
+        T: Clone,
+        A: Zero,
+    {
+        let alloc = A::zero();
+
+        self.infer_boxes_in(&alloc)
+    }
+
+    fn insert_boxes(&mut self, binders: &mut Vec<Binder>, depth: usize, alloc: &A) {
+        use Term::*;
+
+        if let Lambda {
+            body,
+            erased: false,
+        } = self
+        {
+            if body.uses() > 1
+                || body
+                    .find_occurrence_of(Index::top(), |depth| depth != 0, Path::root())
+                    .is_some()
+            {
+                // Under the new duplication the duplicated variable takes the place of the
+                // lambda's, which moves up past it along with everything bound further out.
+                body.shift(Index::top().child());
+                let duplicated = body.take();
+                **body = Duplicate {
+                    expression: alloc.alloc(Variable(Index::top())),
+                    body: alloc.alloc(duplicated),
+                };
+            }
+        }
+
+        match self {
+            Lambda { body, .. } => {
+                binders.push(Binder::Lambda);
+                body.insert_boxes(binders, depth, alloc);
+                binders.pop();
+            }
+            Apply {
+                function,
+                argument,
+                erased,
+            } => {
+                function.insert_boxes(binders, depth, alloc);
+                if !*erased {
+                    argument.insert_boxes(binders, depth, alloc);
+                }
+            }
+            Put(term) => term.insert_boxes(binders, depth + 1, alloc),
+            Duplicate { expression, body } => {
+                expression.insert_boxes(binders, depth, alloc);
+                binders.push(Binder::Duplicate { depth });
+                body.box_uses(Index::top(), false, binders, depth, alloc);
+                body.insert_boxes(binders, depth, alloc);
+                binders.pop();
+            }
+            Wrap(term) => term.insert_boxes(binders, depth, alloc),
+            Annotation { expression, .. } => expression.insert_boxes(binders, depth, alloc),
+            Variable(_) | Reference(_) | Primitive(_) | Function { .. } | Universe => {}
+        }
+    }
+
+    // Boxes the largest subterms that hold the unboxed uses of `variable`, which is duplicated at
+    // box depth `depth`.
+    fn box_uses(
+        &mut self,
+        variable: Index,
+        applied: bool,
+        binders: &mut Vec<Binder>,
+        depth: usize,
+        alloc: &A,
+    ) {
+        use Term::*;
+
+        if self
+            .find_occurrence_of(variable, |depth| depth == 0, Path::root())
+            .is_none()
+        {
+            return;
+        }
+
+        if !applied && self.fits_in_box(binders, depth) {
+            let contents = self.take();
+            *self = Put(alloc.alloc(contents));
+            return;
+        }
+
+        match self {
+            Lambda { body, .. } => {
+                binders.push(Binder::Lambda);
+                body.box_uses(variable.child(), false, binders, depth, alloc);
+                binders.pop();
+            }
+            Apply {
+                function,
+                argument,
+                erased,
+            } => {
+                function.box_uses(variable, true, binders, depth, alloc);
+                if !*erased {
+                    argument.box_uses(variable, false, binders, depth, alloc);
+                }
+            }
+            Duplicate { expression, body } => {
+                expression.box_uses(variable, false, binders, depth, alloc);
+                binders.push(Binder::Duplicate { depth });
+                body.box_uses(variable.child(), false, binders, depth, alloc);
+                binders.pop();
+            }
+            Wrap(term) => term.box_uses(variable, false, binders, depth, alloc),
+            Annotation { expression, .. } => {
+                expression.box_uses(variable, applied, binders, depth, alloc)
+            }
+            Put(_) | Variable(_) | Reference(_) | Primitive(_) | Function { .. } | Universe => {}
+        }
+    }
+
+    // Whether boxing this term keeps every variable it uses, but doesn't bind, stratified: each
+    // must be duplicated at `depth` and used outside of any box.
+    fn fits_in_box(&self, binders: &[Binder], depth: usize) -> bool {
+        fn fits_helper<T, V: Primitives<T>, A: Allocator<T, V>>(
+            term: &Term<T, V, A>,
+            binders: &[Binder],
+            depth: usize,
+            bound: usize,
+            nestings: usize,
+        ) -> bool {
+            use Term::*;
+
+            match term {
+                Variable(index) => {
+                    let index = index.value();
+                    index < bound
+                        || match binders.len().checked_sub(index - bound + 1) {
+                            Some(binder) => matches!(
+                                binders[binder],
+                                Binder::Duplicate { depth: bound_at } if bound_at == depth && nestings == 0
+                            ),
+                            None => false,
+                        }
+                }
+                Lambda { body, .. } => fits_helper(body, binders, depth, bound + 1, nestings),
+                Apply {
+                    function,
+                    argument,
+                    erased,
+                } => {
+                    fits_helper(function, binders, depth, bound, nestings)
+                        && (*erased || fits_helper(argument, binders, depth, bound, nestings))
+                }
+                Put(term) => fits_helper(term, binders, depth, bound, nestings + 1),
+                Duplicate { expression, body } => {
+                    fits_helper(expression, binders, depth, bound, nestings)
+                        && fits_helper(body, binders, depth, bound + 1, nestings)
+                }
+                Wrap(term) => fits_helper(term, binders, depth, bound, nestings),
+                Annotation { expression, .. } => {
+                    fits_helper(expression, binders, depth, bound, nestings)
+                }
+                Reference(_) | Primitive(_) | Function { .. } | Universe => true,
+            }
+        }
+
+        fits_helper(self, binders, depth, 0, 0)
+    }
+}
@@ -0,0 +1,439 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::TermHash;
+
+pub trait EqualityCache {
+    fn register(&mut self, a: TermHash, b: TermHash, checks: bool);
+    fn check(&self, a: TermHash, b: TermHash) -> Option<bool>;
+}
+
+#[derive(Copy, Clone)]
+pub struct NullCache;
+
+pub struct MapCache {
+    data: HashMap<(TermHash, TermHash), bool>,
+}
+
+impl MapCache {
+    pub fn new() -> Self {
+        Self {
+            data: HashMap::new(),
+        }
+    }
+}
+
+impl EqualityCache for MapCache {
+    fn register(&mut self, a: TermHash, b: TermHash, checks: bool) {
+        self.data.insert((a, b), checks);
+    }
+
+    fn check(&self, a: TermHash, b: TermHash) -> Option<bool> {
+        self.data.get(&(a, b)).cloned()
+    }
+}
+
+impl EqualityCache for NullCache {
+    fn register(&mut self, _: TermHash, _: TermHash, _: bool) {}
+
+    fn check(&self, _: TermHash, _: TermHash) -> Option<bool> {
+        None
+    }
+}
+
+impl<'a, T: EqualityCache> EqualityCache for &'a mut T {
+    fn register(&mut self, a: TermHash, b: TermHash, checks: bool) {
+        T::register(self, a, b, checks)
+    }
+
+    fn check(&self, a: TermHash, b: TermHash) -> Option<bool> {
+        T::check(self, a, b)
+    }
+}
+
+const FILE_CACHE_VERSION: u32 = 2;
+
+// Equalities only hold for the definitions they were checked against, so the file records their
+// fingerprint.
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    fingerprint: TermHash,
+    entries: Vec<(TermHash, TermHash, bool)>,
+}
+
+pub struct FileCache {
+    path: PathBuf,
+    fingerprint: TermHash,
+    cache: MapCache,
+    dirty: bool,
+}
+
+impl FileCache {
+    // Loads the equalities saved for the definitions with `fingerprint`. A file saved for other
+    // definitions, or by another version, is discarded and replaced on the next save.
+    pub fn load<P: Into<PathBuf>>(path: P, fingerprint: TermHash) -> io::Result<Self> {
+        let path = path.into();
+        let mut cache = MapCache::new();
+        let mut dirty = false;
+
+        match File::open(&path) {
+            Ok(file) => match serde_json::from_reader::<_, CacheFile>(BufReader::new(file)) {
+                Ok(file)
+                    if file.version == FILE_CACHE_VERSION && file.fingerprint == fingerprint =>
+                {
+                    for (a, b, checks) in file.entries {
+                        cache.register(a, b, checks);
+                    }
+                }
+                _ => dirty = true,
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => Err(e)?,
+        }
+
+        Ok(FileCache {
+            path,
+            fingerprint,
+            cache,
+            dirty,
+        })
+    }
+
+    pub fn save(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let file = CacheFile {
+            version: FILE_CACHE_VERSION,
+            fingerprint: self.fingerprint,
+            entries: self
+                .cache
+                .data
+                .iter()
+                .map(|(&(a, b), &checks)| (a, b, checks))
+                .collect(),
+        };
+
+        // Each save writes its own file next to the cache, so concurrent runs saving the same
+        // cache don't write over each other before the rename, and the last rename wins.
+        static SAVES: AtomicUsize = AtomicUsize::new(0);
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            SAVES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temporary = PathBuf::from(temporary);
+
+        let written = (|| {
+            let mut writer = BufWriter::new(File::create(&temporary)?);
+            serde_json::to_writer(&mut writer, &file)?;
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+            fs::rename(&temporary, &self.path)
+        })();
+        if written.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+        written?;
+        self.dirty = false;
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.data.is_empty()
+    }
+}
+
+impl EqualityCache for FileCache {
+    fn register(&mut self, a: TermHash, b: TermHash, checks: bool) {
+        if self.cache.check(a, b) != Some(checks) {
+            self.cache.register(a, b, checks);
+            self.dirty = true;
+        }
+    }
+
+    fn check(&self, a: TermHash, b: TermHash) -> Option<bool> {
+        self.cache.check(a, b)
+    }
+}
+
+struct LruState {
+    entries: HashMap<(TermHash, TermHash), (bool, u64)>,
+    recency: BTreeMap<u64, (TermHash, TermHash)>,
+    tick: u64,
+}
+
+impl LruState {
+    fn touch(&mut self, key: (TermHash, TermHash)) -> Option<bool> {
+        let tick = self.tick;
+        let (checks, last_used) = self.entries.get_mut(&key)?;
+        self.recency.remove(last_used);
+        *last_used = tick;
+        self.recency.insert(tick, key);
+        self.tick += 1;
+        Some(*checks)
+    }
+}
+
+pub struct LruCache {
+    capacity: usize,
+    state: RefCell<LruState>,
+}
+
+impl LruCache {
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity,
+            state: RefCell::new(LruState {
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                tick: 0,
+            }),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.borrow().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.borrow().entries.is_empty()
+    }
+}
+
+impl EqualityCache for LruCache {
+    fn register(&mut self, a: TermHash, b: TermHash, checks: bool) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let state = self.state.get_mut();
+
+        if state.touch((a, b)).is_some() {
+            state.entries.get_mut(&(a, b)).unwrap().0 = checks;
+            return;
+        }
+
+        if state.entries.len() == self.capacity {
+            let (&oldest, _) = state.recency.iter().next().unwrap();
+            let key = state.recency.remove(&oldest).unwrap();
+            state.entries.remove(&key);
+        }
+
+        let tick = state.tick;
+        state.entries.insert((a, b), (checks, tick));
+        state.recency.insert(tick, (a, b));
+        state.tick += 1;
+    }
+
+    fn check(&self, a: TermHash, b: TermHash) -> Option<bool> {
+        self.state.borrow_mut().touch((a, b))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStatistics {
+    pub hits: usize,
+    pub misses: usize,
+    pub registrations: usize,
+}
+
+impl CacheStatistics {
+    pub fn lookups(&self) -> usize {
+        self.hits + self.misses
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups() == 0 {
+            0.
+        } else {
+            self.hits as f64 / self.lookups() as f64
+        }
+    }
+}
+
+impl Display for CacheStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses ({:.1}% hit rate), {} registrations",
+            self.hits,
+            self.misses,
+            self.hit_rate() * 100.,
+            self.registrations
+        )
+    }
+}
+
+pub struct InstrumentedCache<C> {
+    inner: C,
+    hits: Cell<usize>,
+    misses: Cell<usize>,
+    registrations: usize,
+}
+
+impl<C> InstrumentedCache<C> {
+    pub fn new(inner: C) -> Self {
+        InstrumentedCache {
+            inner,
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+            registrations: 0,
+        }
+    }
+
+    pub fn statistics(&self) -> CacheStatistics {
+        CacheStatistics {
+            hits: self.hits.get(),
+            misses: self.misses.get(),
+            registrations: self.registrations,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.hits.set(0);
+        self.misses.set(0);
+        self.registrations = 0;
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C: EqualityCache> EqualityCache for InstrumentedCache<C> {
+    fn register(&mut self, a: TermHash, b: TermHash, checks: bool) {
+        self.registrations += 1;
+        self.inner.register(a, b, checks)
+    }
+
+    fn check(&self, a: TermHash, b: TermHash) -> Option<bool> {
+        let result = self.inner.check(a, b);
+        if result.is_some() {
+            self.hits.set(self.hits.get() + 1);
+        } else {
+            self.misses.set(self.misses.get() + 1);
+        }
+        result
+    }
+}
+
+pub trait ConcurrentEqualityCache: Send + Sync {
+    fn register_shared(&self, a: TermHash, b: TermHash, checks: bool);
+    fn check_shared(&self, a: TermHash, b: TermHash) -> Option<bool>;
+}
+
+impl<T: ConcurrentEqualityCache> EqualityCache for &T {
+    fn register(&mut self, a: TermHash, b: TermHash, checks: bool) {
+        T::register_shared(self, a, b, checks)
+    }
+
+    fn check(&self, a: TermHash, b: TermHash) -> Option<bool> {
+        T::check_shared(self, a, b)
+    }
+}
+
+impl<T: ConcurrentEqualityCache> EqualityCache for Arc<T> {
+    fn register(&mut self, a: TermHash, b: TermHash, checks: bool) {
+        T::register_shared(self, a, b, checks)
+    }
+
+    fn check(&self, a: TermHash, b: TermHash) -> Option<bool> {
+        T::check_shared(self, a, b)
+    }
+}
+
+const DEFAULT_SHARDS: usize = 16;
+
+pub struct SharedCache {
+    shards: Vec<RwLock<HashMap<(TermHash, TermHash), bool>>>,
+}
+
+impl SharedCache {
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    pub fn with_shards(shards: usize) -> Self {
+        SharedCache {
+            shards: (0..shards.max(1))
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+        }
+    }
+
+    fn shard(&self, a: TermHash, b: TermHash) -> &RwLock<HashMap<(TermHash, TermHash), bool>> {
+        let index = (a.0 ^ b.0.rotate_left(64)) % self.shards.len() as u128;
+        &self.shards[index as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for SharedCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConcurrentEqualityCache for SharedCache {
+    fn register_shared(&self, a: TermHash, b: TermHash, checks: bool) {
+        self.shard(a, b).write().unwrap().insert((a, b), checks);
+    }
+
+    fn check_shared(&self, a: TermHash, b: TermHash) -> Option<bool> {
+        self.shard(a, b).read().unwrap().get(&(a, b)).cloned()
+    }
+}
+
+impl EqualityCache for SharedCache {
+    fn register(&mut self, a: TermHash, b: TermHash, checks: bool) {
+        self.register_shared(a, b, checks)
+    }
+
+    fn check(&self, a: TermHash, b: TermHash) -> Option<bool> {
+        self.check_shared(a, b)
+    }
+}
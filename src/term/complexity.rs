@@ -0,0 +1,138 @@
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+};
+
+use super::{alloc::Allocator, Definitions, Primitives, Stratified, Term};
+
+// The shape of the net a stratified term builds: how much of it sits at each box depth, with
+// every reference inlined where it's used, as `build_net_in` does.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Complexity {
+    // The number of lambdas, applications, variable uses and primitives at each box depth, from
+    // the outermost level in.
+    pub sizes: Vec<usize>,
+}
+
+impl Complexity {
+    pub fn depth(&self) -> usize {
+        self.sizes.len().saturating_sub(1)
+    }
+
+    pub fn size(&self) -> usize {
+        self.sizes.iter().sum()
+    }
+
+    // Stratification bounds normalization by a tower of exponentials whose height grows with the
+    // depth: a net of size s and depth d takes at most 2_2d(s) rewrites, where 2_0(s) = s and
+    // 2_k+1(s) = 2^2_k(s), and never grows past that size either (Danos and Joinet, "Linear logic
+    // and elementary time"). `None` once that no longer fits in a `u64`.
+    pub fn bound(&self) -> Option<u64> {
+        let mut bound = self.size() as u64;
+        for _ in 0..2 * self.depth() {
+            bound = 1u64.checked_shl(u32::try_from(bound).ok()?)?;
+        }
+        Some(bound)
+    }
+
+    fn add(&mut self, other: &Complexity, depth: usize) {
+        for (level, size) in other.sizes.iter().enumerate() {
+            self.add_at(depth + level, *size);
+        }
+    }
+
+    fn add_at(&mut self, depth: usize, size: usize) {
+        if self.sizes.len() <= depth {
+            self.sizes.resize(depth + 1, 0);
+        }
+        self.sizes[depth] += size;
+    }
+}
+
+impl Display for Complexity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "size {} at depth {}", self.size(), self.depth())?;
+        for (depth, size) in self.sizes.iter().enumerate() {
+            writeln!(f, "    depth {}: {}", depth, size)?;
+        }
+        write!(f, "rewrites and net size at most ")?;
+        match self.bound() {
+            Some(bound) => write!(f, "{}", bound),
+            None => write!(f, "{}{}", "2^".repeat(2 * self.depth()), self.size()),
+        }
+    }
+}
+
+impl<'a, 'b, T, U: Definitions<T, V, A>, V: Primitives<T>, A: Allocator<T, V>>
+    Stratified<'a, 'b, T, U, V, A>
+{
+    pub fn complexity(&self) -> Complexity
+    where
+        T: PartialEq + Clone,
+    {
+        let mut complexity = Complexity::default();
+        self.0
+            .add_complexity_in(self.1, 0, &mut complexity, &mut vec![]);
+        complexity
+    }
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+    // Adds the size of this term at each box depth, from `depth` in, to `complexity`. Each
+    // definition is measured once and its measure reused wherever it's referenced.
+    fn add_complexity_in<D: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &self,
+        definitions: &D,
+        depth: usize,
+        complexity: &mut Complexity,
+        measured: &mut Vec<(T, Complexity)>,
+    ) where
+        T: PartialEq + Clone,
+    {
+        use Term::*;
+
+        match self {
+            Variable(_) | Primitive(_) => complexity.add_at(depth, 1),
+            Lambda { body, erased } => {
+                if !*erased {
+                    complexity.add_at(depth, 1);
+                }
+                body.add_complexity_in(definitions, depth, complexity, measured);
+            }
+            Apply {
+                function,
+                argument,
+                erased,
+            } => {
+                function.add_complexity_in(definitions, depth, complexity, measured);
+                if !*erased {
+                    complexity.add_at(depth, 1);
+                    argument.add_complexity_in(definitions, depth, complexity, measured);
+                }
+            }
+            Put(term) => term.add_complexity_in(definitions, depth + 1, complexity, measured),
+            Duplicate { expression, body } => {
+                expression.add_complexity_in(definitions, depth, complexity, measured);
+                body.add_complexity_in(definitions, depth, complexity, measured);
+            }
+            Annotation { expression, .. } => {
+                expression.add_complexity_in(definitions, depth, complexity, measured)
+            }
+            Reference(name) => {
+                if let Some((_, definition)) = measured.iter().find(|(other, _)| other == name) {
+                    complexity.add(definition, depth);
+                    return;
+                }
+                // `Stratified` has checked that references are defined and not recursive.
+                let mut definition = Complexity::default();
+                if let Some(term) = definitions.get(name) {
+                    term.as_ref()
+                        .add_complexity_in(definitions, 0, &mut definition, measured);
+                }
+                complexity.add(&definition, depth);
+                measured.push((name.clone(), definition));
+            }
+            Wrap(_) | Function { .. } | Universe => {}
+        }
+    }
+}
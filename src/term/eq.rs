@@ -1,73 +1,94 @@
-use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
-    hash::{Hash, Hasher},
-};
+use std::hash::Hash;
 
 use derivative::Derivative;
 
 use crate::analysis::Empty;
 
 use super::{
-    alloc::Reallocate, Allocator, Definitions, IntoInner, NormalizationError, Primitives, Show,
-    Term, Zero,
+    alloc::{Reallocate, System},
+    Allocator, Definitions, Direction, EqualityCache, Index, None, NormalizationError, Normalizer,
+    NullCache, Path, Primitives, Show, Term, Zero,
 };
 
 use bumpalo::{boxed::Box as BumpBox, Bump};
 
-#[derive(Derivative)]
-#[derivative(Debug(bound = "T: Show, V: Show"))]
-enum EqualityTree<'a, T, V: Primitives<T>, A: Allocator<T, V>> {
-    Equal(Term<T, V, A>, Term<T, V, A>),
-    Or(BumpBox<'a, Option<(EqualityTree<'a, T, V, A>, EqualityTree<'a, T, V, A>)>>),
-    And(BumpBox<'a, Option<(EqualityTree<'a, T, V, A>, EqualityTree<'a, T, V, A>)>>),
-    Leaf(bool),
+#[derive(Debug)]
+struct PathNode<'a> {
+    parent: Option<&'a PathNode<'a>>,
+    direction: Direction,
 }
 
-pub trait EqualityCache {
-    fn register(&mut self, a: u64, b: u64, checks: bool);
-    fn check(&self, a: u64, b: u64) -> Option<bool>;
-}
-
-#[derive(Copy, Clone)]
-pub struct NullCache;
+type Mismatched<T, V, A> = Result<Option<Mismatch<T, V, A>>, NormalizationError<T, V, A>>;
 
-pub struct MapCache {
-    data: HashMap<(u64, u64), bool>,
+#[derive(Derivative)]
+#[derivative(Debug(bound = "T: Show, V: Show"))]
+pub struct Mismatch<T, V: Primitives<T> = None, A: Allocator<T, V> = System> {
+    pub path: Path,
+    // False when `path` exists in both terms as given. True when reaching it took normalization,
+    // so it addresses their weak head normal forms instead and may not exist in either term.
+    pub normalized: bool,
+    pub left: Term<T, V, A>,
+    pub right: Term<T, V, A>,
 }
 
-impl MapCache {
-    pub fn new() -> Self {
-        Self {
-            data: HashMap::new(),
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Mismatch<T, V, A> {
+    fn new(
+        mut node: Option<&PathNode<'_>>,
+        normalized: bool,
+        left: Term<T, V, A>,
+        right: Term<T, V, A>,
+    ) -> Self {
+        let mut directions = vec![];
+        while let Some(PathNode { parent, direction }) = node {
+            directions.push(*direction);
+            node = *parent;
         }
-    }
-}
+        directions.reverse();
 
-impl EqualityCache for MapCache {
-    fn register(&mut self, a: u64, b: u64, checks: bool) {
-        self.data.insert((a, b), checks);
+        Mismatch {
+            path: directions.into(),
+            normalized,
+            left,
+            right,
+        }
     }
+}
 
-    fn check(&self, a: u64, b: u64) -> Option<bool> {
-        self.data.get(&(a, b)).cloned()
-    }
+// The outcome of one comparison. An inequality found in the cache comes without a witness.
+#[derive(Derivative)]
+#[derivative(Debug(bound = "T: Show, V: Show"))]
+pub enum Equivalence<T, V: Primitives<T> = None, A: Allocator<T, V> = System> {
+    Equivalent,
+    Unequal(Option<Mismatch<T, V, A>>),
 }
 
-impl EqualityCache for NullCache {
-    fn register(&mut self, _: u64, _: u64, _: bool) {}
+#[derive(Derivative)]
+#[derivative(Debug(bound = "T: Show, V: Show"))]
+enum EqualityTree<'a, T, V: Primitives<T>, A: Allocator<T, V>> {
+    // The bool records whether the path to these terms already passes through normalization.
+    Equal(Term<T, V, A>, Term<T, V, A>, Option<&'a PathNode<'a>>, bool),
+    Or(BumpBox<'a, Option<(EqualityTree<'a, T, V, A>, EqualityTree<'a, T, V, A>)>>),
+    And(BumpBox<'a, Option<(EqualityTree<'a, T, V, A>, EqualityTree<'a, T, V, A>)>>),
+    Leaf(bool),
+    Mismatch(Mismatch<T, V, A>),
+}
 
-    fn check(&self, _: u64, _: u64) -> Option<bool> {
-        None
+impl<'a, T, V: Primitives<T>, A: Allocator<T, V>> EqualityTree<'a, T, V, A> {
+    fn is_false(&self) -> bool {
+        matches!(self, EqualityTree::Leaf(false) | EqualityTree::Mismatch(_))
     }
 }
 
-impl<'a, T: EqualityCache> EqualityCache for &'a mut T {
-    fn register(&mut self, a: u64, b: u64, checks: bool) {
-        T::register(self, a, b, checks)
-    }
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+    fn is_erasure_mismatch(&self, other: &Self) -> bool {
+        use Term::*;
 
-    fn check(&self, a: u64, b: u64) -> Option<bool> {
-        T::check(self, a, b)
+        match (self, other) {
+            (Lambda { erased: a, .. }, Lambda { erased: b, .. })
+            | (Apply { erased: a, .. }, Apply { erased: b, .. })
+            | (Function { erased: a, .. }, Function { erased: b, .. }) => a != b,
+            _ => false,
+        }
     }
 }
 
@@ -78,82 +99,116 @@ impl<T: PartialEq + Show + Clone, V: Show + Clone + Primitives<T>, A: Allocator<
     where
         V: PartialEq,
     {
-        match (self, other) {
-            (Term::Variable(a), Term::Variable(b)) => a == b,
-            (
-                Term::Lambda { body, erased },
-                Term::Lambda {
-                    body: b_body,
-                    erased: b_erased,
-                },
-            ) => body.equals(b_body) && erased == b_erased,
-            (
-                Term::Apply {
-                    function,
-                    argument,
-                    erased,
-                },
-                Term::Apply {
-                    function: b_function,
-                    argument: b_argument,
-                    erased: b_erased,
-                },
-            ) => function.equals(b_function) && argument.equals(b_argument) && erased == b_erased,
-            (Term::Put(a), Term::Put(b)) => a.equals(b),
-            (
-                Term::Duplicate { expression, body },
-                Term::Duplicate {
-                    expression: b_expression,
-                    body: b_body,
-                },
-            ) => expression.equals(b_expression) && body.equals(b_body),
-            (Term::Reference(a), Term::Reference(b)) => a == b,
-            (Term::Primitive(a), Term::Primitive(b)) => a == b,
-            (Term::Universe, Term::Universe) => true,
-            (
-                Term::Function {
-                    argument_type,
-                    return_type,
-                    erased,
-                },
-                Term::Function {
-                    argument_type: b_argument_type,
-                    return_type: b_return_type,
-                    erased: b_erased,
-                },
-            ) => {
-                argument_type.equals(b_argument_type)
-                    && return_type.equals(b_return_type)
-                    && erased == b_erased
+        use Term::*;
+
+        let mut stack = vec![(self, other)];
+
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (Variable(a), Variable(b)) => {
+                    if a != b {
+                        return false;
+                    }
+                }
+                (Reference(a), Reference(b)) => {
+                    if a != b {
+                        return false;
+                    }
+                }
+                (Primitive(a), Primitive(b)) => {
+                    if a != b {
+                        return false;
+                    }
+                }
+                (Universe, Universe) => {}
+                (Put(a), Put(b)) | (Wrap(a), Wrap(b)) => stack.push((a, b)),
+                (
+                    Lambda { body, erased },
+                    Lambda {
+                        body: b_body,
+                        erased: b_erased,
+                    },
+                ) => {
+                    if erased != b_erased {
+                        return false;
+                    }
+                    stack.push((body, b_body));
+                }
+                (
+                    Apply {
+                        function: a,
+                        argument: b,
+                        erased,
+                    },
+                    Apply {
+                        function: b_a,
+                        argument: b_b,
+                        erased: b_erased,
+                    },
+                )
+                | (
+                    Function {
+                        argument_type: a,
+                        return_type: b,
+                        erased,
+                    },
+                    Function {
+                        argument_type: b_a,
+                        return_type: b_b,
+                        erased: b_erased,
+                    },
+                )
+                | (
+                    Annotation {
+                        expression: a,
+                        ty: b,
+                        checked: erased,
+                    },
+                    Annotation {
+                        expression: b_a,
+                        ty: b_b,
+                        checked: b_erased,
+                    },
+                ) => {
+                    if erased != b_erased {
+                        return false;
+                    }
+                    stack.push((b, b_b));
+                    stack.push((a, b_a));
+                }
+                (
+                    Duplicate {
+                        expression: a,
+                        body: b,
+                    },
+                    Duplicate {
+                        expression: b_a,
+                        body: b_b,
+                    },
+                ) => {
+                    stack.push((b, b_b));
+                    stack.push((a, b_a));
+                }
+                _ => return false,
             }
-            (
-                Term::Annotation {
-                    checked,
-                    expression,
-                    ty,
-                },
-                Term::Annotation {
-                    checked: b_checked,
-                    expression: b_expression,
-                    ty: b_ty,
-                },
-            ) => expression.equals(b_expression) && ty.equals(b_ty) && checked == b_checked,
-            (Term::Wrap(a), Term::Wrap(b)) => a.equals(b),
-            _ => false,
         }
+
+        true
     }
 
-    pub fn equivalent_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+    // Compares two terms and, when they differ, returns where from the same run. Callers that want
+    // the witness should use this rather than `equivalent_in` followed by `mismatch_in`.
+    pub fn equivalence_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
         &self,
         other: &Self,
         definitions: &U,
         alloc: &A,
         cache: &mut impl EqualityCache,
-    ) -> Result<bool, NormalizationError>
+    ) -> Result<Equivalence<T, V, A>, NormalizationError<T, V, A>>
     where
         A: Reallocate<T, V, B>,
         T: Hash,
-        V: Hash,
+        V: Hash + PartialEq,
     {
         use Term::*;
 
@@ -161,7 +216,7 @@ impl<T: PartialEq + Show + Clone, V: Show + Clone + Primitives<T>, A: Allocator<
             'b,
             U: Definitions<T, V, B>,
             T: Show + PartialEq + Clone + Hash,
-            V: Show + Primitives<T> + Clone + Hash,
+            V: Show + Primitives<T> + Clone + Hash + PartialEq,
             A: Allocator<T, V> + Reallocate<T, V, B>,
             B: Allocator<T, V>,
         >(
@@ -170,80 +225,61 @@ impl<T: PartialEq + Show + Clone, V: Show + Clone + Primitives<T>, A: Allocator<
             alloc: &A,
             o_alloc: &'b Bump,
             cache: &mut impl EqualityCache,
-        ) -> Result<EqualityTree<'b, T, V, A>, NormalizationError> {
+        ) -> Result<EqualityTree<'b, T, V, A>, NormalizationError<T, V, A>> {
             Ok(match tree {
-                this @ EqualityTree::Leaf(_) => this,
-                EqualityTree::And(mut data) => {
-                    let (a, b) = data.as_ref().as_ref().unwrap();
-                    match (a, b) {
-                        (EqualityTree::Leaf(false), _) | (_, EqualityTree::Leaf(false)) => {
-                            EqualityTree::Leaf(false)
-                        }
-                        (EqualityTree::Leaf(true), EqualityTree::Leaf(true)) => {
-                            EqualityTree::Leaf(true)
-                        }
-                        _ => EqualityTree::And(BumpBox::new_in(
-                            Some({
-                                let data = data.take().unwrap();
-                                (
-                                    equivalence_helper(
-                                        data.0,
-                                        definitions,
-                                        alloc,
-                                        o_alloc,
-                                        &mut *cache,
-                                    )?,
-                                    equivalence_helper(data.1, definitions, alloc, o_alloc, cache)?,
-                                )
-                            }),
-                            o_alloc,
-                        )),
+                this @ EqualityTree::Leaf(_) | this @ EqualityTree::Mismatch(_) => this,
+                EqualityTree::And(mut data) => match data.take().unwrap() {
+                    (a, _) if a.is_false() => a,
+                    (_, b) if b.is_false() => b,
+                    (EqualityTree::Leaf(true), EqualityTree::Leaf(true)) => {
+                        EqualityTree::Leaf(true)
                     }
-                }
-                EqualityTree::Or(mut data) => {
-                    let (a, b) = data.as_ref().as_ref().unwrap();
-                    match (&a, &b) {
-                        (EqualityTree::Leaf(true), _) | (_, EqualityTree::Leaf(true)) => {
-                            EqualityTree::Leaf(true)
-                        }
-                        (EqualityTree::Leaf(false), EqualityTree::Leaf(false)) => {
-                            EqualityTree::Leaf(false)
-                        }
-                        _ => EqualityTree::Or(BumpBox::new_in(
-                            {
-                                let data = data.take().unwrap();
-                                Some((
-                                    equivalence_helper(
-                                        data.0,
-                                        definitions,
-                                        alloc,
-                                        o_alloc,
-                                        &mut *cache,
-                                    )?,
-                                    equivalence_helper(data.1, definitions, alloc, o_alloc, cache)?,
-                                ))
-                            },
-                            o_alloc,
+                    (a, b) => EqualityTree::And(BumpBox::new_in(
+                        Some((
+                            equivalence_helper(a, definitions, alloc, o_alloc, &mut *cache)?,
+                            equivalence_helper(b, definitions, alloc, o_alloc, cache)?,
                         )),
+                        o_alloc,
+                    )),
+                },
+                EqualityTree::Or(mut data) => match data.take().unwrap() {
+                    (EqualityTree::Leaf(true), _) | (_, EqualityTree::Leaf(true)) => {
+                        EqualityTree::Leaf(true)
                     }
-                }
-                EqualityTree::Equal(mut a, mut b) => {
-                    a.weak_normalize_in_erased::<_, B>(&Empty, alloc, true)?;
-                    b.weak_normalize_in_erased::<_, B>(&Empty, alloc, true)?;
-
-                    let mut hasher = DefaultHasher::new();
-
-                    a.hash(&mut hasher);
-
-                    let a_hash = hasher.finish();
+                    (a, b @ EqualityTree::Mismatch(_)) if a.is_false() => b,
+                    (a, b) if a.is_false() && b.is_false() => a,
+                    (a, b) => EqualityTree::Or(BumpBox::new_in(
+                        Some((
+                            equivalence_helper(a, definitions, alloc, o_alloc, &mut *cache)?,
+                            equivalence_helper(b, definitions, alloc, o_alloc, cache)?,
+                        )),
+                        o_alloc,
+                    )),
+                },
+                EqualityTree::Equal(mut a, mut b, path, normalized) => {
+                    let child = |direction| {
+                        Some(&*o_alloc.alloc(PathNode {
+                            parent: path,
+                            direction,
+                        }))
+                    };
 
-                    let mut hasher = DefaultHasher::new();
+                    // Once the path passes through normalization it stays there, so the terms as
+                    // given only need hashing while it hasn't.
+                    let given = if normalized {
+                        None
+                    } else {
+                        Some((a.stable_hash(), b.stable_hash()))
+                    };
 
-                    b.hash(&mut hasher);
+                    a.weak_normalize_in_erased::<_, B>(&Empty, alloc, true)?;
+                    b.weak_normalize_in_erased::<_, B>(&Empty, alloc, true)?;
 
-                    let b_hash = hasher.finish();
+                    let a_hash = a.stable_hash();
+                    let b_hash = b.stable_hash();
+                    let reduced = given != Some((a_hash, b_hash));
 
-                    if a_hash == b_hash {
+                    if a_hash == b_hash && a.equals(&b) {
                         return Ok(EqualityTree::Leaf(true));
                     }
 
@@ -274,10 +310,14 @@ impl<T: PartialEq + Show + Clone, V: Show + Clone + Primitives<T>, A: Allocator<
                                         EqualityTree::Equal(
                                             alloc.copy(a_function),
                                             alloc.copy(b_function),
+                                            child(Direction::Function),
+                                            reduced,
                                         ),
                                         EqualityTree::Equal(
                                             alloc.copy(a_argument),
                                             alloc.copy(b_argument),
+                                            child(Direction::Argument),
+                                            reduced,
                                         ),
                                     )),
                                     o_alloc,
@@ -296,107 +336,131 @@ impl<T: PartialEq + Show + Clone, V: Show + Clone + Primitives<T>, A: Allocator<
                     a.weak_normalize_in_erased::<_, B>(definitions, alloc, true)?;
                     b.weak_normalize_in_erased::<_, B>(definitions, alloc, true)?;
 
-                    let ret_b = match (a, b) {
-                        (Universe, Universe) => EqualityTree::Leaf(true),
-                        (
-                            Function {
-                                argument_type: a_argument_type,
-                                return_type: a_return_type,
-                                erased: a_erased,
-                            },
-                            Function {
-                                argument_type: b_argument_type,
-                                return_type: b_return_type,
-                                erased: b_erased,
-                            },
-                        ) => {
-                            if a_erased != b_erased {
-                                EqualityTree::Leaf(false)
-                            } else {
-                                EqualityTree::And(BumpBox::new_in(
-                                    Some((
-                                        EqualityTree::Equal(
-                                            a_argument_type.into_inner(),
-                                            b_argument_type.into_inner(),
-                                        ),
-                                        EqualityTree::Equal(
-                                            a_return_type.into_inner(),
-                                            b_return_type.into_inner(),
-                                        ),
-                                    )),
-                                    o_alloc,
+                    let unfolded = reduced || given != Some((a.stable_hash(), b.stable_hash()));
+                    let ret_b = if a.is_erasure_mismatch(&b) {
+                        None
+                    } else {
+                        match (&mut a, &mut b) {
+                            (Universe, Universe) => Some(EqualityTree::Leaf(true)),
+                            (
+                                Function {
+                                    argument_type: a_argument_type,
+                                    return_type: a_return_type,
+                                    ..
+                                },
+                                Function {
+                                    argument_type: b_argument_type,
+                                    return_type: b_return_type,
+                                    ..
+                                },
+                            ) => Some(EqualityTree::And(BumpBox::new_in(
+                                Some((
+                                    EqualityTree::Equal(
+                                        a_argument_type.take(),
+                                        b_argument_type.take(),
+                                        child(Direction::ArgumentType),
+                                        unfolded,
+                                    ),
+                                    EqualityTree::Equal(
+                                        a_return_type.take(),
+                                        b_return_type.take(),
+                                        child(Direction::ReturnType),
+                                        unfolded,
+                                    ),
+                                )),
+                                o_alloc,
+                            ))),
+                            (Lambda { body: a_body, .. }, Lambda { body: b_body, .. }) => {
+                                Some(EqualityTree::Equal(
+                                    a_body.take(),
+                                    b_body.take(),
+                                    child(Direction::Body),
+                                    unfolded,
                                 ))
                             }
-                        }
-                        (
-                            Lambda {
-                                body: a_body,
-                                erased: a_erased,
-                            },
-                            Lambda {
-                                body: b_body,
-                                erased: b_erased,
-                            },
-                        ) => {
-                            if a_erased != b_erased {
-                                EqualityTree::Leaf(false)
-                            } else {
-                                EqualityTree::Equal(a_body.into_inner(), b_body.into_inner())
-                            }
-                        }
-                        (
-                            Apply {
-                                argument: a_argument,
-                                function: a_function,
-                                erased: a_erased,
-                            },
-                            Apply {
-                                argument: b_argument,
-                                function: b_function,
-                                erased: b_erased,
-                            },
-                        ) => {
-                            if a_erased != b_erased {
-                                EqualityTree::Leaf(false)
-                            } else {
-                                EqualityTree::And(BumpBox::new_in(
-                                    Some((
-                                        EqualityTree::Equal(
-                                            a_argument.into_inner(),
-                                            b_argument.into_inner(),
-                                        ),
-                                        EqualityTree::Equal(
-                                            a_function.into_inner(),
-                                            b_function.into_inner(),
-                                        ),
-                                    )),
-                                    o_alloc,
+                            (
+                                Apply {
+                                    argument: a_argument,
+                                    function: a_function,
+                                    ..
+                                },
+                                Apply {
+                                    argument: b_argument,
+                                    function: b_function,
+                                    ..
+                                },
+                            ) => Some(EqualityTree::And(BumpBox::new_in(
+                                Some((
+                                    EqualityTree::Equal(
+                                        a_argument.take(),
+                                        b_argument.take(),
+                                        child(Direction::Argument),
+                                        unfolded,
+                                    ),
+                                    EqualityTree::Equal(
+                                        a_function.take(),
+                                        b_function.take(),
+                                        child(Direction::Function),
+                                        unfolded,
+                                    ),
+                                )),
+                                o_alloc,
+                            ))),
+                            (Lambda { body, erased }, other) | (other, Lambda { body, erased }) => {
+                                let mut other = other.take();
+                                other.shift_top();
+                                Some(EqualityTree::Equal(
+                                    body.take(),
+                                    Apply {
+                                        function: alloc.alloc(other),
+                                        argument: alloc.alloc(Variable(Index::top())),
+                                        erased: *erased,
+                                    },
+                                    child(Direction::Body),
+                                    // Only one side has a body; the other is eta-expanded.
+                                    true,
                                 ))
                             }
-                        }
-                        (Variable(a), Variable(b)) => EqualityTree::Leaf(a == b),
-                        (Wrap(a), Wrap(b)) => EqualityTree::Equal(a.into_inner(), b.into_inner()),
-                        (Put(a), Put(b)) => EqualityTree::Equal(a.into_inner(), b.into_inner()),
-                        (
-                            Duplicate {
-                                expression: a_expression,
-                                body: a_body,
-                            },
-                            Duplicate {
-                                expression: b_expression,
-                                body: b_body,
-                            },
-                        ) => EqualityTree::And(BumpBox::new_in(
-                            Some((
-                                EqualityTree::Equal(
-                                    a_expression.into_inner(),
-                                    b_expression.into_inner(),
-                                ),
-                                EqualityTree::Equal(a_body.into_inner(), b_body.into_inner()),
+                            (Variable(a), Variable(b)) if a == b => Some(EqualityTree::Leaf(true)),
+                            (Wrap(a), Wrap(b)) | (Put(a), Put(b)) => Some(EqualityTree::Equal(
+                                a.take(),
+                                b.take(),
+                                child(Direction::Contents),
+                                unfolded,
                             )),
-                            o_alloc,
-                        )),
-                        _ => EqualityTree::Leaf(false),
+                            (
+                                Duplicate {
+                                    expression: a_expression,
+                                    body: a_body,
+                                },
+                                Duplicate {
+                                    expression: b_expression,
+                                    body: b_body,
+                                },
+                            ) => Some(EqualityTree::And(BumpBox::new_in(
+                                Some((
+                                    EqualityTree::Equal(
+                                        a_expression.take(),
+                                        b_expression.take(),
+                                        child(Direction::Expression),
+                                        unfolded,
+                                    ),
+                                    EqualityTree::Equal(
+                                        a_body.take(),
+                                        b_body.take(),
+                                        child(Direction::Body),
+                                        unfolded,
+                                    ),
+                                )),
+                                o_alloc,
+                            ))),
+                            _ => None,
+                        }
+                    };
+
+                    let ret_b = match ret_b {
+                        Some(ret_b) => ret_b,
+                        None => EqualityTree::Mismatch(Mismatch::new(path, normalized, a, b)),
                     };
 
                     if let Some(ret_a) = ret_a {
@@ -410,62 +474,153 @@ impl<T: PartialEq + Show + Clone, V: Show + Clone + Primitives<T>, A: Allocator<
 
         let o_alloc = Bump::new();
 
+        let given = (self.stable_hash(), other.stable_hash());
+
         let mut a = alloc.copy(self);
         let mut b = alloc.copy(other);
 
         a.weak_normalize_in_erased(definitions, alloc, true)?;
         b.weak_normalize_in_erased(definitions, alloc, true)?;
 
-        let mut hasher = DefaultHasher::new();
-
-        a.hash(&mut hasher);
-
-        let a_hash = hasher.finish();
-
-        let mut hasher = DefaultHasher::new();
-
-        b.hash(&mut hasher);
-
-        let b_hash = hasher.finish();
+        let a_hash = a.stable_hash();
+        let b_hash = b.stable_hash();
 
-        if a_hash == b_hash {
-            return Ok(true);
+        if a_hash == b_hash && a.equals(&b) {
+            return Ok(Equivalence::Equivalent);
         }
 
         if let Some(leaf) = cache.check(a_hash, b_hash) {
-            return Ok(leaf);
+            return Ok(if leaf {
+                Equivalence::Equivalent
+            } else {
+                Equivalence::Unequal(None)
+            });
         }
 
-        let mut equality = EqualityTree::Equal(a, b);
+        let mut equality = EqualityTree::Equal(a, b, None, given != (a_hash, b_hash));
 
-        while match equality {
-            EqualityTree::Leaf(_) => false,
-            _ => true,
-        } {
+        while let EqualityTree::And(_) | EqualityTree::Or(_) | EqualityTree::Equal(..) = equality {
             equality = equivalence_helper(equality, definitions, alloc, &o_alloc, cache)?;
         }
 
-        Ok(if let EqualityTree::Leaf(leaf) = equality {
-            cache.register(a_hash, b_hash, leaf);
-            leaf
-        } else {
-            panic!()
+        Ok(match equality {
+            EqualityTree::Leaf(leaf) => {
+                cache.register(a_hash, b_hash, leaf);
+                if leaf {
+                    Equivalence::Equivalent
+                } else {
+                    Equivalence::Unequal(None)
+                }
+            }
+            EqualityTree::Mismatch(mismatch) => {
+                cache.register(a_hash, b_hash, false);
+                Equivalence::Unequal(Some(mismatch))
+            }
+            _ => panic!(),
         })
     }
 
+    pub fn equivalent_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &self,
+        other: &Self,
+        definitions: &U,
+        alloc: &A,
+        cache: &mut impl EqualityCache,
+    ) -> Result<bool, NormalizationError<T, V, A>>
+    where
+        A: Reallocate<T, V, B>,
+        T: Hash,
+        V: Hash + PartialEq,
+    {
+        Ok(matches!(
+            self.equivalence_in(other, definitions, alloc, cache)?,
+            Equivalence::Equivalent
+        ))
+    }
+
+    // Checks equivalence with the chosen normalizer. Both agree on every term; evaluation avoids
+    // copying arguments into function bodies, which pays off on terms with many large redexes.
+    pub fn equivalent_with_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &self,
+        other: &Self,
+        normalizer: Normalizer,
+        definitions: &U,
+        alloc: &A,
+        cache: &mut impl EqualityCache,
+    ) -> Result<bool, NormalizationError<T, V, A>>
+    where
+        A: Reallocate<T, V, B>,
+        T: Hash,
+        V: Hash + PartialEq,
+    {
+        match normalizer {
+            Normalizer::Substitution => self.equivalent_in(other, definitions, alloc, cache),
+            Normalizer::Evaluation => {
+                let a_hash = self.stable_hash();
+                let b_hash = other.stable_hash();
+
+                if let Some(leaf) = cache.check(a_hash, b_hash) {
+                    return Ok(leaf);
+                }
+
+                let leaf = self.convertible_in(other, definitions, alloc)?;
+                cache.register(a_hash, b_hash, leaf);
+                Ok(leaf)
+            }
+        }
+    }
+
+    pub fn mismatch_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &self,
+        other: &Self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Mismatched<T, V, A>
+    where
+        A: Reallocate<T, V, B>,
+        T: Hash,
+        V: Hash + PartialEq,
+    {
+        Ok(
+            match self.equivalence_in(other, definitions, alloc, &mut NullCache)? {
+                Equivalence::Equivalent => None,
+                Equivalence::Unequal(mismatch) => mismatch,
+            },
+        )
+    }
+
     pub fn equivalent<U: Definitions<T, V, A>>(
         &self,
         other: &Self,
         definitions: &U,
         cache: &mut impl EqualityCache,
-    ) -> Result<bool, NormalizationError>
+    ) -> Result<bool, NormalizationError<T, V, A>>
     where
         A: Zero + Reallocate<T, V, A>,
         T: Hash,
-        V: Hash,
+        V: Hash + PartialEq,
     {
         let alloc = A::zero();
 
         self.equivalent_in(other, definitions, &alloc, cache)
     }
 }
+
+impl<T: PartialEq + Show + Clone, V: Show + Clone + Primitives<T>, A: Allocator<T, V>>
+    Term<T, V, A>
+{
+    pub fn mismatch<U: Definitions<T, V, A>>(
+        &self,
+        other: &Self,
+        definitions: &U,
+    ) -> Mismatched<T, V, A>
+    where
+        A: Zero + Reallocate<T, V, A>,
+        T: Hash,
+        V: Hash + PartialEq,
+    {
+        let alloc = A::zero();
+
+        self.mismatch_in(other, definitions, &alloc)
+    }
+}
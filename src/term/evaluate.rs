@@ -0,0 +1,1640 @@
+use std::{cell::RefCell, collections::HashMap, hash::Hash, mem, rc::Rc};
+
+use super::{
+    alloc::{Allocator, Reallocate},
+    Definitions, Index, None, NormalizationError, Primitives, Redex, Term, Zero,
+};
+
+// Only `normalize_with_in` and `equivalent_with_in` take a normalizer. Type checking, through
+// `check_in` and `equivalent_in`, always normalizes by substitution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Normalizer {
+    // Rewrites the term in place, copying and substituting on every beta step.
+    #[default]
+    Substitution,
+    // Evaluates the term into semantic values with environments and closures, then reads the
+    // result back into a term.
+    Evaluation,
+}
+
+// An immutable, shareable copy of a term that closures and environments can point into.
+enum Code<T, V> {
+    Variable(usize),
+    Lambda {
+        body: Rc<Code<T, V>>,
+        erased: bool,
+    },
+    Apply {
+        function: Rc<Code<T, V>>,
+        argument: Rc<Code<T, V>>,
+        erased: bool,
+    },
+    Put(Rc<Code<T, V>>),
+    Duplicate {
+        expression: Rc<Code<T, V>>,
+        body: Rc<Code<T, V>>,
+    },
+    Reference(T),
+    Primitive(V),
+    Universe,
+    Function {
+        argument_type: Rc<Code<T, V>>,
+        return_type: Rc<Code<T, V>>,
+        erased: bool,
+    },
+    Annotation {
+        checked: bool,
+        expression: Rc<Code<T, V>>,
+        ty: Rc<Code<T, V>>,
+    },
+    Wrap(Rc<Code<T, V>>),
+}
+
+// A node waiting for its children, which are converted or read back first and left on top of a
+// stack in order.
+#[derive(Clone, Copy)]
+enum Build {
+    Lambda(bool),
+    Apply(bool),
+    Put,
+    Duplicate,
+    Function(bool),
+    Annotation(bool),
+    Wrap,
+}
+
+impl Build {
+    fn code<T, V>(self, codes: &mut Vec<Rc<Code<T, V>>>) -> Code<T, V> {
+        let mut pop = || codes.pop().unwrap();
+
+        match self {
+            Build::Lambda(erased) => Code::Lambda {
+                body: pop(),
+                erased,
+            },
+            Build::Apply(erased) => {
+                let argument = pop();
+                Code::Apply {
+                    function: pop(),
+                    argument,
+                    erased,
+                }
+            }
+            Build::Put => Code::Put(pop()),
+            Build::Duplicate => {
+                let body = pop();
+                Code::Duplicate {
+                    expression: pop(),
+                    body,
+                }
+            }
+            Build::Function(erased) => {
+                let return_type = pop();
+                Code::Function {
+                    argument_type: pop(),
+                    return_type,
+                    erased,
+                }
+            }
+            Build::Annotation(checked) => {
+                let ty = pop();
+                Code::Annotation {
+                    checked,
+                    expression: pop(),
+                    ty,
+                }
+            }
+            Build::Wrap => Code::Wrap(pop()),
+        }
+    }
+
+    fn term<T, V: Primitives<T>, A: Allocator<T, V>>(
+        self,
+        terms: &mut Vec<Term<T, V, A>>,
+        alloc: &A,
+    ) -> Term<T, V, A> {
+        let mut pop = || alloc.alloc(terms.pop().unwrap());
+
+        match self {
+            Build::Lambda(erased) => Term::Lambda {
+                body: pop(),
+                erased,
+            },
+            Build::Apply(erased) => {
+                let argument = pop();
+                Term::Apply {
+                    function: pop(),
+                    argument,
+                    erased,
+                }
+            }
+            Build::Put => Term::Put(pop()),
+            Build::Duplicate => {
+                let body = pop();
+                Term::Duplicate {
+                    expression: pop(),
+                    body,
+                }
+            }
+            Build::Function(erased) => {
+                let return_type = pop();
+                Term::Function {
+                    argument_type: pop(),
+                    return_type,
+                    erased,
+                }
+            }
+            Build::Annotation(checked) => {
+                let ty = pop();
+                Term::Annotation {
+                    checked,
+                    expression: pop(),
+                    ty,
+                }
+            }
+            Build::Wrap => Term::Wrap(pop()),
+        }
+    }
+}
+
+impl<T: Clone, V: Clone> Code<T, V> {
+    fn from_term<A: Allocator<T, V>>(term: &Term<T, V, A>) -> Rc<Self>
+    where
+        V: Primitives<T>,
+    {
+        use Term::*;
+
+        enum Task<'a, T, V: Primitives<T>, A: Allocator<T, V>> {
+            Convert(&'a Term<T, V, A>),
+            Build(Build),
+        }
+
+        let mut tasks = vec![Task::Convert(term)];
+        let mut codes = vec![];
+
+        while let Some(task) = tasks.pop() {
+            let term = match task {
+                Task::Convert(term) => term,
+                Task::Build(build) => {
+                    let code = build.code(&mut codes);
+                    codes.push(Rc::new(code));
+                    continue;
+                }
+            };
+
+            // Children are pushed last first, so they're converted in order.
+            match term {
+                Variable(index) => codes.push(Rc::new(Code::Variable(index.0))),
+                Reference(reference) => codes.push(Rc::new(Code::Reference(reference.clone()))),
+                Primitive(primitive) => codes.push(Rc::new(Code::Primitive(primitive.clone()))),
+                Universe => codes.push(Rc::new(Code::Universe)),
+                Lambda { body, erased } => {
+                    tasks.extend([Task::Build(Build::Lambda(*erased)), Task::Convert(body)])
+                }
+                Apply {
+                    function,
+                    argument,
+                    erased,
+                } => tasks.extend([
+                    Task::Build(Build::Apply(*erased)),
+                    Task::Convert(argument),
+                    Task::Convert(function),
+                ]),
+                Put(term) => tasks.extend([Task::Build(Build::Put), Task::Convert(term)]),
+                Duplicate { expression, body } => tasks.extend([
+                    Task::Build(Build::Duplicate),
+                    Task::Convert(body),
+                    Task::Convert(expression),
+                ]),
+                Function {
+                    argument_type,
+                    return_type,
+                    erased,
+                } => tasks.extend([
+                    Task::Build(Build::Function(*erased)),
+                    Task::Convert(return_type),
+                    Task::Convert(argument_type),
+                ]),
+                Annotation {
+                    checked,
+                    expression,
+                    ty,
+                } => tasks.extend([
+                    Task::Build(Build::Annotation(*checked)),
+                    Task::Convert(ty),
+                    Task::Convert(expression),
+                ]),
+                Wrap(term) => tasks.extend([Task::Build(Build::Wrap), Task::Convert(term)]),
+            }
+        }
+
+        codes.pop().unwrap()
+    }
+}
+
+impl<T, V> Code<T, V> {
+    fn collect(&mut self, garbage: &mut Vec<Garbage<T, V>>) {
+        match self {
+            Code::Lambda { body: term, .. } | Code::Put(term) | Code::Wrap(term) => {
+                Garbage::code(term, garbage)
+            }
+            Code::Apply {
+                function: a,
+                argument: b,
+                ..
+            }
+            | Code::Duplicate {
+                expression: a,
+                body: b,
+            }
+            | Code::Function {
+                argument_type: a,
+                return_type: b,
+                ..
+            }
+            | Code::Annotation {
+                expression: a,
+                ty: b,
+                ..
+            } => {
+                Garbage::code(a, garbage);
+                Garbage::code(b, garbage);
+            }
+            Code::Variable(_) | Code::Reference(_) | Code::Primitive(_) | Code::Universe => {}
+        }
+    }
+}
+
+impl<T, V> Drop for Code<T, V> {
+    fn drop(&mut self) {
+        let mut garbage = vec![];
+        self.collect(&mut garbage);
+        Garbage::dispose(garbage);
+    }
+}
+
+// Parts of a value being dropped that nothing else refers to, moved out of their parents so that
+// dropping a long chain of code, environments or forced arguments loops instead of recursing.
+enum Garbage<T, V> {
+    Code(Code<T, V>),
+    Node(EnvNode<T, V>),
+    Value(Value<T, V>),
+}
+
+impl<T, V> Garbage<T, V> {
+    fn code(code: &mut Rc<Code<T, V>>, garbage: &mut Vec<Self>) {
+        if let Some(code) = Rc::get_mut(code) {
+            if !matches!(
+                code,
+                Code::Variable(_) | Code::Reference(_) | Code::Primitive(_) | Code::Universe
+            ) {
+                garbage.push(Garbage::Code(mem::replace(code, Code::Universe)));
+            }
+        }
+    }
+
+    fn env(env: &mut Env<T, V>, garbage: &mut Vec<Self>) {
+        if let Some(node) = env.0.take() {
+            if let Ok(node) = Rc::try_unwrap(node) {
+                garbage.push(Garbage::Node(node));
+            }
+        }
+    }
+
+    fn thunk(thunk: &mut Thunk<T, V>, garbage: &mut Vec<Self>) {
+        if let Thunk::Delayed(delayed) = thunk {
+            if let Some(delayed) = Rc::get_mut(delayed) {
+                delayed.collect(garbage);
+            }
+        }
+    }
+
+    fn dispose(mut garbage: Vec<Self>) {
+        while let Some(mut item) = garbage.pop() {
+            match &mut item {
+                Garbage::Code(code) => code.collect(&mut garbage),
+                Garbage::Node(node) => node.collect(&mut garbage),
+                Garbage::Value(value) => value.collect(&mut garbage),
+            }
+        }
+    }
+}
+
+// Variables are identified by de Bruijn level while evaluating, so values never need shifting.
+// Variables free in the term being evaluated get negative levels.
+type Level = isize;
+
+enum Thunk<T, V> {
+    Variable(Level),
+    Delayed(Rc<Delayed<T, V>>),
+}
+
+impl<T, V> Clone for Thunk<T, V> {
+    fn clone(&self) -> Self {
+        match self {
+            Thunk::Variable(level) => Thunk::Variable(*level),
+            Thunk::Delayed(delayed) => Thunk::Delayed(delayed.clone()),
+        }
+    }
+}
+
+impl<T, V> Thunk<T, V> {
+    fn delay(code: &Rc<Code<T, V>>, env: &Env<T, V>) -> Self {
+        Thunk::Delayed(Rc::new(Delayed {
+            code: code.clone(),
+            env: env.clone(),
+            value: RefCell::new(None),
+        }))
+    }
+}
+
+// Code waiting to be evaluated in its environment. The value is kept once forced, and every copy
+// of the thunk shares it, so an argument is evaluated at most once however often it is used.
+struct Delayed<T, V> {
+    code: Rc<Code<T, V>>,
+    env: Env<T, V>,
+    value: RefCell<Option<Value<T, V>>>,
+}
+
+struct EnvNode<T, V> {
+    thunk: Thunk<T, V>,
+    next: Env<T, V>,
+    len: usize,
+}
+
+struct Env<T, V>(Option<Rc<EnvNode<T, V>>>);
+
+impl<T, V> Clone for Env<T, V> {
+    fn clone(&self) -> Self {
+        Env(self.0.clone())
+    }
+}
+
+impl<T, V> Env<T, V> {
+    fn empty() -> Self {
+        Env(None)
+    }
+
+    // Maps every index to the variable bound at the corresponding level of a context `len` deep.
+    fn identity(len: usize) -> Self {
+        let mut env = Env::empty();
+        for level in 0..len {
+            env = env.push(Thunk::Variable(level as Level));
+        }
+        env
+    }
+
+    fn len(&self) -> usize {
+        self.0.as_ref().map_or(0, |node| node.len)
+    }
+
+    fn push(&self, thunk: Thunk<T, V>) -> Self {
+        Env(Some(Rc::new(EnvNode {
+            thunk,
+            next: self.clone(),
+            len: self.len() + 1,
+        })))
+    }
+
+    fn get(&self, index: usize) -> Thunk<T, V> {
+        let mut env = self;
+        let mut remaining = index;
+        while let Some(node) = &env.0 {
+            if remaining == 0 {
+                return node.thunk.clone();
+            }
+            remaining -= 1;
+            env = &node.next;
+        }
+        Thunk::Variable(-1 - (index - self.len()) as Level)
+    }
+}
+
+enum Value<T, V> {
+    Lambda {
+        erased: bool,
+        body: Rc<Code<T, V>>,
+        env: Env<T, V>,
+    },
+    Function {
+        erased: bool,
+        argument_type: Thunk<T, V>,
+        return_type: Rc<Code<T, V>>,
+        env: Env<T, V>,
+    },
+    Wrap(Thunk<T, V>),
+    // A term the normalizer leaves unreduced, with its free variables still to be substituted.
+    Frozen(Rc<Code<T, V>>, Env<T, V>),
+    Universe,
+    Primitive(V),
+    Neutral {
+        head: Head<T, V>,
+        spine: Vec<(Thunk<T, V>, bool)>,
+    },
+}
+
+enum Head<T, V> {
+    Variable(Level),
+    Reference(T),
+    // A primitive applied to fewer arguments than its arity.
+    Primitive(V),
+    Stuck(Box<Value<T, V>>),
+}
+
+impl<T: Clone, V: Clone> Clone for Value<T, V> {
+    fn clone(&self) -> Self {
+        match self {
+            Value::Lambda { erased, body, env } => Value::Lambda {
+                erased: *erased,
+                body: body.clone(),
+                env: env.clone(),
+            },
+            Value::Function {
+                erased,
+                argument_type,
+                return_type,
+                env,
+            } => Value::Function {
+                erased: *erased,
+                argument_type: argument_type.clone(),
+                return_type: return_type.clone(),
+                env: env.clone(),
+            },
+            Value::Wrap(term) => Value::Wrap(term.clone()),
+            Value::Frozen(code, env) => Value::Frozen(code.clone(), env.clone()),
+            Value::Universe => Value::Universe,
+            Value::Primitive(primitive) => Value::Primitive(primitive.clone()),
+            Value::Neutral { head, spine } => Value::Neutral {
+                head: match head {
+                    Head::Variable(variable) => Head::Variable(*variable),
+                    Head::Reference(reference) => Head::Reference(reference.clone()),
+                    Head::Primitive(primitive) => Head::Primitive(primitive.clone()),
+                    Head::Stuck(value) => Head::Stuck(value.clone()),
+                },
+                spine: spine.clone(),
+            },
+        }
+    }
+}
+
+impl<T, V> Delayed<T, V> {
+    fn collect(&mut self, garbage: &mut Vec<Garbage<T, V>>) {
+        Garbage::code(&mut self.code, garbage);
+        Garbage::env(&mut self.env, garbage);
+        if let Some(value) = self.value.get_mut().take() {
+            garbage.push(Garbage::Value(value));
+        }
+    }
+}
+
+impl<T, V> Drop for Delayed<T, V> {
+    fn drop(&mut self) {
+        let mut garbage = vec![];
+        self.collect(&mut garbage);
+        Garbage::dispose(garbage);
+    }
+}
+
+impl<T, V> EnvNode<T, V> {
+    fn collect(&mut self, garbage: &mut Vec<Garbage<T, V>>) {
+        Garbage::thunk(&mut self.thunk, garbage);
+        Garbage::env(&mut self.next, garbage);
+    }
+}
+
+impl<T, V> Drop for EnvNode<T, V> {
+    fn drop(&mut self) {
+        let mut garbage = vec![];
+        self.collect(&mut garbage);
+        Garbage::dispose(garbage);
+    }
+}
+
+impl<T, V> Value<T, V> {
+    fn collect(&mut self, garbage: &mut Vec<Garbage<T, V>>) {
+        match self {
+            Value::Lambda { body, env, .. } => {
+                Garbage::code(body, garbage);
+                Garbage::env(env, garbage);
+            }
+            Value::Function {
+                argument_type,
+                return_type,
+                env,
+                ..
+            } => {
+                Garbage::thunk(argument_type, garbage);
+                Garbage::code(return_type, garbage);
+                Garbage::env(env, garbage);
+            }
+            Value::Wrap(thunk) => Garbage::thunk(thunk, garbage),
+            Value::Frozen(code, env) => {
+                Garbage::code(code, garbage);
+                Garbage::env(env, garbage);
+            }
+            Value::Neutral { head, spine } => {
+                if let Head::Stuck(value) = head {
+                    garbage.push(Garbage::Value(mem::replace(&mut **value, Value::Universe)));
+                }
+                for (thunk, _) in spine {
+                    Garbage::thunk(thunk, garbage);
+                }
+            }
+            Value::Universe | Value::Primitive(_) => {}
+        }
+    }
+}
+
+// Outstanding comparisons of a conversion check, expanded breadth-first.
+enum Conversion<T, V> {
+    Leaf(bool),
+    Equal(Value<T, V>, Value<T, V>, usize),
+    And(Box<(Conversion<T, V>, Conversion<T, V>)>),
+    Or(Box<(Conversion<T, V>, Conversion<T, V>)>),
+}
+
+// Junctions are built through `and` and `or`, which settle them as soon as a branch is decided, so
+// a chain of comparisons that succeed doesn't leave a deep tree behind.
+impl<T, V> Conversion<T, V> {
+    fn and(a: Self, b: Self) -> Self {
+        use Conversion::*;
+
+        match (a, b) {
+            (Leaf(false), tree) | (tree, Leaf(false)) => {
+                tree.discard();
+                Leaf(false)
+            }
+            (Leaf(true), tree) | (tree, Leaf(true)) => tree,
+            (a, b) => And(Box::new((a, b))),
+        }
+    }
+
+    fn or(a: Self, b: Self) -> Self {
+        use Conversion::*;
+
+        match (a, b) {
+            (Leaf(true), tree) | (tree, Leaf(true)) => {
+                tree.discard();
+                Leaf(true)
+            }
+            (Leaf(false), tree) | (tree, Leaf(false)) => tree,
+            (a, b) => Or(Box::new((a, b))),
+        }
+    }
+
+    // Drops a tree that's no longer needed without recursing on its depth.
+    fn discard(self) {
+        let mut trees = vec![self];
+        while let Some(tree) = trees.pop() {
+            if let Conversion::And(data) | Conversion::Or(data) = tree {
+                let (a, b) = *data;
+                trees.push(a);
+                trees.push(b);
+            }
+        }
+    }
+}
+
+// The value found so far, or the code still to be evaluated to find it.
+enum State<T, V> {
+    Eval(Rc<Code<T, V>>, Env<T, V>),
+    Return(Value<T, V>),
+}
+
+// What is left to do with a value once it's found. Evaluation keeps these on an explicit stack, so
+// a long chain of applications, or of arguments that force one another, doesn't recurse.
+enum Frame<T, V> {
+    Apply(Thunk<T, V>, bool),
+    // Keeps the value as that of a thunk being forced.
+    Store(Rc<Delayed<T, V>>),
+}
+
+// Work left while reading a value back into a term, kept on an explicit stack like evaluation's.
+enum ReadBack<T, V> {
+    Value(Value<T, V>, usize),
+    Thunk(Thunk<T, V>, usize),
+    // Code read back without reducing it, with its environment substituted into its free
+    // variables.
+    Code {
+        code: Rc<Code<T, V>>,
+        env: Env<T, V>,
+        level: usize,
+        binders: usize,
+    },
+    Build(Build),
+}
+
+// The value of a thunk, if it's known without evaluating anything.
+fn forced<T: Clone, V: Clone>(thunk: Thunk<T, V>) -> Result<Value<T, V>, Rc<Delayed<T, V>>> {
+    match thunk {
+        Thunk::Variable(variable) => Ok(Value::Neutral {
+            head: Head::Variable(variable),
+            spine: vec![],
+        }),
+        Thunk::Delayed(delayed) => {
+            let value = delayed.value.borrow().clone();
+            value.ok_or(delayed)
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    // Mirrors `normalize_in`: erased lambdas, applications and boxes are removed, and types are
+    // left as they are.
+    Computational,
+    // Mirrors the weak normalization used by `equivalent_in`: boxes are removed but erasure is
+    // kept, and types are evaluated so they can be compared.
+    Conversion,
+}
+
+// Normal forms of definitions, looked up before a definition is unfolded during computational
+// evaluation. The unit implementation keeps nothing.
+trait Memo<T, V> {
+    fn get(&self, name: &T) -> Option<Rc<Code<T, V>>>;
+
+    fn insert(&self, name: T, normalized: Rc<Code<T, V>>);
+}
+
+impl<T, V> Memo<T, V> for () {
+    fn get(&self, _: &T) -> Option<Rc<Code<T, V>>> {
+        None
+    }
+
+    fn insert(&self, _: T, _: Rc<Code<T, V>>) {}
+}
+
+// Normal forms of the definitions unfolded by `evaluate_in_session`, shared by every term evaluated
+// in the same session so that each definition is normalized at most once. A session borrows the
+// definitions it was made for and evaluates with those, so its normal forms can't outlive them or
+// be reused with others.
+pub struct Session<'a, T, U, V: Primitives<T> = None> {
+    definitions: &'a U,
+    normalized: RefCell<HashMap<T, Rc<Code<T, V>>>>,
+}
+
+impl<'a, T: Hash + Eq, U, V: Primitives<T>> Session<'a, T, U, V> {
+    pub fn new(definitions: &'a U) -> Self {
+        Session {
+            definitions,
+            normalized: RefCell::new(HashMap::new()),
+        }
+    }
+
+    // The number of definitions normalized so far.
+    pub fn len(&self) -> usize {
+        self.normalized.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, name: &T) -> bool {
+        self.normalized.borrow().contains_key(name)
+    }
+}
+
+impl<T: Hash + Eq, U, V: Primitives<T>> Memo<T, V> for Session<'_, T, U, V> {
+    fn get(&self, name: &T) -> Option<Rc<Code<T, V>>> {
+        self.normalized.borrow().get(name).cloned()
+    }
+
+    fn insert(&self, name: T, normalized: Rc<Code<T, V>>) {
+        self.normalized.borrow_mut().insert(name, normalized);
+    }
+}
+
+// Applies the jet attached to a definition with the evaluator's allocator, as
+// `Definitions::apply_jet` does. Conversion has none, so its definitions are always unfolded.
+type Jet<'a, T, V, A> = dyn Fn(&T, Vec<Term<T, V, A>>) -> Option<Result<Term<T, V, A>, NormalizationError<T, V, A>>>
+    + 'a;
+
+struct Evaluator<'a, T, V: Primitives<T>, U, A: Allocator<T, V>> {
+    definitions: &'a U,
+    alloc: &'a A,
+    mode: Mode,
+    memo: &'a dyn Memo<T, V>,
+    jet: &'a Jet<'a, T, V, A>,
+    // The first primitive failure, which stops the evaluation being reported as a success.
+    error: RefCell<Option<NormalizationError<T, V, A>>>,
+}
+
+fn index(level: usize, variable: Level) -> Index {
+    Index((level as Level - 1 - variable) as usize)
+}
+
+impl<'a, T: Clone, V: Primitives<T> + Clone, U, A: Allocator<T, V>> Evaluator<'a, T, V, U, A> {
+    fn eval<B: Allocator<T, V>>(
+        &self,
+        code: &Rc<Code<T, V>>,
+        env: &Env<T, V>,
+        level: usize,
+    ) -> Value<T, V>
+    where
+        U: Definitions<T, V, B>,
+    {
+        self.run(State::Eval(code.clone(), env.clone()), vec![], level)
+    }
+
+    // Evaluates until there's a value and no frame left to take it.
+    fn run<B: Allocator<T, V>>(
+        &self,
+        mut state: State<T, V>,
+        mut frames: Vec<Frame<T, V>>,
+        level: usize,
+    ) -> Value<T, V>
+    where
+        U: Definitions<T, V, B>,
+    {
+        loop {
+            let value = match state {
+                State::Return(value) => value,
+                State::Eval(code, env) => match &*code {
+                    Code::Variable(index) => match forced(env.get(*index)) {
+                        Ok(value) => value,
+                        Err(delayed) => {
+                            state = State::Eval(delayed.code.clone(), delayed.env.clone());
+                            frames.push(Frame::Store(delayed));
+                            continue;
+                        }
+                    },
+                    Code::Lambda { body, erased } => {
+                        if *erased && self.mode == Mode::Computational {
+                            state = State::Eval(body.clone(), env.push(env.get(0)));
+                            continue;
+                        }
+                        Value::Lambda {
+                            erased: *erased,
+                            body: body.clone(),
+                            env,
+                        }
+                    }
+                    Code::Apply {
+                        function,
+                        argument,
+                        erased,
+                    } => {
+                        if !*erased || self.mode != Mode::Computational {
+                            frames.push(Frame::Apply(Thunk::delay(argument, &env), *erased));
+                        }
+                        state = State::Eval(function.clone(), env);
+                        continue;
+                    }
+                    Code::Put(term) => {
+                        state = State::Eval(term.clone(), env);
+                        continue;
+                    }
+                    Code::Duplicate { expression, body } => {
+                        let expression = Thunk::delay(expression, &env);
+                        state = State::Eval(body.clone(), env.push(expression));
+                        continue;
+                    }
+                    Code::Annotation { expression, .. } => {
+                        state = State::Eval(expression.clone(), env);
+                        continue;
+                    }
+                    Code::Reference(reference) => {
+                        // Conversion unfolds references only when their names fail to match, since
+                        // recursive types would otherwise unfold forever. A jet is kept until it's
+                        // saturated, and unfolded when read back if it wasn't applied.
+                        let jet = matches!(self.definitions.jet_arity(reference), Some(arity) if arity > 0);
+                        if self.mode == Mode::Computational && !jet {
+                            if let Some(code) = self.definition(reference) {
+                                state = State::Eval(code, Env::empty());
+                                continue;
+                            }
+                        }
+                        Value::Neutral {
+                            head: Head::Reference(reference.clone()),
+                            spine: vec![],
+                        }
+                    }
+                    Code::Universe => Value::Universe,
+                    Code::Primitive(primitive) => Value::Primitive(primitive.clone()),
+                    Code::Wrap(term) => match self.mode {
+                        Mode::Computational => Value::Frozen(code.clone(), env),
+                        Mode::Conversion => Value::Wrap(Thunk::delay(term, &env)),
+                    },
+                    Code::Function {
+                        argument_type,
+                        return_type,
+                        erased,
+                    } => match self.mode {
+                        Mode::Computational => Value::Frozen(code.clone(), env),
+                        Mode::Conversion => Value::Function {
+                            erased: *erased,
+                            argument_type: Thunk::delay(argument_type, &env),
+                            return_type: return_type.clone(),
+                            env,
+                        },
+                    },
+                },
+            };
+
+            state = match frames.pop() {
+                None => return value,
+                Some(Frame::Store(delayed)) => {
+                    *delayed.value.borrow_mut() = Some(value.clone());
+                    State::Return(value)
+                }
+                Some(Frame::Apply(argument, erased)) => match value {
+                    Value::Lambda { body, env, .. } => State::Eval(body, env.push(argument)),
+                    function => State::Return(self.apply_stuck(function, argument, erased, level)),
+                },
+            };
+        }
+    }
+
+    fn force<B: Allocator<T, V>>(&self, thunk: Thunk<T, V>, level: usize) -> Value<T, V>
+    where
+        U: Definitions<T, V, B>,
+    {
+        match forced(thunk) {
+            Ok(value) => value,
+            Err(delayed) => self.run(
+                State::Eval(delayed.code.clone(), delayed.env.clone()),
+                vec![Frame::Store(delayed)],
+                level,
+            ),
+        }
+    }
+
+    // The code a reference unfolds to: its definition, or during computational evaluation the
+    // definition's normal form, which is memoized.
+    fn definition<B: Allocator<T, V>>(&self, reference: &T) -> Option<Rc<Code<T, V>>>
+    where
+        U: Definitions<T, V, B>,
+    {
+        let definition = self.definitions.get(reference)?;
+
+        Some(match self.mode {
+            Mode::Computational => self.memo.get(reference).unwrap_or_else(|| {
+                let value = self.eval(&Code::from_term(definition.as_ref()), &Env::empty(), 0);
+                // Jets in the normal form are left folded, so they can still be applied to the
+                // arguments it's given.
+                let normalized = Code::from_term(&self.read_back(ReadBack::Value(value, 0), false));
+                self.memo.insert(reference.clone(), normalized.clone());
+                normalized
+            }),
+            Mode::Conversion => Code::from_term(definition.as_ref()),
+        })
+    }
+
+    // Replaces a reference at the head of a neutral value by its definition, or returns the value
+    // unchanged if there is nothing to unfold.
+    fn unfold<B: Allocator<T, V>>(
+        &self,
+        value: Value<T, V>,
+        level: usize,
+    ) -> Result<Value<T, V>, Value<T, V>>
+    where
+        U: Definitions<T, V, B>,
+    {
+        let code = match &value {
+            Value::Neutral {
+                head: Head::Reference(reference),
+                ..
+            } => self.definition(reference),
+            _ => None,
+        };
+
+        match (code, value) {
+            (Some(code), Value::Neutral { spine, .. }) => Ok(self.apply_spine(code, spine, level)),
+            (_, value) => Err(value),
+        }
+    }
+
+    // Evaluates closed code applied to the arguments of a spine.
+    fn apply_spine<B: Allocator<T, V>>(
+        &self,
+        code: Rc<Code<T, V>>,
+        spine: Vec<(Thunk<T, V>, bool)>,
+        level: usize,
+    ) -> Value<T, V>
+    where
+        U: Definitions<T, V, B>,
+    {
+        let frames = spine
+            .into_iter()
+            .rev()
+            .map(|(argument, erased)| Frame::Apply(argument, erased))
+            .collect();
+        self.run(State::Eval(code, Env::empty()), frames, level)
+    }
+
+    fn apply<B: Allocator<T, V>>(
+        &self,
+        function: Value<T, V>,
+        argument: Thunk<T, V>,
+        erased: bool,
+        level: usize,
+    ) -> Value<T, V>
+    where
+        U: Definitions<T, V, B>,
+    {
+        self.run(
+            State::Return(function),
+            vec![Frame::Apply(argument, erased)],
+            level,
+        )
+    }
+
+    // Applies a value that isn't a lambda, which extends its spine, saturating a primitive or jet at
+    // its head or leaving it stuck.
+    fn apply_stuck<B: Allocator<T, V>>(
+        &self,
+        function: Value<T, V>,
+        argument: Thunk<T, V>,
+        erased: bool,
+        level: usize,
+    ) -> Value<T, V>
+    where
+        U: Definitions<T, V, B>,
+    {
+        let (head, mut spine) = match function {
+            Value::Neutral { head, spine } => (head, spine),
+            Value::Primitive(primitive) => (Head::Primitive(primitive), vec![]),
+            function => (Head::Stuck(Box::new(function)), vec![]),
+        };
+        spine.push((argument, erased));
+
+        match head {
+            Head::Primitive(primitive)
+                if spine.iter().filter(|(_, erased)| !erased).count() == primitive.arity() =>
+            {
+                self.apply_primitive(primitive, spine, level)
+            }
+            Head::Reference(reference)
+                if self.mode == Mode::Computational
+                    && self.definitions.jet_arity(&reference)
+                        == Some(spine.iter().filter(|(_, erased)| !erased).count()) =>
+            {
+                self.apply_jet(reference, spine, level)
+            }
+            head => Value::Neutral { head, spine },
+        }
+    }
+
+    // Primitives receive the erased normal forms of their arguments as terms, as they do from
+    // `normalize_in`. A primitive that declines is left applied, and so is a failed one, whose
+    // error is kept for the caller.
+    fn apply_primitive<B: Allocator<T, V>>(
+        &self,
+        primitive: V,
+        spine: Vec<(Thunk<T, V>, bool)>,
+        level: usize,
+    ) -> Value<T, V>
+    where
+        U: Definitions<T, V, B>,
+    {
+        let quote_arguments = || {
+            spine
+                .iter()
+                .filter(|(_, erased)| !erased)
+                .map(|(argument, _)| match self.mode {
+                    Mode::Computational => self.quote(self.force(argument.clone(), level), level),
+                    // Thunks forced during conversion hold values that keep erasure and types, so
+                    // the argument is evaluated again computationally.
+                    Mode::Conversion => {
+                        let evaluator = Evaluator {
+                            definitions: self.definitions,
+                            alloc: self.alloc,
+                            mode: Mode::Computational,
+                            memo: self.memo,
+                            jet: self.jet,
+                            error: RefCell::new(None),
+                        };
+                        let code = Code::from_term(&self.substitute_thunk(argument, level));
+                        let value = evaluator.eval(&code, &Env::identity(level), level);
+                        let argument = evaluator.quote(value, level);
+                        if let Some(error) = evaluator.error.into_inner() {
+                            self.fail(error);
+                        }
+                        argument
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        match primitive.apply(quote_arguments(), self.alloc) {
+            Some(Ok(result)) => self.eval(&Code::from_term(&result), &Env::identity(level), level),
+            None => Value::Neutral {
+                head: Head::Stuck(Box::new(Value::Primitive(primitive))),
+                spine,
+            },
+            Some(Err(error)) => {
+                // The arguments are quoted again to report the application, so that only failures
+                // pay for it.
+                let alloc = self.alloc;
+                let term = quote_arguments().into_iter().fold(
+                    Term::Primitive(primitive.clone()),
+                    |function, argument| Term::Apply {
+                        function: alloc.alloc(function),
+                        argument: alloc.alloc(argument),
+                        erased: false,
+                    },
+                );
+                self.fail(NormalizationError::PrimitiveFailed(
+                    error,
+                    Box::new(Redex {
+                        term,
+                        path: None,
+                        definition: None,
+                    }),
+                ));
+                Value::Neutral {
+                    head: Head::Stuck(Box::new(Value::Primitive(primitive))),
+                    spine,
+                }
+            }
+        }
+    }
+
+    // Jets receive the erased normal forms of their arguments, as they do from `normalize_in`. A
+    // jet that declines or fails is left applied, to be unfolded when it's read back, and a failure
+    // is kept for the caller.
+    fn apply_jet<B: Allocator<T, V>>(
+        &self,
+        reference: T,
+        spine: Vec<(Thunk<T, V>, bool)>,
+        level: usize,
+    ) -> Value<T, V>
+    where
+        U: Definitions<T, V, B>,
+    {
+        let arguments = spine
+            .iter()
+            .filter(|(_, erased)| !erased)
+            .map(|(argument, _)| self.quote(self.force(argument.clone(), level), level))
+            .collect();
+
+        match (self.jet)(&reference, arguments) {
+            Some(Ok(result)) => self.eval(&Code::from_term(&result), &Env::identity(level), level),
+            Some(Err(error)) => {
+                self.fail(error);
+                Value::Neutral {
+                    head: Head::Reference(reference),
+                    spine,
+                }
+            }
+            None => Value::Neutral {
+                head: Head::Reference(reference),
+                spine,
+            },
+        }
+    }
+
+    fn fail(&self, error: NormalizationError<T, V, A>) {
+        self.error.borrow_mut().get_or_insert(error);
+    }
+
+    fn quote<B: Allocator<T, V>>(&self, value: Value<T, V>, level: usize) -> Term<T, V, A>
+    where
+        U: Definitions<T, V, B>,
+    {
+        self.read_back(ReadBack::Value(value, level), true)
+    }
+
+    fn substitute_thunk<B: Allocator<T, V>>(
+        &self,
+        thunk: &Thunk<T, V>,
+        level: usize,
+    ) -> Term<T, V, A>
+    where
+        U: Definitions<T, V, B>,
+    {
+        match thunk {
+            Thunk::Variable(variable) => Term::Variable(index(level, *variable)),
+            Thunk::Delayed(delayed) => self.read_back(
+                ReadBack::Code {
+                    code: delayed.code.clone(),
+                    env: delayed.env.clone(),
+                    level,
+                    binders: 0,
+                },
+                true,
+            ),
+        }
+    }
+
+    // Jets left folded during computational evaluation are unfolded on the way, unless
+    // `unfold_jets` is false.
+    fn read_back<B: Allocator<T, V>>(
+        &self,
+        task: ReadBack<T, V>,
+        unfold_jets: bool,
+    ) -> Term<T, V, A>
+    where
+        U: Definitions<T, V, B>,
+    {
+        let alloc = self.alloc;
+        let mut tasks = vec![task];
+        let mut terms = vec![];
+
+        // Children are pushed last first, so they're read back in order.
+        while let Some(task) = tasks.pop() {
+            match task {
+                ReadBack::Value(value, level) => match value {
+                    Value::Lambda { erased, body, env } => {
+                        let body =
+                            self.eval(&body, &env.push(Thunk::Variable(level as Level)), level + 1);
+                        tasks.push(ReadBack::Build(Build::Lambda(erased)));
+                        tasks.push(ReadBack::Value(body, level + 1));
+                    }
+                    Value::Function {
+                        erased,
+                        argument_type,
+                        return_type,
+                        env,
+                    } => {
+                        let env = env
+                            .push(Thunk::Variable(level as Level))
+                            .push(Thunk::Variable(level as Level + 1));
+                        let return_type = self.eval(&return_type, &env, level + 2);
+                        tasks.push(ReadBack::Build(Build::Function(erased)));
+                        tasks.push(ReadBack::Value(return_type, level + 2));
+                        tasks.push(ReadBack::Thunk(argument_type, level));
+                    }
+                    Value::Wrap(term) => {
+                        tasks.push(ReadBack::Build(Build::Wrap));
+                        tasks.push(ReadBack::Thunk(term, level));
+                    }
+                    Value::Frozen(code, env) => tasks.push(ReadBack::Code {
+                        code,
+                        env,
+                        level,
+                        binders: 0,
+                    }),
+                    Value::Universe => terms.push(Term::Universe),
+                    Value::Primitive(primitive) => terms.push(Term::Primitive(primitive)),
+                    Value::Neutral { head, spine } => {
+                        let code = match &head {
+                            Head::Reference(reference)
+                                if unfold_jets && self.mode == Mode::Computational =>
+                            {
+                                self.definition(reference)
+                            }
+                            _ => None,
+                        };
+                        if let Some(code) = code {
+                            let value = self.apply_spine(code, spine, level);
+                            tasks.push(ReadBack::Value(value, level));
+                            continue;
+                        }
+
+                        for (argument, erased) in spine.into_iter().rev() {
+                            tasks.push(ReadBack::Build(Build::Apply(erased)));
+                            tasks.push(ReadBack::Thunk(argument, level));
+                        }
+                        match head {
+                            Head::Variable(variable) => {
+                                terms.push(Term::Variable(index(level, variable)))
+                            }
+                            Head::Reference(reference) => terms.push(Term::Reference(reference)),
+                            Head::Primitive(primitive) => terms.push(Term::Primitive(primitive)),
+                            Head::Stuck(value) => tasks.push(ReadBack::Value(*value, level)),
+                        }
+                    }
+                },
+                ReadBack::Thunk(thunk, level) => {
+                    let value = self.force(thunk, level);
+                    tasks.push(ReadBack::Value(value, level));
+                }
+                ReadBack::Code {
+                    code,
+                    env,
+                    level,
+                    binders,
+                } => {
+                    let child = |code: &Rc<Code<T, V>>, binders| ReadBack::Code {
+                        code: code.clone(),
+                        env: env.clone(),
+                        level,
+                        binders,
+                    };
+
+                    match &*code {
+                        Code::Variable(bound) if *bound < binders => {
+                            terms.push(Term::Variable(Index(*bound)))
+                        }
+                        Code::Variable(free) => match env.get(*free - binders) {
+                            Thunk::Variable(variable) => {
+                                terms.push(Term::Variable(index(level + binders, variable)))
+                            }
+                            Thunk::Delayed(delayed) => tasks.push(ReadBack::Code {
+                                code: delayed.code.clone(),
+                                env: delayed.env.clone(),
+                                level: level + binders,
+                                binders: 0,
+                            }),
+                        },
+                        Code::Lambda { body, erased } => tasks.extend([
+                            ReadBack::Build(Build::Lambda(*erased)),
+                            child(body, binders + 1),
+                        ]),
+                        Code::Apply {
+                            function,
+                            argument,
+                            erased,
+                        } => tasks.extend([
+                            ReadBack::Build(Build::Apply(*erased)),
+                            child(argument, binders),
+                            child(function, binders),
+                        ]),
+                        Code::Put(term) => {
+                            tasks.extend([ReadBack::Build(Build::Put), child(term, binders)])
+                        }
+                        Code::Duplicate { expression, body } => tasks.extend([
+                            ReadBack::Build(Build::Duplicate),
+                            child(body, binders + 1),
+                            child(expression, binders),
+                        ]),
+                        Code::Reference(reference) => {
+                            terms.push(Term::Reference(reference.clone()))
+                        }
+                        Code::Primitive(primitive) => {
+                            terms.push(Term::Primitive(primitive.clone()))
+                        }
+                        Code::Universe => terms.push(Term::Universe),
+                        Code::Function {
+                            argument_type,
+                            return_type,
+                            erased,
+                        } => tasks.extend([
+                            ReadBack::Build(Build::Function(*erased)),
+                            child(return_type, binders + 2),
+                            child(argument_type, binders),
+                        ]),
+                        Code::Annotation {
+                            checked,
+                            expression,
+                            ty,
+                        } => tasks.extend([
+                            ReadBack::Build(Build::Annotation(*checked)),
+                            child(ty, binders),
+                            child(expression, binders),
+                        ]),
+                        Code::Wrap(term) => {
+                            tasks.extend([ReadBack::Build(Build::Wrap), child(term, binders)])
+                        }
+                    }
+                }
+                ReadBack::Build(build) => {
+                    let term = build.term(&mut terms, alloc);
+                    terms.push(term);
+                }
+            }
+        }
+
+        terms.pop().unwrap()
+    }
+
+    fn convertible<B: Allocator<T, V>>(&self, a: Value<T, V>, b: Value<T, V>, level: usize) -> bool
+    where
+        U: Definitions<T, V, B>,
+        T: PartialEq,
+        V: PartialEq,
+    {
+        let mut tree = Conversion::Equal(a, b, level);
+
+        loop {
+            tree = match self.expand(tree) {
+                Conversion::Leaf(leaf) => return leaf,
+                tree => tree,
+            };
+        }
+    }
+
+    // Takes one step on every open comparison in the tree, so a mismatch near the root is found
+    // even when another branch keeps unfolding recursive definitions. The tree is walked with an
+    // explicit stack, since a long spine makes it deep.
+    fn expand<B: Allocator<T, V>>(&self, tree: Conversion<T, V>) -> Conversion<T, V>
+    where
+        U: Definitions<T, V, B>,
+        T: PartialEq,
+        V: PartialEq,
+    {
+        use Conversion::*;
+
+        // A junction whose first branch is being expanded, holding its second, or whose second
+        // branch is being expanded, holding its expanded first.
+        enum Pending<T, V> {
+            Second(bool, Conversion<T, V>),
+            Combine(bool, Conversion<T, V>),
+        }
+
+        let mut pending = vec![];
+        let mut tree = tree;
+
+        loop {
+            let mut expanded = loop {
+                match tree {
+                    Leaf(_) => break tree,
+                    Equal(a, b, level) => break self.compare(a, b, level),
+                    And(data) => {
+                        let (a, b) = *data;
+                        pending.push(Pending::Second(true, b));
+                        tree = a;
+                    }
+                    Or(data) => {
+                        let (a, b) = *data;
+                        pending.push(Pending::Second(false, b));
+                        tree = a;
+                    }
+                }
+            };
+
+            loop {
+                match pending.pop() {
+                    None => return expanded,
+                    // A decided first branch settles the junction without the second.
+                    Some(Pending::Second(conjunction, second)) if matches!(expanded, Leaf(leaf) if leaf != conjunction) =>
+                    {
+                        second.discard();
+                    }
+                    Some(Pending::Second(conjunction, second)) => {
+                        pending.push(Pending::Combine(conjunction, expanded));
+                        tree = second;
+                        break;
+                    }
+                    Some(Pending::Combine(conjunction, first)) => {
+                        expanded = if conjunction {
+                            Conversion::and(first, expanded)
+                        } else {
+                            Conversion::or(first, expanded)
+                        };
+                    }
+                }
+            }
+        }
+    }
+
+    fn compare<B: Allocator<T, V>>(
+        &self,
+        a: Value<T, V>,
+        b: Value<T, V>,
+        level: usize,
+    ) -> Conversion<T, V>
+    where
+        U: Definitions<T, V, B>,
+        T: PartialEq,
+        V: PartialEq,
+    {
+        use Conversion::*;
+
+        let shortcut = match (&a, &b) {
+            (
+                Value::Neutral {
+                    head: Head::Reference(a_reference),
+                    spine: a_spine,
+                },
+                Value::Neutral {
+                    head: Head::Reference(b_reference),
+                    spine: b_spine,
+                },
+            ) if a_reference == b_reference => Some(self.spines(a_spine, b_spine, level)),
+            _ => None,
+        };
+
+        let (a, b) = match (self.unfold(a, level), self.unfold(b, level)) {
+            (Err(a), Err(b)) => (a, b),
+            (a, b) => {
+                let a = a.unwrap_or_else(|a| a);
+                let b = b.unwrap_or_else(|b| b);
+                let unfolded = Equal(a, b, level);
+                return match shortcut {
+                    Some(shortcut) => Conversion::or(shortcut, unfolded),
+                    None => unfolded,
+                };
+            }
+        };
+
+        let variable = Thunk::Variable(level as Level);
+
+        match (a, b) {
+            (
+                Value::Lambda {
+                    erased: a_erased,
+                    body: a_body,
+                    env: a_env,
+                },
+                Value::Lambda {
+                    erased: b_erased,
+                    body: b_body,
+                    env: b_env,
+                },
+            ) => {
+                if a_erased != b_erased {
+                    return Leaf(false);
+                }
+                let a = self.eval(&a_body, &a_env.push(variable.clone()), level + 1);
+                let b = self.eval(&b_body, &b_env.push(variable), level + 1);
+                Equal(a, b, level + 1)
+            }
+            (Value::Lambda { erased, body, env }, other)
+            | (other, Value::Lambda { erased, body, env }) => {
+                let body = self.eval(&body, &env.push(variable.clone()), level + 1);
+                let other = self.apply(other, variable, erased, level + 1);
+                Equal(body, other, level + 1)
+            }
+            (
+                Value::Function {
+                    erased: a_erased,
+                    argument_type: a_argument_type,
+                    return_type: a_return_type,
+                    env: a_env,
+                },
+                Value::Function {
+                    erased: b_erased,
+                    argument_type: b_argument_type,
+                    return_type: b_return_type,
+                    env: b_env,
+                },
+            ) => {
+                if a_erased != b_erased {
+                    return Leaf(false);
+                }
+                let argument = Thunk::Variable(level as Level + 1);
+                let a_env = a_env.push(variable.clone()).push(argument.clone());
+                let b_env = b_env.push(variable).push(argument);
+                Conversion::and(
+                    Equal(
+                        self.force(a_argument_type, level),
+                        self.force(b_argument_type, level),
+                        level,
+                    ),
+                    Equal(
+                        self.eval(&a_return_type, &a_env, level + 2),
+                        self.eval(&b_return_type, &b_env, level + 2),
+                        level + 2,
+                    ),
+                )
+            }
+            (Value::Wrap(a), Value::Wrap(b)) => {
+                Equal(self.force(a, level), self.force(b, level), level)
+            }
+            (Value::Universe, Value::Universe) => Leaf(true),
+            (Value::Primitive(a), Value::Primitive(b)) => Leaf(a == b),
+            (
+                Value::Neutral {
+                    head: a_head,
+                    spine: a_spine,
+                },
+                Value::Neutral {
+                    head: b_head,
+                    spine: b_spine,
+                },
+            ) => {
+                let head = match (a_head, b_head) {
+                    (Head::Variable(a), Head::Variable(b)) => Leaf(a == b),
+                    (Head::Reference(a), Head::Reference(b)) => Leaf(a == b),
+                    (Head::Primitive(a), Head::Primitive(b)) => Leaf(a == b),
+                    (Head::Stuck(a), Head::Stuck(b)) => Equal(*a, *b, level),
+                    _ => Leaf(false),
+                };
+                Conversion::and(head, self.spines(&a_spine, &b_spine, level))
+            }
+            _ => Leaf(false),
+        }
+    }
+
+    fn spines<B: Allocator<T, V>>(
+        &self,
+        a: &[(Thunk<T, V>, bool)],
+        b: &[(Thunk<T, V>, bool)],
+        level: usize,
+    ) -> Conversion<T, V>
+    where
+        U: Definitions<T, V, B>,
+        T: PartialEq,
+        V: PartialEq,
+    {
+        if a.len() != b.len() {
+            return Conversion::Leaf(false);
+        }
+
+        a.iter().zip(b).fold(
+            Conversion::Leaf(true),
+            |tree, ((a, a_erased), (b, b_erased))| {
+                let argument = if a_erased == b_erased {
+                    Conversion::Equal(
+                        self.force(a.clone(), level),
+                        self.force(b.clone(), level),
+                        level,
+                    )
+                } else {
+                    Conversion::Leaf(false)
+                };
+                Conversion::and(tree, argument)
+            },
+        )
+    }
+}
+
+impl<T: Clone, V: Primitives<T> + Clone, A: Allocator<T, V>> Term<T, V, A> {
+    // Normalizes the term by evaluation, producing the same normal form as `normalize_in`.
+    // Arguments are evaluated at most once, and each definition is normalized at most once. Jets
+    // are applied once saturated, as `normalize_in` applies them.
+    pub fn evaluate_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Hash + Eq,
+        A: Reallocate<T, V, B>,
+    {
+        self.evaluate_in_session(alloc, &Session::new(definitions))
+    }
+
+    // Like `evaluate_in` with the session's definitions, but reuses the normal forms of
+    // definitions unfolded by earlier evaluations in the same session.
+    pub fn evaluate_in_session<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        alloc: &A,
+        session: &Session<'_, T, U, V>,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Hash + Eq,
+        A: Reallocate<T, V, B>,
+    {
+        let jet = |name: &T, arguments| session.definitions.apply_jet(name, arguments, alloc);
+        let evaluator = Evaluator {
+            definitions: session.definitions,
+            alloc,
+            mode: Mode::Computational,
+            memo: session,
+            jet: &jet,
+            error: RefCell::new(None),
+        };
+
+        let value = evaluator.eval(&Code::from_term(self), &Env::empty(), 0);
+        let term = evaluator.quote(value, 0);
+
+        match evaluator.error.into_inner() {
+            Some(error) => Err(error),
+            None => {
+                *self = term;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn evaluate<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Hash + Eq,
+        A: Zero + Reallocate<T, V, B>,
+    {
+        let alloc = A::zero();
+        self.evaluate_in(definitions, &alloc)
+    }
+
+    pub fn normalize_with_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        normalizer: Normalizer,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Hash + Eq,
+        A: Reallocate<T, V, B>,
+    {
+        match normalizer {
+            Normalizer::Substitution => self.normalize_in(definitions, alloc),
+            Normalizer::Evaluation => self.evaluate_in(definitions, alloc),
+        }
+    }
+
+    pub(crate) fn convertible_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &self,
+        other: &Self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<bool, NormalizationError<T, V, A>>
+    where
+        T: PartialEq,
+        V: PartialEq,
+    {
+        let evaluator = Evaluator {
+            definitions,
+            alloc,
+            mode: Mode::Conversion,
+            memo: &(),
+            jet: &|_, _| None,
+            error: RefCell::new(None),
+        };
+
+        let a = evaluator.eval(&Code::from_term(self), &Env::empty(), 0);
+        let b = evaluator.eval(&Code::from_term(other), &Env::empty(), 0);
+        let convertible = evaluator.convertible(a, b, 0);
+
+        match evaluator.error.into_inner() {
+            Some(error) => Err(error),
+            None => Ok(convertible),
+        }
+    }
+}
@@ -1,5 +1,7 @@
-use super::{alloc::Allocator, Primitives, Show, Term};
+use super::{alloc::Allocator, Primitives, Show, Term, TypedDefinitions};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     hash::{Hash, Hasher},
     mem::discriminant,
 };
@@ -11,58 +13,327 @@ impl<
     > Hash for Term<T, V, A>
 {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        discriminant(self).hash(state);
+        // Trailing flags are hashed after the children, so they go on the stack first.
+        enum Item<'a, T> {
+            Term(&'a T),
+            Flag(bool),
+        }
 
-        match self {
-            Term::Variable(variable) => variable.hash(state),
-            Term::Lambda { body, erased } => {
-                body.hash(state);
-                erased.hash(state);
-            }
-            Term::Apply {
-                function,
-                argument,
-                erased,
-            } => {
-                function.hash(state);
-                argument.hash(state);
-                erased.hash(state);
-            }
-            Term::Put(term) => {
-                term.hash(state);
-            }
-            Term::Duplicate { expression, body } => {
-                expression.hash(state);
-                body.hash(state);
-            }
-            Term::Reference(reference) => {
-                reference.hash(state);
-            }
-            Term::Primitive(prim) => {
-                prim.hash(state);
-            }
-            Term::Universe => {}
-            Term::Function {
-                argument_type,
-                return_type,
-                erased,
-            } => {
-                argument_type.hash(state);
-                return_type.hash(state);
-                erased.hash(state);
-            }
-            Term::Annotation {
-                checked,
-                expression,
-                ty,
-            } => {
-                checked.hash(state);
-                expression.hash(state);
-                ty.hash(state);
+        let mut stack = vec![Item::Term(self)];
+
+        while let Some(item) = stack.pop() {
+            let term = match item {
+                Item::Term(term) => term,
+                Item::Flag(flag) => {
+                    flag.hash(state);
+                    continue;
+                }
+            };
+
+            discriminant(term).hash(state);
+
+            match term {
+                Term::Variable(variable) => variable.hash(state),
+                Term::Lambda { body, erased } => {
+                    stack.push(Item::Flag(*erased));
+                    stack.push(Item::Term(body));
+                }
+                Term::Apply {
+                    function,
+                    argument,
+                    erased,
+                } => {
+                    stack.push(Item::Flag(*erased));
+                    stack.push(Item::Term(argument));
+                    stack.push(Item::Term(function));
+                }
+                Term::Put(term) => {
+                    stack.push(Item::Term(term));
+                }
+                Term::Duplicate { expression, body } => {
+                    stack.push(Item::Term(body));
+                    stack.push(Item::Term(expression));
+                }
+                Term::Reference(reference) => {
+                    reference.hash(state);
+                }
+                Term::Primitive(prim) => {
+                    prim.hash(state);
+                }
+                Term::Universe => {}
+                Term::Function {
+                    argument_type,
+                    return_type,
+                    erased,
+                } => {
+                    stack.push(Item::Flag(*erased));
+                    stack.push(Item::Term(return_type));
+                    stack.push(Item::Term(argument_type));
+                }
+                Term::Annotation {
+                    checked,
+                    expression,
+                    ty,
+                } => {
+                    checked.hash(state);
+                    stack.push(Item::Term(ty));
+                    stack.push(Item::Term(expression));
+                }
+                Term::Wrap(term) => {
+                    stack.push(Item::Term(term));
+                }
             }
-            Term::Wrap(term) => {
-                term.hash(state);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TermHash(pub u128);
+
+const FNV_OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+// 128-bit FNV-1a with all integers written little-endian and 64 bits wide, so the output
+// does not depend on the platform or on the standard library's hasher.
+struct StableHasher(u128);
+
+impl StableHasher {
+    fn new() -> Self {
+        StableHasher(FNV_OFFSET_BASIS)
+    }
+
+    fn finish_stable(&self) -> TermHash {
+        TermHash(self.0)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0 as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u128;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.write_u8(i as u8);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
+}
+
+impl<T: Hash, V: Hash + Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+    // `references` gives the hash a reference stands for, or `None` to hash it by name.
+    fn stable_hash_into(
+        &self,
+        state: &mut StableHasher,
+        references: &mut impl FnMut(&T) -> Option<TermHash>,
+    ) {
+        use Term::*;
+
+        let mut stack = vec![self];
+
+        while let Some(term) = stack.pop() {
+            match term {
+                Variable(variable) => {
+                    state.write_u8(0);
+                    state.write_usize(variable.0);
+                }
+                Lambda { body, erased } => {
+                    state.write_u8(1);
+                    state.write_u8(*erased as u8);
+                    stack.push(body);
+                }
+                Apply {
+                    function,
+                    argument,
+                    erased,
+                } => {
+                    state.write_u8(2);
+                    state.write_u8(*erased as u8);
+                    stack.push(argument);
+                    stack.push(function);
+                }
+                Put(term) => {
+                    state.write_u8(3);
+                    stack.push(term);
+                }
+                Duplicate { expression, body } => {
+                    state.write_u8(4);
+                    stack.push(body);
+                    stack.push(expression);
+                }
+                Reference(reference) => match references(reference) {
+                    Some(hash) => {
+                        state.write_u8(11);
+                        state.write_u128(hash.0);
+                    }
+                    None => {
+                        state.write_u8(5);
+                        reference.hash(state);
+                    }
+                },
+                Primitive(prim) => {
+                    state.write_u8(6);
+                    prim.hash(state);
+                }
+                Universe => {
+                    state.write_u8(7);
+                }
+                Function {
+                    argument_type,
+                    return_type,
+                    erased,
+                } => {
+                    state.write_u8(8);
+                    state.write_u8(*erased as u8);
+                    stack.push(return_type);
+                    stack.push(argument_type);
+                }
+                Annotation {
+                    checked,
+                    expression,
+                    ty,
+                } => {
+                    state.write_u8(9);
+                    state.write_u8(*checked as u8);
+                    stack.push(ty);
+                    stack.push(expression);
+                }
+                Wrap(term) => {
+                    state.write_u8(10);
+                    stack.push(term);
+                }
             }
         }
     }
+
+    pub fn stable_hash(&self) -> TermHash {
+        let mut state = StableHasher::new();
+        self.stable_hash_into(&mut state, &mut |_| None);
+        state.finish_stable()
+    }
+}
+
+// A stable hash of the term of the definition `name`, if there is one, in which each reference is
+// hashed as the content hash of the definition it names rather than by name. Changing any
+// definition the term reaches changes the hash, but renaming one doesn't. References to undefined
+// names, and those leading back to a definition still being hashed, are hashed by name.
+pub fn content_hash<
+    T: Hash + Eq + Clone,
+    V: Hash + Primitives<T>,
+    A: Allocator<T, V>,
+    D: TypedDefinitions<T, V, A>,
+>(
+    definitions: &D,
+    name: &T,
+) -> Option<TermHash> {
+    definition_hash(definitions, name, &mut HashMap::new())
+}
+
+// `content_hash`, where `memo` holds the hashes found so far, and `None` for names that are
+// undefined or still being hashed.
+pub(crate) fn definition_hash<
+    T: Hash + Eq + Clone,
+    V: Hash + Primitives<T>,
+    A: Allocator<T, V>,
+    D: TypedDefinitions<T, V, A>,
+>(
+    definitions: &D,
+    name: &T,
+    memo: &mut HashMap<T, Option<TermHash>>,
+) -> Option<TermHash> {
+    if let Some(hash) = memo.get(name) {
+        return *hash;
+    }
+    memo.insert(name.clone(), None);
+
+    let definition = definitions.get_typed(name)?;
+    let mut state = StableHasher::new();
+    definition
+        .as_ref()
+        .1
+        .stable_hash_into(&mut state, &mut |reference| {
+            definition_hash(definitions, reference, memo)
+        });
+    let hash = state.finish_stable();
+    memo.insert(name.clone(), Some(hash));
+    Some(hash)
+}
+
+// A stable hash of a set of definitions, covering the name, type, term and opacity of each but not
+// the order they come in.
+pub(crate) fn fingerprint<
+    'a,
+    T: Hash + 'a,
+    V: Hash + Primitives<T> + 'a,
+    A: Allocator<T, V> + 'a,
+>(
+    definitions: impl IntoIterator<Item = (&'a T, &'a (Term<T, V, A>, Term<T, V, A>), bool)>,
+) -> TermHash {
+    let mut hashes: Vec<_> = definitions
+        .into_iter()
+        .map(|(name, (ty, term), opaque)| {
+            let mut state = StableHasher::new();
+            name.hash(&mut state);
+            ty.stable_hash_into(&mut state, &mut |_| None);
+            term.stable_hash_into(&mut state, &mut |_| None);
+            state.write_u8(opaque as u8);
+            state.0
+        })
+        .collect();
+    hashes.sort_unstable();
+
+    let mut state = StableHasher::new();
+    for hash in hashes {
+        state.write_u128(hash);
+    }
+    state.finish_stable()
 }
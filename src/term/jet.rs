@@ -0,0 +1,130 @@
+use std::{cell::RefCell, collections::HashMap, hash::Hash};
+
+use super::{
+    alloc::{Allocator, Reallocate},
+    hash::definition_hash,
+    DefinitionResult, NormalizationError, Path, Primitives, Redex, Show, Term, TermHash,
+    TypedDefinitions,
+};
+
+// A native implementation of a definition, computing what unfolding it would. A jet works on
+// decoded values: it reads its arguments, which are in erased normal form, as whatever they encode
+// and builds the encoding of the result. It is registered under the content hash of the term it
+// implements, so it is never used once that definition, or any definition it refers to, differs
+// from the ones it was written against, and the definition remains its specification.
+pub trait Jet<T, V: Primitives<T>> {
+    // The number of non-erased arguments the jet is applied to at once.
+    fn arity(&self) -> usize;
+
+    // Returns `None` if the arguments can't be decoded, for instance because they contain free
+    // variables, in which case the definition is unfolded instead.
+    fn apply<A: Allocator<T, V>>(
+        &self,
+        arguments: &[Term<T, V, A>],
+        alloc: &A,
+    ) -> Option<Term<T, V, A>>;
+}
+
+// Typed definitions with jets attached by the content hash of their terms (see
+// `Term::content_hash_in`). A reference contributes the hash of the definition it names, so the
+// hash pins both the body of a definition and everything it refers to.
+//
+// With `cross_check` set, every result of a jet is compared with the normal form reached by
+// unfolding the definition instead, and a difference is reported as
+// `NormalizationError::JetMismatch`. That costs more than the jet saves, so it is meant for tests.
+pub struct JetDefinitions<T, D, J> {
+    pub definitions: D,
+    pub jets: HashMap<TermHash, J>,
+    pub cross_check: bool,
+    hashes: RefCell<HashMap<T, Option<TermHash>>>,
+}
+
+impl<T, D, J> JetDefinitions<T, D, J> {
+    pub fn new(definitions: D, jets: HashMap<TermHash, J>) -> Self {
+        JetDefinitions {
+            definitions,
+            jets,
+            cross_check: false,
+            hashes: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn cross_checked(mut self) -> Self {
+        self.cross_check = true;
+        self
+    }
+
+    // The jet for the current term of a definition. Hashes are computed once per name.
+    fn jet<V: Primitives<T> + Hash, A: Allocator<T, V>>(&self, name: &T) -> Option<(TermHash, &J)>
+    where
+        T: Hash + Eq + Clone,
+        D: TypedDefinitions<T, V, A>,
+    {
+        let hash = definition_hash(&self.definitions, name, &mut self.hashes.borrow_mut());
+        hash.and_then(|hash| self.jets.get(&hash).map(|jet| (hash, jet)))
+    }
+}
+
+impl<
+        T: Hash + Eq + Clone + Show,
+        V: Primitives<T> + Hash + PartialEq + Show,
+        A: Allocator<T, V>,
+        D: TypedDefinitions<T, V, A>,
+        J: Jet<T, V>,
+    > TypedDefinitions<T, V, A> for JetDefinitions<T, D, J>
+{
+    fn get_typed(&self, name: &T) -> Option<DefinitionResult<'_, (Term<T, V, A>, Term<T, V, A>)>> {
+        self.definitions.get_typed(name)
+    }
+
+    fn is_opaque(&self, name: &T) -> bool {
+        self.definitions.is_opaque(name)
+    }
+
+    fn jet_arity(&self, name: &T) -> Option<usize> {
+        self.jet(name).map(|(_, jet)| jet.arity())
+    }
+
+    fn apply_jet<B: Reallocate<T, V, A>>(
+        &self,
+        name: &T,
+        arguments: Vec<Term<T, V, B>>,
+        alloc: &B,
+    ) -> Option<Result<Term<T, V, B>, NormalizationError<T, V, B>>>
+    where
+        T: Clone,
+        V: Clone,
+    {
+        let (hash, jet) = self.jet(name)?;
+        let result = jet.apply(&arguments, alloc)?;
+
+        if self.cross_check {
+            let mut expected =
+                arguments
+                    .into_iter()
+                    .fold(Term::Reference(name.clone()), |function, argument| {
+                        Term::Apply {
+                            function: alloc.alloc(function),
+                            argument: alloc.alloc(argument),
+                            erased: false,
+                        }
+                    });
+            let application = alloc.copy(&expected);
+            if let Err(error) = expected.normalize_in(&self.definitions, alloc) {
+                return Some(Err(error));
+            }
+            if !expected.equals(&result) {
+                return Some(Err(NormalizationError::JetMismatch(
+                    hash,
+                    Box::new(Redex {
+                        term: application,
+                        path: Some(Path::root()),
+                        definition: Some(name.clone()),
+                    }),
+                )));
+            }
+        }
+
+        Some(Ok(result))
+    }
+}
@@ -1,57 +1,86 @@
 use std::convert::Infallible;
 
-use super::{Primitives, Term};
+use super::{
+    alloc::{Frame, Leaf},
+    Primitives, Term,
+};
 
-impl<T, V: Primitives<T>> Term<T, V> {
+impl<T: Clone, V: Primitives<T> + Clone> Term<T, V> {
     pub fn try_map_primitive<U: Primitives<T>, E, F: Fn(V) -> Result<U, E> + Clone>(
         self,
         f: F,
     ) -> Result<Term<T, U>, E> {
         use Term::*;
 
-        Ok(match self {
-            Variable(var) => Variable(var),
-            Lambda { body, erased } => Lambda {
-                body: Box::new(body.try_map_primitive(f)?),
-                erased,
-            },
-            Primitive(primitive) => Primitive(f(primitive)?),
-            Apply {
-                function,
-                argument,
-                erased,
-            } => Apply {
-                function: Box::new(function.try_map_primitive(f.clone())?),
-                argument: Box::new(argument.try_map_primitive(f)?),
-                erased,
-            },
-            Put(term) => Put(Box::new(term.try_map_primitive(f)?)),
-            Duplicate { expression, body } => Duplicate {
-                expression: Box::new(expression.try_map_primitive(f.clone())?),
-                body: Box::new(body.try_map_primitive(f)?),
-            },
-            Reference(reference) => Reference(reference),
-            Universe => Universe,
-            Function {
-                argument_type,
-                return_type,
-                erased,
-            } => Function {
-                argument_type: Box::new(argument_type.try_map_primitive(f.clone())?),
-                return_type: Box::new(return_type.try_map_primitive(f)?),
-                erased,
-            },
-            Annotation {
-                checked,
-                expression,
-                ty,
-            } => Annotation {
-                expression: Box::new(expression.try_map_primitive(f.clone())?),
-                ty: Box::new(ty.try_map_primitive(f)?),
-                checked,
-            },
-            Wrap(term) => Wrap(Box::new(term.try_map_primitive(f)?)),
-        })
+        // As in `try_map_reference_in`, children are mapped before their parent.
+        let mut stack = vec![Frame::Map(self)];
+        let mut mapped: Vec<Term<T, U>> = vec![];
+
+        while let Some(frame) = stack.pop() {
+            let mut this = match frame {
+                Frame::Map(term) => {
+                    match term.into_leaf() {
+                        Ok(Leaf::Reference(reference)) => mapped.push(Reference(reference)),
+                        Ok(Leaf::Primitive(primitive)) => mapped.push(Primitive(f(primitive)?)),
+                        Err(mut term) => {
+                            let children = term.take_children_in_order();
+                            stack.push(Frame::Rebuild(term));
+                            stack.extend(children.into_iter().rev().map(Frame::Map));
+                        }
+                    }
+                    continue;
+                }
+                Frame::Rebuild(term) => term,
+            };
+
+            let mut pop = || Box::new(mapped.pop().unwrap());
+
+            let term = match &mut this {
+                Variable(var) => Variable(*var),
+                Universe => Universe,
+                Lambda { erased, .. } => Lambda {
+                    body: pop(),
+                    erased: *erased,
+                },
+                Put(_) => Put(pop()),
+                Wrap(_) => Wrap(pop()),
+                Apply { erased, .. } => {
+                    let argument = pop();
+                    Apply {
+                        function: pop(),
+                        argument,
+                        erased: *erased,
+                    }
+                }
+                Duplicate { .. } => {
+                    let body = pop();
+                    Duplicate {
+                        expression: pop(),
+                        body,
+                    }
+                }
+                Function { erased, .. } => {
+                    let return_type = pop();
+                    Function {
+                        argument_type: pop(),
+                        return_type,
+                        erased: *erased,
+                    }
+                }
+                Annotation { checked, .. } => {
+                    let ty = pop();
+                    Annotation {
+                        expression: pop(),
+                        ty,
+                        checked: *checked,
+                    }
+                }
+                Reference(_) | Primitive(_) => unreachable!(),
+            };
+            mapped.push(term);
+        }
+
+        Ok(mapped.pop().unwrap())
     }
 
     pub fn map_primitive<U: Primitives<T>, F: Clone + Fn(V) -> U>(self, f: F) -> Term<T, U> {
@@ -1,11 +1,11 @@
 use std::convert::Infallible;
 
 use super::{
-    alloc::{Allocator, IntoInner, Zero},
+    alloc::{Allocator, Frame, Leaf, Zero},
     Primitives, Term,
 };
 
-impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+impl<T: Clone, V: Primitives<T> + Clone, A: Allocator<T, V>> Term<T, V, A> {
     pub fn try_map_reference_in<U, E, F: Fn(T) -> Result<Term<U, V, A>, E> + Clone>(
         self,
         f: F,
@@ -17,65 +17,75 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
     {
         use Term::*;
 
-        Ok(match self {
-            Variable(var) => Variable(var),
-            Lambda { body, erased } => Lambda {
-                body: alloc.alloc(body.into_inner().try_map_reference_in(f, alloc)?),
-                erased,
-            },
-            Primitive(primitive) => Primitive(primitive),
-            Apply {
-                function,
-                argument,
-                erased,
-            } => Apply {
-                function: alloc.alloc(
-                    function
-                        .into_inner()
-                        .try_map_reference_in(f.clone(), alloc)?,
-                ),
-                argument: alloc.alloc(argument.into_inner().try_map_reference_in(f, alloc)?),
-                erased,
-            },
-            Put(term) => Put(alloc.alloc(term.into_inner().try_map_reference_in(f, alloc)?)),
-            Duplicate { expression, body } => Duplicate {
-                expression: alloc.alloc(
-                    expression
-                        .into_inner()
-                        .try_map_reference_in(f.clone(), alloc)?,
-                ),
-                body: alloc.alloc(body.into_inner().try_map_reference_in(f, alloc)?),
-            },
-            Reference(reference) => f(reference)?,
-            Universe => Universe,
-            Function {
-                argument_type,
-                return_type,
-                erased,
-            } => Function {
-                argument_type: alloc.alloc(
-                    argument_type
-                        .into_inner()
-                        .try_map_reference_in(f.clone(), alloc)?,
-                ),
-                return_type: alloc.alloc(return_type.into_inner().try_map_reference_in(f, alloc)?),
-                erased,
-            },
-            Annotation {
-                checked,
-                expression,
-                ty,
-            } => Annotation {
-                expression: alloc.alloc(
-                    expression
-                        .into_inner()
-                        .try_map_reference_in(f.clone(), alloc)?,
-                ),
-                ty: alloc.alloc(ty.into_inner().try_map_reference_in(f, alloc)?),
-                checked,
-            },
-            Wrap(term) => Wrap(alloc.alloc(term.into_inner().try_map_reference_in(f, alloc)?)),
-        })
+        // Children are mapped before their parent, which is rebuilt from the last results.
+        let mut stack = vec![Frame::Map(self)];
+        let mut mapped: Vec<Term<U, V, A>> = vec![];
+
+        while let Some(frame) = stack.pop() {
+            let mut this = match frame {
+                Frame::Map(term) => {
+                    match term.into_leaf() {
+                        Ok(Leaf::Reference(reference)) => mapped.push(f(reference)?),
+                        Ok(Leaf::Primitive(primitive)) => mapped.push(Primitive(primitive)),
+                        Err(mut term) => {
+                            let children = term.take_children_in_order();
+                            stack.push(Frame::Rebuild(term));
+                            stack.extend(children.into_iter().rev().map(Frame::Map));
+                        }
+                    }
+                    continue;
+                }
+                Frame::Rebuild(term) => term,
+            };
+
+            let mut pop = || alloc.alloc(mapped.pop().unwrap());
+
+            let term = match &mut this {
+                Variable(var) => Variable(*var),
+                Universe => Universe,
+                Lambda { erased, .. } => Lambda {
+                    body: pop(),
+                    erased: *erased,
+                },
+                Put(_) => Put(pop()),
+                Wrap(_) => Wrap(pop()),
+                Apply { erased, .. } => {
+                    let argument = pop();
+                    Apply {
+                        function: pop(),
+                        argument,
+                        erased: *erased,
+                    }
+                }
+                Duplicate { .. } => {
+                    let body = pop();
+                    Duplicate {
+                        expression: pop(),
+                        body,
+                    }
+                }
+                Function { erased, .. } => {
+                    let return_type = pop();
+                    Function {
+                        argument_type: pop(),
+                        return_type,
+                        erased: *erased,
+                    }
+                }
+                Annotation { checked, .. } => {
+                    let ty = pop();
+                    Annotation {
+                        expression: pop(),
+                        ty,
+                        checked: *checked,
+                    }
+                }
+                Reference(_) | Primitive(_) => unreachable!(),
+            };
+            mapped.push(term);
+        }
+
+        Ok(mapped.pop().unwrap())
     }
 
     pub fn map_reference_in<U, F: Clone + Fn(T) -> Term<U, V, A>>(
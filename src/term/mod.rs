@@ -1,27 +1,51 @@
 use std::fmt::Debug;
 
 pub mod alloc;
-use alloc::{Allocator, IntoInner, System, Zero};
+use alloc::{Allocator, System, Zero};
+mod boxes;
+mod cache;
+pub use cache::{
+    CacheStatistics, ConcurrentEqualityCache, EqualityCache, FileCache, InstrumentedCache,
+    LruCache, MapCache, NullCache, SharedCache,
+};
+mod complexity;
+pub use complexity::Complexity;
 mod eq;
-pub use eq::{EqualityCache, MapCache, NullCache};
+pub use eq::{Equivalence, Mismatch};
+mod evaluate;
+pub use evaluate::{Normalizer, Session};
 mod hash;
+pub(crate) use hash::fingerprint;
+pub use hash::{content_hash, TermHash};
 mod index;
+mod jet;
+pub use jet::{Jet, JetDefinitions};
 mod map_primitive;
 mod map_reference;
+#[cfg(feature = "native")]
+pub mod native;
 mod normalize;
 #[cfg(feature = "parser")]
 mod parse;
+mod path;
+pub use path::{Direction, Path};
 mod serde_impls;
 mod show;
 mod stratified;
+mod usage;
+pub use usage::{BinderKind, Usage, Use};
 
-pub use crate::analysis::{AnalysisError, DefinitionResult, Definitions, TypedDefinitions};
-pub use normalize::NormalizationError;
+pub use crate::analysis::{
+    AnalysisError, DefinitionResult, Definitions, OpaqueDefinitions, TypedDefinitions,
+};
+pub use normalize::{
+    NormalizationError, NormalizationStatistics, Redex, Reduction, ReductionStrategy, Rule,
+};
 #[cfg(feature = "parser")]
-pub use parse::{parse, typed, untyped, ParseError, Referent};
+pub use parse::{parse, typed, untyped, ParseError, PrimitiveParser, Referent};
 use serde::{Deserialize, Serialize};
-pub(crate) use show::debug_reference;
-pub use show::Show;
+pub(crate) use show::{debug_optional_reference, debug_reference, debug_references};
+pub use show::{Named, Show};
 pub use stratified::{StratificationError, Stratified};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
@@ -37,14 +61,27 @@ impl Show for None {
     }
 }
 
+pub type PrimitiveError = Box<dyn std::error::Error + Send + Sync>;
+
 pub trait Primitives<T>: Sized {
     fn ty<A: Allocator<T, Self>>(&self, alloc: &A) -> Term<T, Self, A>;
 
+    // The number of arguments the primitive is applied to at once. Until then it is left
+    // partially applied. Erased arguments aren't counted or passed. A primitive of arity zero is a
+    // constant, which is never applied.
+    fn arity(&self) -> usize {
+        1
+    }
+
+    // Applies the primitive to exactly `arity` arguments, each in erased normal form. It declines
+    // with `None` when an argument isn't a value it computes with, such as a variable, and the
+    // application is left stuck. An error is reported by normalization as
+    // `NormalizationError::PrimitiveFailed`.
     fn apply<A: Allocator<T, Self>>(
         &self,
-        argument: &Term<T, Self, A>,
+        arguments: Vec<Term<T, Self, A>>,
         alloc: &A,
-    ) -> Term<T, Self, A>
+    ) -> Option<Result<Term<T, Self, A>, PrimitiveError>>
     where
         Self: Sized;
 }
@@ -54,7 +91,11 @@ impl<T> Primitives<T> for None {
         panic!()
     }
 
-    fn apply<A: Allocator<T, Self>>(&self, _: &Term<T, Self, A>, _: &A) -> Term<T, Self, A> {
+    fn apply<A: Allocator<T, Self>>(
+        &self,
+        _: Vec<Term<T, Self, A>>,
+        _: &A,
+    ) -> Option<Result<Term<T, Self, A>, PrimitiveError>> {
         panic!()
     }
 }
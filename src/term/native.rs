@@ -0,0 +1,606 @@
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "parser")]
+use super::PrimitiveParser;
+use super::{alloc::Allocator, Index, PrimitiveError, Primitives, Term};
+
+// A ready-made set of primitives for native integers, byte strings and UTF-8 strings.
+//
+// Values have a primitive type, which is itself a primitive whose type is the universe. Operations
+// are curried functions over those types. Comparisons return the `Bool` encoding and lengths the
+// `Nat` encoding of the standard prelude, so the types of operations refer to definitions named
+// `Bool` and `Nat`, and checking them needs those definitions (along with `true`, `false`, `zero`
+// and `succ`, which the types of `Bool` and `Nat` mention). The results themselves are closed
+// terms that don't depend on any definition.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Native {
+    Integer(Integer),
+    Bytes(Vec<u8>),
+    String(String),
+    Type(NativeType),
+    Operation(Operation),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IntegerType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+}
+
+// An integer of one of the fixed-width types, which always lies within the range of its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedInteger")]
+pub struct Integer {
+    ty: IntegerType,
+    value: i128,
+}
+
+#[derive(Deserialize)]
+struct UncheckedInteger {
+    ty: IntegerType,
+    value: i128,
+}
+
+impl TryFrom<UncheckedInteger> for Integer {
+    type Error = String;
+
+    fn try_from(integer: UncheckedInteger) -> Result<Self, Self::Error> {
+        Integer::new(integer.ty, integer.value).ok_or_else(|| {
+            format!(
+                "{} is out of range for {}",
+                integer.value,
+                integer.ty.name()
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NativeType {
+    Integer(IntegerType),
+    Bytes,
+    String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Operation {
+    Integer(IntegerType, IntegerOperation),
+    Bytes(SequenceOperation),
+    String(SequenceOperation),
+}
+
+// Arithmetic fails rather than wrapping when the result is out of range, and division and
+// remainder fail when dividing by zero. Division rounds towards zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IntegerOperation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+// The length of a string counts its characters, and the length of a byte string its bytes. Either
+// is read back as a `Nat`, which fails past `MAX_LENGTH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SequenceOperation {
+    Concat,
+    Length,
+    Equal,
+}
+
+impl IntegerType {
+    pub const ALL: [IntegerType; 8] = [
+        IntegerType::U8,
+        IntegerType::U16,
+        IntegerType::U32,
+        IntegerType::U64,
+        IntegerType::I8,
+        IntegerType::I16,
+        IntegerType::I32,
+        IntegerType::I64,
+    ];
+
+    pub fn min(&self) -> i128 {
+        use IntegerType::*;
+
+        match self {
+            U8 | U16 | U32 | U64 => 0,
+            I8 => i8::MIN as i128,
+            I16 => i16::MIN as i128,
+            I32 => i32::MIN as i128,
+            I64 => i64::MIN as i128,
+        }
+    }
+
+    pub fn max(&self) -> i128 {
+        use IntegerType::*;
+
+        match self {
+            U8 => u8::MAX as i128,
+            U16 => u16::MAX as i128,
+            U32 => u32::MAX as i128,
+            U64 => u64::MAX as i128,
+            I8 => i8::MAX as i128,
+            I16 => i16::MAX as i128,
+            I32 => i32::MAX as i128,
+            I64 => i64::MAX as i128,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        use IntegerType::*;
+
+        match self {
+            U8 => "u8",
+            U16 => "u16",
+            U32 => "u32",
+            U64 => "u64",
+            I8 => "i8",
+            I16 => "i16",
+            I32 => "i32",
+            I64 => "i64",
+        }
+    }
+}
+
+impl Integer {
+    // Returns `None` if the value is out of the range of the type.
+    pub fn new(ty: IntegerType, value: i128) -> Option<Self> {
+        if value < ty.min() || value > ty.max() {
+            None
+        } else {
+            Some(Integer { ty, value })
+        }
+    }
+
+    pub fn ty(&self) -> IntegerType {
+        self.ty
+    }
+
+    pub fn value(&self) -> i128 {
+        self.value
+    }
+}
+
+macro_rules! integer_from {
+    ($($native:ty => $ty:ident),*) => {
+        $(
+            impl From<$native> for Integer {
+                fn from(value: $native) -> Self {
+                    Integer {
+                        ty: IntegerType::$ty,
+                        value: value as i128,
+                    }
+                }
+            }
+
+            impl From<$native> for Native {
+                fn from(value: $native) -> Self {
+                    Native::Integer(value.into())
+                }
+            }
+        )*
+    };
+}
+
+integer_from!(
+    u8 => U8, u16 => U16, u32 => U32, u64 => U64,
+    i8 => I8, i16 => I16, i32 => I32, i64 => I64
+);
+
+impl From<&str> for Native {
+    fn from(value: &str) -> Self {
+        Native::String(value.to_owned())
+    }
+}
+
+impl From<String> for Native {
+    fn from(value: String) -> Self {
+        Native::String(value)
+    }
+}
+
+impl From<Vec<u8>> for Native {
+    fn from(value: Vec<u8>) -> Self {
+        Native::Bytes(value)
+    }
+}
+
+impl IntegerOperation {
+    pub const ALL: [IntegerOperation; 10] = [
+        IntegerOperation::Add,
+        IntegerOperation::Subtract,
+        IntegerOperation::Multiply,
+        IntegerOperation::Divide,
+        IntegerOperation::Remainder,
+        IntegerOperation::Equal,
+        IntegerOperation::Less,
+        IntegerOperation::LessOrEqual,
+        IntegerOperation::Greater,
+        IntegerOperation::GreaterOrEqual,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        use IntegerOperation::*;
+
+        match self {
+            Add => "add",
+            Subtract => "subtract",
+            Multiply => "multiply",
+            Divide => "divide",
+            Remainder => "remainder",
+            Equal => "equal",
+            Less => "less",
+            LessOrEqual => "less_or_equal",
+            Greater => "greater",
+            GreaterOrEqual => "greater_or_equal",
+        }
+    }
+
+    fn is_comparison(&self) -> bool {
+        use IntegerOperation::*;
+
+        matches!(self, Equal | Less | LessOrEqual | Greater | GreaterOrEqual)
+    }
+}
+
+impl SequenceOperation {
+    pub const ALL: [SequenceOperation; 3] = [
+        SequenceOperation::Concat,
+        SequenceOperation::Length,
+        SequenceOperation::Equal,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        use SequenceOperation::*;
+
+        match self {
+            Concat => "concat",
+            Length => "length",
+            Equal => "equal",
+        }
+    }
+}
+
+impl NativeType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NativeType::Integer(ty) => ty.name(),
+            NativeType::Bytes => "bytes",
+            NativeType::String => "string",
+        }
+    }
+}
+
+impl Operation {
+    pub fn operand(&self) -> NativeType {
+        match self {
+            Operation::Integer(ty, _) => NativeType::Integer(*ty),
+            Operation::Bytes(_) => NativeType::Bytes,
+            Operation::String(_) => NativeType::String,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Integer(_, operation) => operation.name(),
+            Operation::Bytes(operation) | Operation::String(operation) => operation.name(),
+        }
+    }
+
+    // Applies an operation that produces a native value.
+    fn evaluate(&self, arguments: &[Native]) -> Result<Native, PrimitiveError> {
+        use IntegerOperation::*;
+
+        Ok(match (self, arguments) {
+            (Operation::Integer(ty, operation), [Native::Integer(a), Native::Integer(b)]) => {
+                let (a, b) = (a.value, b.value);
+                if matches!(operation, Divide | Remainder) && b == 0 {
+                    return Err(format!("division by zero in {} of {}", self, a).into());
+                }
+                let value = match operation {
+                    Add => a.checked_add(b),
+                    Subtract => a.checked_sub(b),
+                    Multiply => a.checked_mul(b),
+                    Divide => a.checked_div(b),
+                    Remainder => a.checked_rem(b),
+                    _ => unreachable!(),
+                };
+                let integer = value.and_then(|value| Integer::new(*ty, value));
+                Native::Integer(
+                    integer
+                        .ok_or_else(|| format!("{} of {} and {} is out of range", self, a, b))?,
+                )
+            }
+            (Operation::Bytes(SequenceOperation::Concat), [Native::Bytes(a), Native::Bytes(b)]) => {
+                Native::Bytes([a.as_slice(), b.as_slice()].concat())
+            }
+            (
+                Operation::String(SequenceOperation::Concat),
+                [Native::String(a), Native::String(b)],
+            ) => Native::String([a.as_str(), b.as_str()].concat()),
+            _ => unreachable!(),
+        })
+    }
+}
+
+// `/prop \t \f t` or `/prop \t \f f`.
+fn boolean<T: From<&'static str>, A: Allocator<T, Native>>(
+    value: bool,
+    alloc: &A,
+) -> Term<T, Native, A> {
+    let lambda = |body, erased| Term::Lambda {
+        body: alloc.alloc(body),
+        erased,
+    };
+
+    let variable = Term::Variable(Index(if value { 1 } else { 0 }));
+    lambda(lambda(lambda(variable, false), false), true)
+}
+
+// The longest sequence whose length is read back. A `Nat` is unary, so a length takes a term as
+// deep as its value, and anything longer fails instead.
+pub const MAX_LENGTH: usize = 1 << 16;
+
+// `zero` is `/prop \z \s z` and `(succ n)` is `/prop \z \s (s n)`.
+fn natural<T: From<&'static str>, A: Allocator<T, Native>>(
+    value: usize,
+    alloc: &A,
+) -> Result<Term<T, Native, A>, PrimitiveError> {
+    if value > MAX_LENGTH {
+        return Err(format!("length {} is longer than {}", value, MAX_LENGTH).into());
+    }
+
+    let lambda = |body, erased| Term::Lambda {
+        body: alloc.alloc(body),
+        erased,
+    };
+
+    let mut term = lambda(lambda(lambda(Term::Variable(Index(1)), false), false), true);
+    for _ in 0..value {
+        let body = Term::Apply {
+            function: alloc.alloc(Term::Variable(Index(0))),
+            argument: alloc.alloc(term),
+            erased: false,
+        };
+        term = lambda(lambda(lambda(body, false), false), true);
+    }
+    Ok(term)
+}
+
+impl<T: From<&'static str>> Primitives<T> for Native {
+    fn ty<A: Allocator<T, Self>>(&self, alloc: &A) -> Term<T, Self, A> {
+        let native = |ty| Term::Primitive(Native::Type(ty));
+        let function = |argument_type, return_type| Term::Function {
+            argument_type: alloc.alloc(argument_type),
+            return_type: alloc.alloc(return_type),
+            erased: false,
+        };
+
+        match self {
+            Native::Integer(integer) => native(NativeType::Integer(integer.ty)),
+            Native::Bytes(_) => native(NativeType::Bytes),
+            Native::String(_) => native(NativeType::String),
+            Native::Type(_) => Term::Universe,
+            Native::Operation(operation) => {
+                let operand = operation.operand();
+                let result = match operation {
+                    Operation::Integer(_, operation) if operation.is_comparison() => {
+                        Term::Reference("Bool".into())
+                    }
+                    Operation::Bytes(SequenceOperation::Equal)
+                    | Operation::String(SequenceOperation::Equal) => Term::Reference("Bool".into()),
+                    Operation::Bytes(SequenceOperation::Length)
+                    | Operation::String(SequenceOperation::Length) => {
+                        return function(native(operand), Term::Reference("Nat".into()));
+                    }
+                    _ => native(operand),
+                };
+                function(native(operand), function(native(operand), result))
+            }
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Native::Operation(Operation::Bytes(SequenceOperation::Length))
+            | Native::Operation(Operation::String(SequenceOperation::Length)) => 1,
+            Native::Operation(_) => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply<A: Allocator<T, Self>>(
+        &self,
+        arguments: Vec<Term<T, Self, A>>,
+        alloc: &A,
+    ) -> Option<Result<Term<T, Self, A>, PrimitiveError>> {
+        let operation = match self {
+            Native::Operation(operation) => operation,
+            _ => return Some(Err(format!("{} isn't a function", self).into())),
+        };
+
+        // A literal of the wrong type is an error, but anything else that isn't a literal, like
+        // a variable, leaves the operation stuck.
+        let operand = operation.operand();
+        let mut literals = vec![];
+        let mut stuck = false;
+        for argument in &arguments {
+            match argument {
+                Term::Primitive(native) if native.native_type() == Some(operand) => {
+                    literals.push(native.clone())
+                }
+                Term::Primitive(_) => {
+                    return Some(Err(format!(
+                        "{} expects arguments of type {}",
+                        self,
+                        Native::Type(operand)
+                    )
+                    .into()))
+                }
+                _ => stuck = true,
+            }
+        }
+        if stuck {
+            return None;
+        }
+
+        Some(apply_operation(operation, &literals, alloc))
+    }
+}
+
+// Applies an operation to literals of its operand type.
+fn apply_operation<T: From<&'static str>, A: Allocator<T, Native>>(
+    operation: &Operation,
+    arguments: &[Native],
+    alloc: &A,
+) -> Result<Term<T, Native, A>, PrimitiveError> {
+    use SequenceOperation::*;
+
+    Ok(match (operation, arguments) {
+        (Operation::Integer(_, comparison), [Native::Integer(a), Native::Integer(b)])
+            if comparison.is_comparison() =>
+        {
+            let (a, b) = (a.value, b.value);
+            boolean(
+                match comparison {
+                    IntegerOperation::Equal => a == b,
+                    IntegerOperation::Less => a < b,
+                    IntegerOperation::LessOrEqual => a <= b,
+                    IntegerOperation::Greater => a > b,
+                    IntegerOperation::GreaterOrEqual => a >= b,
+                    _ => unreachable!(),
+                },
+                alloc,
+            )
+        }
+        (Operation::Bytes(Length), [Native::Bytes(bytes)]) => natural(bytes.len(), alloc)?,
+        (Operation::String(Length), [Native::String(string)]) => {
+            natural(string.chars().count(), alloc)?
+        }
+        (Operation::Bytes(Equal), [a, b]) | (Operation::String(Equal), [a, b]) => {
+            boolean(a == b, alloc)
+        }
+        _ => Term::Primitive(operation.evaluate(arguments)?),
+    })
+}
+
+impl Native {
+    // The type of a native value, or `None` for types and operations.
+    pub fn native_type(&self) -> Option<NativeType> {
+        match self {
+            Native::Integer(integer) => Some(NativeType::Integer(integer.ty)),
+            Native::Bytes(_) => Some(NativeType::Bytes),
+            Native::String(_) => Some(NativeType::String),
+            Native::Type(_) | Native::Operation(_) => None,
+        }
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}.{}", self.operand().name(), self.name())
+    }
+}
+
+// Integers are written with the suffix of their type, like `3u8`, and strings and byte strings as
+// quoted literals. Types and operations are written as names prefixed with `#`, like `#u8` and
+// `#string.concat`.
+impl Display for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Native::Integer(integer) => write!(f, "{}{}", integer.value, integer.ty.name()),
+            Native::Bytes(bytes) => {
+                write!(f, "b\"")?;
+                for byte in bytes {
+                    write!(f, "{}", std::ascii::escape_default(*byte))?;
+                }
+                write!(f, "\"")
+            }
+            Native::String(string) => write!(f, "{:?}", string),
+            Native::Type(ty) => write!(f, "#{}", ty.name()),
+            Native::Operation(operation) => operation.fmt(f),
+        }
+    }
+}
+
+// Reads back what `Display` writes, except byte strings, which have no literal. A name that starts
+// with a digit is an integer literal only if it ends in a type suffix like `u8`, so `42` and
+// `2fold` stay references.
+#[cfg(feature = "parser")]
+impl<T: From<&'static str>> PrimitiveParser<T> for Native {
+    fn integer(literal: &str) -> Option<Result<Self, String>> {
+        let digits = literal
+            .char_indices()
+            .find(|&(index, c)| !(c.is_ascii_digit() || (index == 0 && c == '-')))
+            .map_or(literal.len(), |(index, _)| index);
+        let (value, suffix) = literal.split_at(digits);
+        let ty = IntegerType::ALL.iter().find(|ty| ty.name() == suffix)?;
+
+        Some(
+            value
+                .parse()
+                .ok()
+                .and_then(|value| Integer::new(*ty, value))
+                .map(Native::Integer)
+                .ok_or_else(|| format!("{} is out of range for {}", value, ty.name())),
+        )
+    }
+
+    fn string(literal: String) -> Result<Self, String> {
+        Ok(Native::String(literal))
+    }
+
+    fn named(name: &str) -> Result<Self, String> {
+        let unknown = || format!("unknown primitive #{}", name);
+        let (operand, operation) = match name.split_once('.') {
+            Some((operand, operation)) => (operand, Some(operation)),
+            None => (name, None),
+        };
+
+        let ty = IntegerType::ALL
+            .iter()
+            .map(|ty| NativeType::Integer(*ty))
+            .chain([NativeType::Bytes, NativeType::String])
+            .find(|ty| ty.name() == operand)
+            .ok_or_else(unknown)?;
+        let operation = match operation {
+            Some(operation) => operation,
+            None => return Ok(Native::Type(ty)),
+        };
+
+        let sequence = || {
+            SequenceOperation::ALL
+                .iter()
+                .find(|sequence| sequence.name() == operation)
+                .copied()
+        };
+        match ty {
+            NativeType::Integer(ty) => IntegerOperation::ALL
+                .iter()
+                .find(|integer| integer.name() == operation)
+                .map(|integer| Operation::Integer(ty, *integer)),
+            NativeType::Bytes => sequence().map(Operation::Bytes),
+            NativeType::String => sequence().map(Operation::String),
+        }
+        .map(Native::Operation)
+        .ok_or_else(unknown)
+    }
+}
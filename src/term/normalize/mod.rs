@@ -1,14 +1,224 @@
-use std::{fmt::Debug, mem::replace};
+use derivative::Derivative;
+use std::{
+    fmt::{self, Debug, Display},
+    hash::Hash,
+    mem::replace,
+};
 
-use super::{alloc::Reallocate, Allocator, Definitions, Index, IntoInner, Primitives, Term, Zero};
+use super::{
+    alloc::{Reallocate, System},
+    debug_optional_reference, Allocator, Definitions, Direction, Index, None, Path, PrimitiveError,
+    Primitives, Show, Term, TermHash, Zero,
+};
+
+mod statistics;
+pub use statistics::NormalizationStatistics;
+use statistics::Recorder;
+mod step;
+pub use step::{Reduction, Rule};
+mod strategy;
+pub use strategy::ReductionStrategy;
 
 #[cfg(test)]
 mod tests;
 
-#[derive(Debug)]
-pub enum NormalizationError {
-    InvalidDuplication,
-    InvalidApplication,
+// Every variant carries the redex where normalization failed. It is boxed so that the results
+// passed around the normalizer stay small.
+#[derive(Derivative)]
+#[derivative(Debug(bound = "T: Show, V: Show"))]
+pub enum NormalizationError<T, V: Primitives<T> = None, A: Allocator<T, V> = System> {
+    InvalidDuplication(Box<Redex<T, V, A>>),
+    // A put in function position, which nothing can reduce.
+    InvalidApplication(Box<Redex<T, V, A>>),
+    PrimitiveFailed(PrimitiveError, Box<Redex<T, V, A>>),
+    // A cross-checked jet, identified by the hash it is registered under, disagreed with the
+    // definition it implements.
+    JetMismatch(TermHash, Box<Redex<T, V, A>>),
+}
+
+// Where normalization failed. The term is a copy of the offending subterm as it stood then, and
+// the path leads to it from the root of the term being normalized, as far as that had been
+// reduced. The definition is the innermost one unfolded into a subterm the failure arose in, which
+// the offending subterm usually comes from. Evaluation doesn't track positions, so it reports neither.
+#[derive(Derivative)]
+#[derivative(Debug(bound = "T: Show, V: Show"))]
+pub struct Redex<T, V: Primitives<T> = None, A: Allocator<T, V> = System> {
+    pub term: Term<T, V, A>,
+    pub path: Option<Path>,
+    #[derivative(Debug(format_with = "debug_optional_reference"))]
+    pub definition: Option<T>,
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Redex<T, V, A> {
+    fn at_root(term: Term<T, V, A>) -> Box<Self> {
+        Box::new(Redex {
+            term,
+            path: Some(Path::root()),
+            definition: None,
+        })
+    }
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> NormalizationError<T, V, A> {
+    pub fn redex(&self) -> &Redex<T, V, A> {
+        use NormalizationError::*;
+
+        match self {
+            InvalidDuplication(redex)
+            | InvalidApplication(redex)
+            | PrimitiveFailed(_, redex)
+            | JetMismatch(_, redex) => redex,
+        }
+    }
+
+    fn redex_mut(&mut self) -> &mut Redex<T, V, A> {
+        use NormalizationError::*;
+
+        match self {
+            InvalidDuplication(redex)
+            | InvalidApplication(redex)
+            | PrimitiveFailed(_, redex)
+            | JetMismatch(_, redex) => redex,
+        }
+    }
+
+    // Places an error raised within a subterm: its path is prefixed with the directions to that
+    // subterm, and it is attributed to `definition` unless something closer was being unfolded.
+    pub(crate) fn within(
+        mut self,
+        directions: impl IntoIterator<Item = Direction>,
+        definition: Option<&T>,
+    ) -> Self
+    where
+        T: Clone,
+    {
+        let redex = self.redex_mut();
+        if let Some(path) = &mut redex.path {
+            let mut directions: Vec<_> = directions.into_iter().collect();
+            directions.extend_from_slice(path.directions());
+            *path = directions.into();
+        }
+        if redex.definition.is_none() {
+            redex.definition = definition.cloned();
+        }
+        self
+    }
+}
+
+impl<T: Show, V: Primitives<T> + Show, A: Allocator<T, V>> Display for NormalizationError<T, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use NormalizationError::*;
+
+        match self {
+            InvalidDuplication(_) => write!(f, "Duplication of a term that is never a put")?,
+            InvalidApplication(_) => write!(f, "Application of a put")?,
+            PrimitiveFailed(error, _) => write!(f, "Primitive failed: {}", error)?,
+            JetMismatch(hash, _) => write!(
+                f,
+                "Jet registered under {:032x} disagrees with its definition",
+                hash.0
+            )?,
+        }
+
+        let redex = self.redex();
+        if let Some(path) = &redex.path {
+            write!(f, " at {}", path)?;
+        }
+        if let Some(definition) = &redex.definition {
+            write!(f, " while unfolding ")?;
+            definition.fmt(f)?;
+        }
+        write!(f, "\n    {}", redex.term.named())
+    }
+}
+
+enum Step<T, V: Primitives<T>, A: Allocator<T, V>> {
+    Keep,
+    Replace(Term<T, V, A>),
+    Reduce(Term<T, V, A>),
+    Descend(Direction),
+    // Fails with the parent as the redex.
+    Fail(Raise<T, V, A>),
+}
+
+type Raise<T, V, A> = fn(Box<Redex<T, V, A>>) -> NormalizationError<T, V, A>;
+
+// The definitions unfolded into the subterms of a spine, each with the depth of the stack and the
+// direction of the frame above the subterm it was unfolded into. A definition is left behind with
+// its subterm, unless the subterm was the function of a redex, whose reduct carries it on. A
+// failure is attributed to the innermost one still around the focus.
+struct Unfolded<T>(Vec<(usize, Option<Direction>, T)>);
+
+impl<T> Unfolded<T> {
+    fn new() -> Self {
+        Unfolded(vec![])
+    }
+
+    fn current<V: Primitives<T>, A: Allocator<T, V>>(
+        &self,
+        stack: &[(Term<T, V, A>, Direction)],
+    ) -> Option<&T> {
+        self.0
+            .iter()
+            .rev()
+            .find(|(depth, direction, _)| {
+                *depth <= stack.len() && (*depth == 0 || Some(stack[*depth - 1].1) == *direction)
+            })
+            .map(|(_, _, name)| name)
+    }
+
+    // Records a definition unfolded at the focus, in place of any unfolded there before.
+    fn enter<V: Primitives<T>, A: Allocator<T, V>>(
+        &mut self,
+        stack: &[(Term<T, V, A>, Direction)],
+        name: T,
+    ) {
+        let depth = stack.len();
+        let direction = stack.last().map(|(_, direction)| *direction);
+        self.0
+            .retain(|(other, other_direction, _)| (*other, *other_direction) != (depth, direction));
+        self.0.push((depth, direction, name));
+    }
+
+    // Called with the frame just popped off `stack` about to take `step`. The definitions
+    // unfolded into its other children are kept while it descends into one of them.
+    fn leave<V: Primitives<T>, A: Allocator<T, V>>(
+        &mut self,
+        stack: &[(Term<T, V, A>, Direction)],
+        step: &Step<T, V, A>,
+    ) {
+        let depth = stack.len();
+        match step {
+            Step::Descend(_) => self.0.retain(|(other, _, _)| *other <= depth + 1),
+            Step::Reduce(_) | Step::Replace(_) => {
+                let carried = self.0.iter().rposition(|(other, direction, _)| {
+                    *other == depth + 1
+                        && matches!(direction, Some(Direction::Function | Direction::Body))
+                });
+                let carried = carried.map(|index| self.0.remove(index).2);
+                self.0.retain(|(other, _, _)| *other <= depth);
+                if let Some(name) = carried {
+                    self.enter(stack, name);
+                }
+            }
+            Step::Keep | Step::Fail(_) => self.0.retain(|(other, _, _)| *other <= depth),
+        }
+    }
+
+    // Called once the frames of the applications saturating the focus have been popped off
+    // `stack`, carrying the innermost definition unfolded into them up to the focus.
+    fn saturate<V: Primitives<T>, A: Allocator<T, V>>(
+        &mut self,
+        stack: &[(Term<T, V, A>, Direction)],
+    ) {
+        let depth = stack.len();
+        let carried = self.0.iter().rposition(|(other, _, _)| *other > depth);
+        let carried = carried.map(|index| self.0.remove(index).2);
+        self.0.retain(|(other, _, _)| *other <= depth);
+        if let Some(name) = carried {
+            self.enter(stack, name);
+        }
+    }
 }
 
 impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
@@ -19,46 +229,48 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
     pub(crate) fn shift_by(&mut self, replaced: Index, by: isize) {
         use Term::*;
 
-        match self {
-            Variable(index) => {
-                if !index.is_below(replaced) {
-                    if by > 0 {
-                        index.0 += by as usize;
-                    } else {
-                        index.0 -= by.abs() as usize;
+        let mut stack = vec![(self, replaced)];
+
+        while let Some((term, replaced)) = stack.pop() {
+            match term {
+                Variable(index) => {
+                    if !index.is_below(replaced) {
+                        if by > 0 {
+                            index.0 += by as usize;
+                        } else {
+                            index.0 -= by.unsigned_abs();
+                        }
                     }
                 }
-            }
-            Lambda { body, .. } => body.shift_by(replaced.child(), by),
-            Apply {
-                function, argument, ..
-            } => {
-                function.shift_by(replaced, by);
-                argument.shift_by(replaced, by);
-            }
-            Put(term) => {
-                term.shift_by(replaced, by);
-            }
-            Duplicate {
-                expression, body, ..
-            } => {
-                expression.shift_by(replaced, by);
-                body.shift_by(replaced.child(), by);
-            }
-            Reference(_) | Primitive(_) | Universe => {}
+                Lambda { body, .. } => stack.push((body, replaced.child())),
+                Apply {
+                    function, argument, ..
+                } => {
+                    stack.push((function, replaced));
+                    stack.push((argument, replaced));
+                }
+                Put(term) => stack.push((term, replaced)),
+                Duplicate {
+                    expression, body, ..
+                } => {
+                    stack.push((expression, replaced));
+                    stack.push((body, replaced.child()));
+                }
+                Reference(_) | Primitive(_) | Universe => {}
 
-            Wrap(term) => term.shift_by(replaced, by),
-            Annotation { expression, ty, .. } => {
-                expression.shift_by(replaced, by);
-                ty.shift_by(replaced, by);
-            }
-            Function {
-                argument_type,
-                return_type,
-                ..
-            } => {
-                argument_type.shift_by(replaced, by);
-                return_type.shift_by(replaced.child().child(), by);
+                Wrap(term) => stack.push((term, replaced)),
+                Annotation { expression, ty, .. } => {
+                    stack.push((expression, replaced));
+                    stack.push((ty, replaced));
+                }
+                Function {
+                    argument_type,
+                    return_type,
+                    ..
+                } => {
+                    stack.push((argument_type, replaced));
+                    stack.push((return_type, replaced.child().child()));
+                }
             }
         }
     }
@@ -77,64 +289,66 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
         term: &Term<T, V, A>,
         alloc: &A,
         shift: bool,
-    ) where
+    ) -> usize
+    where
         T: Clone,
         V: Clone,
     {
         use Term::*;
 
-        match self {
-            Variable(idx) => {
-                if variable == *idx {
-                    *self = alloc.copy(term);
-                } else if idx.is_above(variable) {
-                    if shift {
+        let mut occurrences = 0;
+
+        // Rather than shifting a fresh copy of `term` under every binder, track how many binders
+        // have been entered and shift once when the variable is actually replaced.
+        let mut stack = vec![(self, variable, 0)];
+
+        while let Some((this, variable, binders)) = stack.pop() {
+            match this {
+                Variable(idx) => {
+                    if variable == *idx {
+                        occurrences += 1;
+                        let mut term = alloc.copy(term);
+                        if binders > 0 {
+                            term.shift_top_by(binders);
+                        }
+                        *this = term;
+                    } else if idx.is_above(variable) && shift {
                         *idx = idx.parent();
                     }
                 }
-            }
-            Lambda { body, .. } => {
-                let mut term = alloc.copy(term);
-                term.shift_top();
-                body.substitute_in(variable.child(), &term, alloc, shift);
-            }
-            Apply {
-                function, argument, ..
-            } => {
-                function.substitute_in(variable, term, alloc, shift);
-                argument.substitute_in(variable, term, alloc, shift);
-            }
-            Put(expr) => {
-                expr.substitute_in(variable, term, alloc, shift);
-            }
-            Duplicate {
-                body, expression, ..
-            } => {
-                expression.substitute_in(variable, term, alloc, shift);
-                let mut term = alloc.copy(term);
-                term.shift_top();
-                body.substitute_in(variable.child(), &term, alloc, shift);
-            }
-            Reference(_) | Universe => {}
-            Primitive(_) => todo!(),
+                Lambda { body, .. } => stack.push((body, variable.child(), binders + 1)),
+                Apply {
+                    function, argument, ..
+                } => {
+                    stack.push((function, variable, binders));
+                    stack.push((argument, variable, binders));
+                }
+                Put(expr) => stack.push((expr, variable, binders)),
+                Duplicate {
+                    body, expression, ..
+                } => {
+                    stack.push((expression, variable, binders));
+                    stack.push((body, variable.child(), binders + 1));
+                }
+                Reference(_) | Primitive(_) | Universe => {}
 
-            Wrap(expr) => expr.substitute_in(variable, term, alloc, shift),
-            Annotation { expression, ty, .. } => {
-                expression.substitute_in(variable, term, alloc, shift);
-                ty.substitute_in(variable, term, alloc, shift);
-            }
-            Function {
-                argument_type,
-                return_type,
-                ..
-            } => {
-                argument_type.substitute_in(variable, term, alloc, shift);
-
-                let mut term = alloc.copy(term);
-                term.shift_top_by(2);
-                return_type.substitute_in(variable.child().child(), &term, alloc, shift);
+                Wrap(expr) => stack.push((expr, variable, binders)),
+                Annotation { expression, ty, .. } => {
+                    stack.push((expression, variable, binders));
+                    stack.push((ty, variable, binders));
+                }
+                Function {
+                    argument_type,
+                    return_type,
+                    ..
+                } => {
+                    stack.push((argument_type, variable, binders));
+                    stack.push((return_type, variable.child().child(), binders + 2));
+                }
             }
         }
+
+        occurrences
     }
 
     pub fn substitute_top_in(&mut self, term: &Term<T, V, A>, alloc: &A)
@@ -142,7 +356,7 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
         T: Clone,
         V: Clone,
     {
-        self.substitute_in(Index::top(), term, alloc, true)
+        self.substitute_in(Index::top(), term, alloc, true);
     }
 
     pub(crate) fn substitute_top_in_unshifted(&mut self, term: &Term<T, V, A>, alloc: &A)
@@ -150,7 +364,7 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
         T: Clone,
         V: Clone,
     {
-        self.substitute_in(Index::top(), term, alloc, false)
+        self.substitute_in(Index::top(), term, alloc, false);
     }
 
     pub(crate) fn substitute_function_in(
@@ -181,12 +395,275 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
         self.substitute_in(Index::top(), argument_binding, alloc, false);
     }
 
+    // The primitive at the head of this spine of applications, and the number of non-erased
+    // arguments it is applied to.
+    pub(crate) fn primitive_head(&self) -> Option<(&V, usize)> {
+        let mut arguments = 0;
+        let mut term = self;
+
+        loop {
+            match term {
+                Term::Apply {
+                    function, erased, ..
+                } => {
+                    if !*erased {
+                        arguments += 1;
+                    }
+                    term = function;
+                }
+                Term::Primitive(primitive) => return Some((primitive, arguments)),
+                _ => return None,
+            }
+        }
+    }
+
+    // Called when a term is finished with its parent frames on the stack. If it is a primitive and
+    // the applications it is the function of saturate it, their frames are popped and the
+    // saturated application is left in `term`. Looking upwards from the primitive means no
+    // application has to walk its spine down to the head to find out whether it applies one.
+    fn saturate(term: &mut Self, stack: &mut Vec<(Self, Direction)>) -> bool {
+        match term {
+            Term::Primitive(primitive) => {
+                let arity = primitive.arity();
+                Self::saturate_with(term, stack, arity)
+            }
+            _ => false,
+        }
+    }
+
+    // Pops the frames of the applications that give `term` `arity` non-erased arguments, and
+    // leaves the saturated application in `term`. Nothing saturates an arity of zero, so a
+    // constant primitive is never applied.
+    fn saturate_with(term: &mut Self, stack: &mut Vec<(Self, Direction)>, arity: usize) -> bool {
+        let mut remaining = arity;
+        if remaining == 0 {
+            return false;
+        }
+
+        for index in (0..stack.len()).rev() {
+            match &stack[index] {
+                (Term::Apply { erased, .. }, Direction::Function) => {
+                    if !*erased {
+                        remaining -= 1;
+                    }
+                    if remaining == 0 {
+                        let mut frames = stack.split_off(index);
+                        *term = Self::reassemble(term.take(), &mut frames);
+                        return true;
+                    }
+                }
+                _ => return false,
+            }
+        }
+
+        false
+    }
+
+    // Applies the saturated primitive at the head of this spine to its normalized arguments, or
+    // returns `None` if it declines them.
+    fn apply_primitive_recorded(
+        &mut self,
+        arguments: Vec<Self>,
+        alloc: &A,
+        recorder: &mut impl Recorder<T>,
+    ) -> Result<Option<Self>, NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+    {
+        let primitive = match self.primitive_head() {
+            Some((primitive, _)) => primitive,
+            None => unreachable!(),
+        };
+
+        let result = match primitive.apply(arguments, alloc) {
+            Some(result) => result.map_err(|error| {
+                NormalizationError::PrimitiveFailed(error, Redex::at_root(alloc.copy(self)))
+            })?,
+            None => return Ok(None),
+        };
+        recorder.primitive(self, &result);
+        Ok(Some(result))
+    }
+
+    // Normalizes the non-erased arguments of this spine of applications in place, from left to
+    // right, and returns copies of them.
+    fn normalize_arguments_recorded<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+        alloc: &A,
+        recorder: &mut impl Recorder<T>,
+    ) -> Result<Vec<Self>, NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        let mut arguments = vec![];
+        let mut depth = 0;
+        let mut term = &mut *self;
+        while let Term::Apply {
+            function,
+            argument,
+            erased,
+        } = term
+        {
+            if !*erased {
+                arguments.push((depth, &mut **argument));
+            }
+            depth += 1;
+            term = function;
+        }
+
+        for (depth, argument) in arguments.into_iter().rev() {
+            let mut stack = vec![];
+            let mut term = argument.take();
+            let result = Self::normalize_spine(&mut term, &mut stack, definitions, alloc, recorder);
+            *argument = Self::reassemble(term, &mut stack);
+            result.map_err(|error| {
+                let mut directions = vec![Direction::Function; depth];
+                directions.push(Direction::Argument);
+                error.within(directions, None)
+            })?;
+        }
+
+        let mut arguments = vec![];
+        let mut term = &*self;
+        while let Term::Apply {
+            function,
+            argument,
+            erased,
+        } = term
+        {
+            if !*erased {
+                arguments.push(alloc.copy(argument));
+            }
+            term = function;
+        }
+        arguments.reverse();
+
+        Ok(arguments)
+    }
+
+    // Applies the jet of the definition at the head of this saturated spine to its normalized
+    // arguments. If the jet declines, the definition is unfolded in place instead, leaving an
+    // application to reduce as usual.
+    fn apply_jet_recorded<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        arguments: Vec<Self>,
+        definitions: &U,
+        alloc: &A,
+        recorder: &mut impl Recorder<T>,
+    ) -> Result<Self, NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        let mut head = &mut *self;
+        while let Term::Apply { function, .. } = head {
+            head = function;
+        }
+        let name = match head {
+            Term::Reference(name) => name,
+            _ => unreachable!(),
+        };
+
+        if let Some(result) = definitions.apply_jet(name, arguments, alloc) {
+            let result = result?;
+            recorder.jet(self, &result);
+            return Ok(result);
+        }
+
+        if let Some(definition) = definitions.get(name) {
+            recorder.unfold(name, definition.as_ref());
+            *head = alloc.reallocating_copy(definition.as_ref());
+        }
+        Ok(self.take())
+    }
+
+    fn reassemble(mut term: Self, stack: &mut Vec<(Self, Direction)>) -> Self {
+        while let Some((mut parent, direction)) = stack.pop() {
+            *parent.child_mut(direction).unwrap() = term;
+            term = parent;
+        }
+        term
+    }
+
+    // Locates an error raised at the focus of a spine, relative to the root of the spine.
+    fn locate(
+        error: NormalizationError<T, V, A>,
+        stack: &[(Self, Direction)],
+        unfolded: Option<&T>,
+    ) -> NormalizationError<T, V, A>
+    where
+        T: Clone,
+    {
+        error.within(stack.iter().map(|(_, direction)| *direction), unfolded)
+    }
+
+    fn continue_from(
+        term: &mut Self,
+        stack: &mut Vec<(Self, Direction)>,
+        mut parent: Self,
+        step: Step<T, V, A>,
+        alloc: &A,
+    ) -> Result<bool, NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+    {
+        Ok(match step {
+            Step::Keep => {
+                *term = parent;
+                false
+            }
+            Step::Replace(replacement) => {
+                *term = replacement;
+                false
+            }
+            Step::Reduce(reduct) => {
+                *term = reduct;
+                true
+            }
+            Step::Descend(direction) => {
+                *term = parent.child_mut(direction).unwrap().take();
+                stack.push((parent, direction));
+                true
+            }
+            Step::Fail(error) => {
+                let error = error(Redex::at_root(alloc.copy(&parent)));
+                *term = parent;
+                Err(error)?
+            }
+        })
+    }
+
     pub(crate) fn weak_normalize_in_erased<U: Definitions<T, V, B>, B: Allocator<T, V>>(
         &mut self,
         definitions: &U,
         alloc: &A,
         erase: bool,
-    ) -> Result<(), NormalizationError>
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        let mut stack = vec![];
+        let mut term = self.take();
+        let result = Self::weak_normalize_spine(&mut term, &mut stack, definitions, alloc, erase);
+        *self = Self::reassemble(term, &mut stack);
+        result
+    }
+
+    fn weak_normalize_spine<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        term: &mut Self,
+        stack: &mut Vec<(Self, Direction)>,
+        definitions: &U,
+        alloc: &A,
+        erase: bool,
+    ) -> Result<(), NormalizationError<T, V, A>>
     where
         T: Clone,
         V: Clone,
@@ -194,110 +671,143 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
     {
         use Term::*;
 
-        match self {
-            Reference(binding) => {
-                if let Some(term) = definitions.get(binding).map(|term| {
-                    let mut term = alloc.reallocating_copy(term.as_ref());
-                    term.weak_normalize_in_erased(definitions, alloc, erase)?;
-                    Ok(term)
-                }) {
-                    *self = term?;
-                }
-            }
-            Apply {
-                function,
-                argument,
-                erased,
-            } => {
-                function.weak_normalize_in_erased(definitions, alloc, erase)?;
-                let f = alloc.copy(&*function);
-                match f {
-                    Put(_) => Err(NormalizationError::InvalidApplication)?,
-                    Duplicate { body, expression } => {
-                        let mut argument = alloc.copy_boxed(argument);
-                        argument.shift_top();
-                        let body = alloc.alloc(Apply {
-                            function: body,
-                            argument,
-                            erased: *erased,
-                        });
-                        *self = Duplicate { expression, body };
-                    }
-                    Lambda { mut body, .. } => {
-                        body.substitute_top_in(argument, alloc);
-                        body.weak_normalize_in_erased(definitions, alloc, erase)?;
-                        *self = body.into_inner();
-                    }
-                    Primitive(prim) => {
-                        *self = prim.apply(argument, alloc);
+        let mut unfolded = Unfolded::new();
+
+        loop {
+            let direction = match term {
+                Reference(binding) => {
+                    if let Some(definition) = definitions.get(binding) {
+                        unfolded.enter(stack, binding.clone());
+                        *term = alloc.reallocating_copy(definition.as_ref());
+                        continue;
                     }
-                    _ => {}
+                    None
                 }
-            }
+                Apply { .. } => Some(Direction::Function),
 
-            Put(term) if erase => {
-                term.weak_normalize_in_erased(definitions, alloc, erase)?;
-                *self = replace(term, Term::Universe);
-            }
+                Put(inner) if erase => {
+                    *term = inner.take();
+                    continue;
+                }
 
-            Duplicate { body, expression } if erase => {
-                body.substitute_top_in(expression, alloc);
-                body.weak_normalize_in_erased(definitions, alloc, erase)?;
-                *self = replace(body, Term::Universe);
-            }
+                Duplicate { body, expression } if erase => {
+                    body.substitute_top_in(expression, alloc);
+                    *term = body.take();
+                    continue;
+                }
 
-            Variable(_) | Primitive(_) | Lambda { .. } | Put(_) => {}
+                Variable(_) | Primitive(_) | Lambda { .. } | Put(_) => None,
 
-            Duplicate { body, expression } => {
-                expression.weak_normalize_in_erased(definitions, alloc, erase)?;
+                Duplicate { .. } => Some(Direction::Expression),
 
-                match &mut **expression {
-                    Put(term) => {
-                        body.substitute_top_in(term, alloc);
-                        body.weak_normalize_in_erased(definitions, alloc, erase)?;
-                        *self = replace(body, Term::Universe);
-                    }
-                    Duplicate {
-                        body: sub_body,
-                        expression: sub_expression,
-                    } => {
-                        body.shift(Index::top().child());
-                        let dup = Duplicate {
-                            body: alloc.copy_boxed(body),
-                            expression: alloc.copy_boxed(sub_body),
-                        };
-                        *self = Duplicate {
-                            expression: alloc.alloc(replace(sub_expression, Term::Universe)),
-                            body: alloc.alloc(dup),
-                        };
-                    }
-                    _ => {}
+                Universe | Function { .. } | Wrap(_) => None,
+                Annotation { expression, .. } => {
+                    *term = expression.take();
+                    continue;
                 }
+            };
+
+            if let Some(direction) = direction {
+                let child = term.child_mut(direction).unwrap().take();
+                stack.push((replace(term, child), direction));
+                continue;
             }
 
-            Universe | Function { .. } | Wrap(_) => {}
-            Annotation { expression, .. } => {
-                expression.weak_normalize_in_erased(definitions, alloc, erase)?;
-                *self = replace(expression, Term::Universe);
+            if Self::saturate(term, stack) {
+                let arguments = term
+                    .normalize_arguments_recorded(definitions, alloc, &mut ())
+                    .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?;
+                unfolded.saturate(stack);
+                let result = term
+                    .apply_primitive_recorded(arguments, alloc, &mut ())
+                    .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?;
+                // A declined application is stuck, and its arguments are already normal.
+                if let Some(result) = result {
+                    *term = result;
+                    continue;
+                }
             }
-        }
 
-        Ok(())
+            loop {
+                let (mut parent, direction) = match stack.pop() {
+                    Some(frame) => frame,
+                    None => return Ok(()),
+                };
+                *parent.child_mut(direction).unwrap() = term.take();
+
+                let step = match &mut parent {
+                    Apply {
+                        function,
+                        argument,
+                        erased,
+                    } => match &mut **function {
+                        Put(_) => Step::Fail(NormalizationError::InvalidApplication),
+                        Duplicate { body, expression } => {
+                            let mut argument = argument.take();
+                            argument.shift_top();
+                            let body = alloc.alloc(Apply {
+                                function: alloc.alloc(body.take()),
+                                argument: alloc.alloc(argument),
+                                erased: *erased,
+                            });
+                            Step::Replace(Duplicate {
+                                expression: alloc.alloc(expression.take()),
+                                body,
+                            })
+                        }
+                        Lambda { body, .. } => {
+                            body.substitute_top_in(argument, alloc);
+                            Step::Reduce(body.take())
+                        }
+                        _ => Step::Keep,
+                    },
+                    Duplicate { body, expression } => match &mut **expression {
+                        Put(term) => {
+                            body.substitute_top_in(term, alloc);
+                            Step::Reduce(body.take())
+                        }
+                        Duplicate {
+                            body: sub_body,
+                            expression: sub_expression,
+                        } => {
+                            body.shift(Index::top().child());
+                            let dup = Duplicate {
+                                body: alloc.alloc(body.take()),
+                                expression: alloc.alloc(sub_body.take()),
+                            };
+                            Step::Replace(Duplicate {
+                                expression: alloc.alloc(sub_expression.take()),
+                                body: alloc.alloc(dup),
+                            })
+                        }
+                        _ => Step::Keep,
+                    },
+                    _ => Step::Keep,
+                };
+
+                unfolded.leave(stack, &step);
+                if Self::continue_from(term, stack, parent, step, alloc)
+                    .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?
+                {
+                    break;
+                }
+            }
+        }
     }
 
-    pub(crate) fn extract_from_annotation(self) -> Self {
-        if let Term::Annotation { expression, .. } = self {
-            expression.into_inner().extract_from_annotation()
-        } else {
-            self
+    pub(crate) fn extract_from_annotation(mut self) -> Self {
+        while let Term::Annotation { expression, .. } = &mut self {
+            let expression = expression.take();
+            self = expression;
         }
+        self
     }
 
     pub fn weak_normalize_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
         &mut self,
         definitions: &U,
         alloc: &A,
-    ) -> Result<(), NormalizationError>
+    ) -> Result<(), NormalizationError<T, V, A>>
     where
         T: Clone,
         V: Clone,
@@ -310,7 +820,57 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
         &mut self,
         definitions: &U,
         alloc: &A,
-    ) -> Result<(), NormalizationError>
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        self.normalize_recorded(definitions, alloc, &mut ())
+    }
+
+    pub fn normalize_in_with_statistics<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<NormalizationStatistics<T>, NormalizationError<T, V, A>>
+    where
+        T: Clone + Hash + Eq,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        let mut statistics = NormalizationStatistics::default();
+        self.normalize_recorded(definitions, alloc, &mut statistics)?;
+        Ok(statistics)
+    }
+
+    fn normalize_recorded<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+        alloc: &A,
+        recorder: &mut impl Recorder<T>,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        recorder.start(self);
+
+        let mut stack = vec![];
+        let mut term = self.take();
+        let result = Self::normalize_spine(&mut term, &mut stack, definitions, alloc, recorder);
+        *self = Self::reassemble(term, &mut stack);
+        result
+    }
+
+    fn normalize_spine<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        term: &mut Self,
+        stack: &mut Vec<(Self, Direction)>,
+        definitions: &U,
+        alloc: &A,
+        recorder: &mut impl Recorder<T>,
+    ) -> Result<(), NormalizationError<T, V, A>>
     where
         T: Clone,
         V: Clone,
@@ -318,74 +878,145 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
     {
         use Term::*;
 
-        match self {
-            Reference(binding) => {
-                if let Some(term) = definitions.get(binding).map(|term| {
-                    let mut term = alloc.reallocating_copy(term.as_ref());
-                    term.normalize_in(definitions, alloc)?;
-                    Ok(term)
-                }) {
-                    *self = term?;
+        let mut unfolded = Unfolded::new();
+
+        loop {
+            recorder.visit(stack.len() + 1);
+
+            if let Reference(binding) = term {
+                if let Some(arity) = definitions.jet_arity(binding) {
+                    let name = binding.clone();
+                    if Self::saturate_with(term, stack, arity) {
+                        let arguments = term
+                            .normalize_arguments_recorded(definitions, alloc, recorder)
+                            .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?;
+                        unfolded.saturate(stack);
+                        unfolded.enter(stack, name);
+                        *term = term
+                            .apply_jet_recorded(arguments, definitions, alloc, recorder)
+                            .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?;
+                        continue;
+                    }
                 }
             }
-            Lambda { body, erased, .. } => {
-                body.normalize_in(definitions, alloc)?;
-                if *erased {
-                    body.substitute_top_in(&Term::Variable(Index::top()), alloc);
-                    *self = replace(&mut *body, Universe);
+
+            let direction = match term {
+                Reference(binding) => {
+                    if let Some(definition) = definitions.get(binding) {
+                        recorder.unfold(binding, definition.as_ref());
+                        unfolded.enter(stack, binding.clone());
+                        *term = alloc.reallocating_copy(definition.as_ref());
+                        continue;
+                    }
+                    None
                 }
+                Lambda { .. } => Some(Direction::Body),
+                Put(inner) => {
+                    recorder.put();
+                    *term = inner.take();
+                    continue;
+                }
+                Duplicate { body, expression } => {
+                    let occurrences = body.substitute_in(Index::top(), expression, alloc, true);
+                    recorder.duplication(expression, occurrences);
+                    *term = body.take();
+                    continue;
+                }
+                Apply { .. } => Some(Direction::Function),
+                Variable(_) | Universe | Primitive(_) | Wrap(_) | Function { .. } => None,
+
+                Annotation { expression, ty, .. } => {
+                    recorder.annotation(ty);
+                    *term = expression.take();
+                    continue;
+                }
+            };
+
+            if let Some(direction) = direction {
+                let child = term.child_mut(direction).unwrap().take();
+                stack.push((replace(term, child), direction));
+                continue;
             }
-            Put(term) => {
-                term.normalize_in(definitions, alloc)?;
-                *self = replace(term, Term::Universe);
-            }
-            Duplicate { body, expression } => {
-                body.substitute_top_in(expression, alloc);
-                body.normalize_in(definitions, alloc)?;
-                *self = replace(body, Term::Universe);
+
+            if Self::saturate(term, stack) {
+                let arguments = term
+                    .normalize_arguments_recorded(definitions, alloc, recorder)
+                    .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?;
+                unfolded.saturate(stack);
+                let result = term
+                    .apply_primitive_recorded(arguments, alloc, recorder)
+                    .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?;
+                // A declined application is stuck, and its arguments are already normal.
+                if let Some(result) = result {
+                    *term = result;
+                    continue;
+                }
             }
-            Apply {
-                function,
-                argument,
-                erased,
-            } => {
-                function.normalize_in(definitions, alloc)?;
-                let function = alloc.copy(function);
-                if *erased {
-                    *self = function;
-                } else {
-                    match function {
-                        Put(_) => Err(NormalizationError::InvalidApplication)?,
-                        Primitive(primitive) => {
-                            *self = primitive.apply(argument, alloc);
-                        }
-                        Lambda { mut body, .. } => {
-                            body.substitute_top_in(argument, alloc);
-                            body.normalize_in(definitions, alloc)?;
 
-                            *self = body.into_inner();
+            loop {
+                let (mut parent, direction) = match stack.pop() {
+                    Some(frame) => frame,
+                    None => return Ok(()),
+                };
+                *parent.child_mut(direction).unwrap() = term.take();
+
+                let step = match (&mut parent, direction) {
+                    (Lambda { body, erased }, _) => {
+                        if *erased {
+                            recorder.erased_lambda();
+                            body.substitute_top_in(&Term::Variable(Index::top()), alloc);
+                            // A primitive at the head of the body may be saturated by the
+                            // applications the lambda was the function of.
+                            if body.primitive_head().is_some() {
+                                Step::Reduce(body.take())
+                            } else {
+                                Step::Replace(body.take())
+                            }
+                        } else {
+                            Step::Keep
                         }
-                        _ => {
-                            argument.normalize_in(definitions, alloc)?;
+                    }
+                    (
+                        Apply {
+                            function,
+                            argument,
+                            erased,
+                        },
+                        Direction::Function,
+                    ) => {
+                        if *erased {
+                            recorder.erased_application(argument);
+                            Step::Replace(function.take())
+                        } else {
+                            match &mut **function {
+                                Put(_) => Step::Fail(NormalizationError::InvalidApplication),
+                                Lambda { body, .. } => {
+                                    let occurrences =
+                                        body.substitute_in(Index::top(), argument, alloc, true);
+                                    recorder.beta(argument, occurrences);
+                                    Step::Reduce(body.take())
+                                }
+                                _ => Step::Descend(Direction::Argument),
+                            }
                         }
                     }
-                }
-            }
-            Variable(_) | Universe | Primitive(_) | Wrap(_) | Function { .. } => {}
+                    _ => Step::Keep,
+                };
 
-            Annotation { expression, .. } => {
-                expression.normalize_in(definitions, alloc)?;
-                *self = replace(expression, Term::Universe);
+                unfolded.leave(stack, &step);
+                if Self::continue_from(term, stack, parent, step, alloc)
+                    .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?
+                {
+                    break;
+                }
             }
         }
-
-        Ok(())
     }
 
     pub fn normalize<U: Definitions<T, V, B>, B: Allocator<T, V>>(
         &mut self,
         definitions: &U,
-    ) -> Result<(), NormalizationError>
+    ) -> Result<(), NormalizationError<T, V, A>>
     where
         T: Clone,
         V: Clone,
@@ -395,10 +1026,23 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
         self.normalize_in(definitions, &alloc)
     }
 
+    pub fn normalize_with_statistics<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+    ) -> Result<NormalizationStatistics<T>, NormalizationError<T, V, A>>
+    where
+        T: Clone + Hash + Eq,
+        V: Clone,
+        A: Zero + Reallocate<T, V, B>,
+    {
+        let alloc = A::zero();
+        self.normalize_in_with_statistics(definitions, &alloc)
+    }
+
     pub fn weak_normalize<U: Definitions<T, V, A>>(
         &mut self,
         definitions: &U,
-    ) -> Result<(), NormalizationError>
+    ) -> Result<(), NormalizationError<T, V, A>>
     where
         T: Clone + Debug,
         V: Clone,
@@ -427,6 +1071,6 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
     {
         let alloc = A::zero();
 
-        self.substitute_in(variable, term, &alloc, true)
+        self.substitute_in(variable, term, &alloc, true);
     }
 }
@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    hash::Hash,
+};
+
+use super::super::{Allocator, Primitives, Term};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizationStatistics<T: Hash + Eq> {
+    pub beta_reductions: usize,
+    pub erased_lambdas: usize,
+    pub erased_applications: usize,
+    pub duplications: usize,
+    pub puts: usize,
+    pub unfoldings: HashMap<T, usize>,
+    pub primitive_applications: usize,
+    pub jet_applications: usize,
+    pub max_size: usize,
+    pub max_depth: usize,
+    size: usize,
+}
+
+impl<T: Hash + Eq> Default for NormalizationStatistics<T> {
+    fn default() -> Self {
+        NormalizationStatistics {
+            beta_reductions: 0,
+            erased_lambdas: 0,
+            erased_applications: 0,
+            duplications: 0,
+            puts: 0,
+            unfoldings: HashMap::new(),
+            primitive_applications: 0,
+            jet_applications: 0,
+            max_size: 0,
+            max_depth: 0,
+            size: 0,
+        }
+    }
+}
+
+impl<T: Hash + Eq> NormalizationStatistics<T> {
+    pub fn total_unfoldings(&self) -> usize {
+        self.unfoldings.values().sum()
+    }
+
+    // Every rewrite performed, comparable to the rewrite count reported by `Net::reduce`.
+    pub fn reductions(&self) -> usize {
+        self.beta_reductions
+            + self.erased_lambdas
+            + self.erased_applications
+            + self.duplications
+            + self.puts
+            + self.total_unfoldings()
+            + self.primitive_applications
+            + self.jet_applications
+    }
+
+    fn resize(&mut self, removed: usize, added: usize) {
+        self.size = (self.size + added).saturating_sub(removed);
+        self.max_size = self.max_size.max(self.size);
+    }
+}
+
+impl<T: Hash + Eq + Display> Display for NormalizationStatistics<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} reductions", self.reductions())?;
+        writeln!(f, "    beta: {}", self.beta_reductions)?;
+        writeln!(f, "    erased lambdas: {}", self.erased_lambdas)?;
+        writeln!(f, "    erased applications: {}", self.erased_applications)?;
+        writeln!(f, "    duplications: {}", self.duplications)?;
+        writeln!(f, "    puts: {}", self.puts)?;
+        writeln!(
+            f,
+            "    primitive applications: {}",
+            self.primitive_applications
+        )?;
+        writeln!(f, "    jet applications: {}", self.jet_applications)?;
+        writeln!(f, "    unfoldings: {}", self.total_unfoldings())?;
+
+        let mut unfoldings: Vec<_> = self.unfoldings.iter().collect();
+        unfoldings.sort_by(|(a_name, a), (b_name, b)| {
+            b.cmp(a)
+                .then_with(|| a_name.to_string().cmp(&b_name.to_string()))
+        });
+        for (name, count) in unfoldings {
+            writeln!(f, "        {}: {}", name, count)?;
+        }
+
+        writeln!(f, "max size: {}", self.max_size)?;
+        write!(f, "max depth: {}", self.max_depth)
+    }
+}
+
+// Hooks called by the normalizer at each rewrite. The unit implementation is used when no
+// statistics are requested, so normalization doesn't pay for measuring term sizes.
+pub(crate) trait Recorder<T> {
+    fn start<V: Primitives<T>, A: Allocator<T, V>>(&mut self, _: &Term<T, V, A>) {}
+
+    fn visit(&mut self, _depth: usize) {}
+
+    fn unfold<V: Primitives<T>, A: Allocator<T, V>>(&mut self, _: &T, _: &Term<T, V, A>) {}
+
+    fn beta<V: Primitives<T>, A: Allocator<T, V>>(&mut self, _: &Term<T, V, A>, _: usize) {}
+
+    fn erased_lambda(&mut self) {}
+
+    fn erased_application<V: Primitives<T>, A: Allocator<T, V>>(&mut self, _: &Term<T, V, A>) {}
+
+    fn duplication<V: Primitives<T>, A: Allocator<T, V>>(&mut self, _: &Term<T, V, A>, _: usize) {}
+
+    fn put(&mut self) {}
+
+    fn annotation<V: Primitives<T>, A: Allocator<T, V>>(&mut self, _: &Term<T, V, A>) {}
+
+    fn primitive<V: Primitives<T>, A: Allocator<T, V>>(
+        &mut self,
+        _application: &Term<T, V, A>,
+        _result: &Term<T, V, A>,
+    ) {
+    }
+
+    fn jet<V: Primitives<T>, A: Allocator<T, V>>(
+        &mut self,
+        _application: &Term<T, V, A>,
+        _result: &Term<T, V, A>,
+    ) {
+    }
+}
+
+impl<T> Recorder<T> for () {}
+
+impl<T: Hash + Eq + Clone> Recorder<T> for NormalizationStatistics<T> {
+    fn start<V: Primitives<T>, A: Allocator<T, V>>(&mut self, term: &Term<T, V, A>) {
+        self.size = 0;
+        self.resize(0, term.size());
+    }
+
+    fn visit(&mut self, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+    }
+
+    fn unfold<V: Primitives<T>, A: Allocator<T, V>>(&mut self, name: &T, term: &Term<T, V, A>) {
+        *self.unfoldings.entry(name.clone()).or_insert(0) += 1;
+        self.resize(1, term.size());
+    }
+
+    fn beta<V: Primitives<T>, A: Allocator<T, V>>(
+        &mut self,
+        argument: &Term<T, V, A>,
+        occurrences: usize,
+    ) {
+        self.beta_reductions += 1;
+        let size = argument.size();
+        self.resize(2 + size + occurrences, occurrences * size);
+    }
+
+    fn erased_lambda(&mut self) {
+        self.erased_lambdas += 1;
+        self.resize(1, 0);
+    }
+
+    fn erased_application<V: Primitives<T>, A: Allocator<T, V>>(
+        &mut self,
+        argument: &Term<T, V, A>,
+    ) {
+        self.erased_applications += 1;
+        self.resize(1 + argument.size(), 0);
+    }
+
+    fn duplication<V: Primitives<T>, A: Allocator<T, V>>(
+        &mut self,
+        expression: &Term<T, V, A>,
+        occurrences: usize,
+    ) {
+        self.duplications += 1;
+        let size = expression.size();
+        self.resize(1 + size + occurrences, occurrences * size);
+    }
+
+    fn put(&mut self) {
+        self.puts += 1;
+        self.resize(1, 0);
+    }
+
+    fn annotation<V: Primitives<T>, A: Allocator<T, V>>(&mut self, ty: &Term<T, V, A>) {
+        self.resize(1 + ty.size(), 0);
+    }
+
+    fn primitive<V: Primitives<T>, A: Allocator<T, V>>(
+        &mut self,
+        application: &Term<T, V, A>,
+        result: &Term<T, V, A>,
+    ) {
+        self.primitive_applications += 1;
+        self.resize(application.size(), result.size());
+    }
+
+    fn jet<V: Primitives<T>, A: Allocator<T, V>>(
+        &mut self,
+        application: &Term<T, V, A>,
+        result: &Term<T, V, A>,
+    ) {
+        self.jet_applications += 1;
+        self.resize(application.size(), result.size());
+    }
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+    pub fn size(&self) -> usize {
+        use Term::*;
+
+        let mut size = 0;
+        let mut stack = vec![self];
+
+        while let Some(term) = stack.pop() {
+            size += 1;
+
+            match term {
+                Variable(_) | Reference(_) | Primitive(_) | Universe => {}
+                Lambda { body: term, .. } | Put(term) | Wrap(term) => stack.push(term),
+                Apply {
+                    function: a,
+                    argument: b,
+                    ..
+                }
+                | Duplicate {
+                    expression: a,
+                    body: b,
+                }
+                | Function {
+                    argument_type: a,
+                    return_type: b,
+                    ..
+                }
+                | Annotation {
+                    expression: a,
+                    ty: b,
+                    ..
+                } => {
+                    stack.push(a);
+                    stack.push(b);
+                }
+            }
+        }
+
+        size
+    }
+}
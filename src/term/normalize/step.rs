@@ -0,0 +1,636 @@
+use std::fmt::{self, Display};
+
+use derivative::Derivative;
+
+use super::super::{
+    alloc::{Reallocate, System},
+    Allocator, Definitions, Direction, Index, None, Path, Primitives, Show, Term, Zero,
+};
+use super::{NormalizationError, Redex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    // An application of a lambda substitutes the argument into its body.
+    Beta,
+    // An erased application discards its argument during full normalization.
+    ErasedBeta,
+    // An erased lambda is removed during full normalization.
+    ErasedLambda,
+    // A put is stripped during full normalization.
+    PutElimination,
+    // A duplication substitutes its expression directly during full normalization.
+    Duplication,
+    // A duplication of a put substitutes the put's contents.
+    DupOfPut,
+    // A duplication of a duplication floats the inner duplication outwards.
+    DupOfDup,
+    // An application of a duplication floats the duplication outwards.
+    ApplyOfDup,
+    // A reference is replaced by its definition.
+    Unfolding,
+    // An annotation is replaced by the annotated expression.
+    AnnotationStripping,
+    // A primitive is applied to as many arguments as its arity.
+    PrimitiveApplication,
+    // The jet attached to a definition is applied to as many arguments as its arity. A jet that
+    // declines is reported as the unfolding of the definition instead.
+    JetApplication,
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Rule::*;
+
+        write!(
+            f,
+            "{}",
+            match self {
+                Beta => "beta",
+                ErasedBeta => "erased beta",
+                ErasedLambda => "erased lambda",
+                PutElimination => "put elimination",
+                Duplication => "duplication",
+                DupOfPut => "dup of put",
+                DupOfDup => "dup of dup",
+                ApplyOfDup => "apply of dup",
+                Unfolding => "unfolding",
+                AnnotationStripping => "annotation stripping",
+                PrimitiveApplication => "primitive application",
+                JetApplication => "jet application",
+            }
+        )
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = "T: Show, V: Show"))]
+pub struct Reduction<T, V: Primitives<T> = None, A: Allocator<T, V> = System> {
+    pub path: Path,
+    pub rule: Rule,
+    pub redex: Term<T, V, A>,
+    pub reduct: Term<T, V, A>,
+}
+
+type Stepped<T, V, A> = Result<Option<Reduction<T, V, A>>, NormalizationError<T, V, A>>;
+
+enum Visit {
+    Enter,
+    Body,
+    Function,
+    Argument,
+    Expression,
+    // The saturated application of a primitive or jet is visited, with this many of its non-erased
+    // arguments already visited.
+    Saturated(usize),
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+    // The number of frames, from the top of the stack, of the applications that saturate a
+    // primitive or jet of `arity` being entered, if they do. Like the normalizer, this counts
+    // upwards from the head rather than walking each application down to it.
+    fn saturating_frames(arity: usize, stack: &[(&Self, Visit)]) -> Option<usize> {
+        let mut remaining = arity;
+
+        for (frames, (term, visit)) in stack.iter().rev().enumerate() {
+            match (term, visit) {
+                (Term::Apply { erased, .. }, Visit::Function) if remaining > 0 => {
+                    if !*erased {
+                        remaining -= 1;
+                    }
+                    if remaining == 0 {
+                        return Some(frames + 1);
+                    }
+                }
+                _ => return None,
+            }
+        }
+
+        None
+    }
+
+    // Called on entering a primitive or jet of `arity`. If it is saturated, the frames of the
+    // applications that saturate it are popped, the path is moved up to the outermost of them, and
+    // it is returned.
+    fn saturated_application<'a>(
+        arity: usize,
+        stack: &mut Vec<(&'a Self, Visit)>,
+        path: &mut Path,
+    ) -> Option<&'a Self> {
+        let frames = Self::saturating_frames(arity, stack)?;
+        for _ in 0..frames {
+            path.pop();
+        }
+        let application = stack[stack.len() - frames].0;
+        stack.truncate(stack.len() - frames);
+        Some(application)
+    }
+
+    // The non-erased arguments of this saturated primitive or jet application, from left to right,
+    // each with the path to it from the application.
+    fn spine_arguments(&self) -> Vec<(Path, &Self)> {
+        let mut arguments = vec![];
+        let mut depth = 0;
+        let mut term = self;
+        while let Term::Apply {
+            function,
+            argument,
+            erased,
+        } = term
+        {
+            if !*erased {
+                let mut path: Path = vec![Direction::Function; depth].into();
+                path.push(Direction::Argument);
+                arguments.push((path, &**argument));
+            }
+            depth += 1;
+            term = function;
+        }
+        arguments.reverse();
+        arguments
+    }
+
+    // Whether applying the primitive of this saturated application, whose arguments are normal,
+    // rewrites anything. A failure does, since it is reported by contracting the application.
+    fn primitive_applies(&self, alloc: &A) -> bool
+    where
+        T: Clone,
+        V: Clone,
+    {
+        let arguments = self
+            .spine_arguments()
+            .into_iter()
+            .map(|(_, argument)| alloc.copy(argument))
+            .collect();
+        !matches!(
+            alloc
+                .copy(self)
+                .apply_primitive_recorded(arguments, alloc, &mut ()),
+            Ok(None)
+        )
+    }
+
+    // The first redex in the arguments of this saturated primitive application, in the order
+    // `normalize_in` normalizes them, or the application itself once they're normal, if applying
+    // the primitive rewrites anything. `path` leads to the application, and is extended to the
+    // redex.
+    fn primitive_redex<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &self,
+        path: &mut Path,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<Option<Rule>, NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        for (to, argument) in self.spine_arguments() {
+            let redex = argument.next_redex(definitions, alloc).map_err(|error| {
+                let directions = path.directions().iter().chain(to.directions());
+                error.within(directions.copied().collect::<Vec<_>>(), None)
+            })?;
+            if let Some((within, rule)) = redex {
+                for direction in to.directions().iter().chain(within.directions()) {
+                    path.push(*direction);
+                }
+                return Ok(Some(rule));
+            }
+        }
+
+        Ok(if self.primitive_applies(alloc) {
+            Some(Rule::PrimitiveApplication)
+        } else {
+            None
+        })
+    }
+
+    // Finds the redex `normalize_in` would contract next, visiting the term in the same order.
+    fn next_redex<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<Option<(Path, Rule)>, NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        use Term::*;
+
+        let mut path = Path::root();
+        let mut stack = vec![(self, Visit::Enter)];
+
+        while let Some((term, visit)) = stack.pop() {
+            let rule = match (visit, term) {
+                // As with a primitive, a jet's arguments are visited first. Jets are only used by
+                // full normalization.
+                (Visit::Enter, Reference(binding)) => {
+                    let application = definitions.jet_arity(binding).and_then(|arity| {
+                        Self::saturated_application(arity, &mut stack, &mut path)
+                    });
+                    match application {
+                        Some(application) => {
+                            stack.push((application, Visit::Saturated(0)));
+                            None
+                        }
+                        None if definitions.get(binding).is_some() => Some(Rule::Unfolding),
+                        None => None,
+                    }
+                }
+                (Visit::Enter, Put(_)) => Some(Rule::PutElimination),
+                (Visit::Enter, Duplicate { .. }) => Some(Rule::Duplication),
+                (Visit::Enter, Annotation { .. }) => Some(Rule::AnnotationStripping),
+                (Visit::Enter, Primitive(primitive)) => {
+                    // The arguments are visited first, and the primitive applied once they're
+                    // normal.
+                    if let Some(application) =
+                        Self::saturated_application(primitive.arity(), &mut stack, &mut path)
+                    {
+                        stack.push((application, Visit::Saturated(0)));
+                    }
+                    None
+                }
+                (Visit::Enter, Lambda { body, .. }) => {
+                    stack.push((term, Visit::Body));
+                    stack.push((body, Visit::Enter));
+                    path.push(Direction::Body);
+                    None
+                }
+                (Visit::Enter, Apply { function, .. }) => {
+                    stack.push((term, Visit::Function));
+                    stack.push((function, Visit::Enter));
+                    path.push(Direction::Function);
+                    None
+                }
+                (Visit::Enter, _) => None,
+
+                (Visit::Body, Lambda { erased, .. }) => {
+                    path.pop();
+                    if *erased {
+                        Some(Rule::ErasedLambda)
+                    } else {
+                        None
+                    }
+                }
+                (
+                    Visit::Function,
+                    Apply {
+                        function,
+                        argument,
+                        erased,
+                    },
+                ) => {
+                    path.pop();
+                    if *erased {
+                        Some(Rule::ErasedBeta)
+                    } else {
+                        match &**function {
+                            Put(_) => {
+                                Err(NormalizationError::InvalidApplication(Box::new(Redex {
+                                    term: alloc.copy(term),
+                                    path: Some(path.clone()),
+                                    definition: None,
+                                })))?
+                            }
+                            Lambda { .. } => Some(Rule::Beta),
+                            _ => {
+                                stack.push((term, Visit::Argument));
+                                stack.push((argument, Visit::Enter));
+                                path.push(Direction::Argument);
+                                None
+                            }
+                        }
+                    }
+                }
+                (Visit::Argument, _) => {
+                    path.pop();
+                    None
+                }
+                (Visit::Saturated(visited), _) => {
+                    let arguments = term.spine_arguments();
+                    if let Some((to, _)) = visited.checked_sub(1).map(|last| &arguments[last]) {
+                        for _ in to.directions() {
+                            path.pop();
+                        }
+                    }
+                    match arguments.get(visited) {
+                        Some((to, argument)) => {
+                            stack.push((term, Visit::Saturated(visited + 1)));
+                            stack.push((argument, Visit::Enter));
+                            for direction in to.directions() {
+                                path.push(*direction);
+                            }
+                            None
+                        }
+                        None if term.primitive_head().is_none() => Some(Rule::JetApplication),
+                        None if term.primitive_applies(alloc) => Some(Rule::PrimitiveApplication),
+                        None => None,
+                    }
+                }
+                _ => unreachable!(),
+            };
+
+            if let Some(rule) = rule {
+                return Ok(Some((path, rule)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Finds the redex `weak_normalize_in` would contract next, visiting the term in the same order.
+    fn next_weak_redex<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<Option<(Path, Rule)>, NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        use Term::*;
+
+        let mut path = Path::root();
+        let mut stack = vec![(self, Visit::Enter)];
+
+        while let Some((term, visit)) = stack.pop() {
+            let rule = match (visit, term) {
+                (Visit::Enter, Reference(binding)) if definitions.get(binding).is_some() => {
+                    Some(Rule::Unfolding)
+                }
+                (Visit::Enter, Annotation { .. }) => Some(Rule::AnnotationStripping),
+                // Primitive arguments are normalized fully, even by weak normalization.
+                (Visit::Enter, Primitive(primitive)) => {
+                    match Self::saturated_application(primitive.arity(), &mut stack, &mut path) {
+                        Some(application) => {
+                            application.primitive_redex(&mut path, definitions, alloc)?
+                        }
+                        None => None,
+                    }
+                }
+                (Visit::Enter, Apply { function, .. }) => {
+                    stack.push((term, Visit::Function));
+                    stack.push((function, Visit::Enter));
+                    path.push(Direction::Function);
+                    None
+                }
+                (Visit::Enter, Duplicate { expression, .. }) => {
+                    stack.push((term, Visit::Expression));
+                    stack.push((expression, Visit::Enter));
+                    path.push(Direction::Expression);
+                    None
+                }
+                (Visit::Enter, _) => None,
+
+                (Visit::Function, Apply { function, .. }) => {
+                    path.pop();
+                    match &**function {
+                        Put(_) => Err(NormalizationError::InvalidApplication(Box::new(Redex {
+                            term: alloc.copy(term),
+                            path: Some(path.clone()),
+                            definition: None,
+                        })))?,
+                        Duplicate { .. } => Some(Rule::ApplyOfDup),
+                        Lambda { .. } => Some(Rule::Beta),
+                        _ => None,
+                    }
+                }
+                (Visit::Expression, Duplicate { expression, .. }) => {
+                    path.pop();
+                    match &**expression {
+                        Put(_) => Some(Rule::DupOfPut),
+                        Duplicate { .. } => Some(Rule::DupOfDup),
+                        _ => None,
+                    }
+                }
+                _ => unreachable!(),
+            };
+
+            if let Some(rule) = rule {
+                return Ok(Some((path, rule)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Contracts the redex at the root of this term by exactly one application of `rule`, and
+    // returns the rule that was applied, which differs only when a jet declines.
+    fn contract_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        rule: Rule,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<Rule, NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        use Term::*;
+
+        let reduct = match (rule, &mut *self) {
+            (Rule::Unfolding, Reference(binding)) => {
+                let definition = definitions.get(binding).unwrap();
+                alloc.reallocating_copy(definition.as_ref())
+            }
+            (Rule::PutElimination, Put(term)) => term.take(),
+            (Rule::Duplication, Duplicate { expression, body }) => {
+                body.substitute_top_in(expression, alloc);
+                body.take()
+            }
+            (Rule::DupOfPut, Duplicate { expression, body }) => match &mut **expression {
+                Put(term) => {
+                    body.substitute_top_in(term, alloc);
+                    body.take()
+                }
+                _ => unreachable!(),
+            },
+            (Rule::DupOfDup, Duplicate { expression, body }) => match &mut **expression {
+                Duplicate {
+                    body: sub_body,
+                    expression: sub_expression,
+                } => {
+                    body.shift(Index::top().child());
+                    let dup = Duplicate {
+                        body: alloc.alloc(body.take()),
+                        expression: alloc.alloc(sub_body.take()),
+                    };
+                    Duplicate {
+                        expression: alloc.alloc(sub_expression.take()),
+                        body: alloc.alloc(dup),
+                    }
+                }
+                _ => unreachable!(),
+            },
+            (Rule::AnnotationStripping, Annotation { expression, .. }) => expression.take(),
+            (Rule::ErasedLambda, Lambda { body, .. }) => {
+                body.substitute_top_in(&Variable(Index::top()), alloc);
+                body.take()
+            }
+            (Rule::ErasedBeta, Apply { function, .. }) => function.take(),
+            (
+                Rule::ApplyOfDup,
+                Apply {
+                    function,
+                    argument,
+                    erased,
+                },
+            ) => match &mut **function {
+                Duplicate { expression, body } => {
+                    let mut argument = argument.take();
+                    argument.shift_top();
+                    let body = alloc.alloc(Apply {
+                        function: alloc.alloc(body.take()),
+                        argument: alloc.alloc(argument),
+                        erased: *erased,
+                    });
+                    Duplicate {
+                        expression: alloc.alloc(expression.take()),
+                        body,
+                    }
+                }
+                _ => unreachable!(),
+            },
+            (
+                Rule::Beta,
+                Apply {
+                    function, argument, ..
+                },
+            ) => match &mut **function {
+                Lambda { body, .. } => {
+                    body.substitute_top_in(argument, alloc);
+                    body.take()
+                }
+                _ => unreachable!(),
+            },
+            // The arguments have been stepped to their normal forms first.
+            (Rule::PrimitiveApplication, Apply { .. }) => {
+                let arguments = self
+                    .spine_arguments()
+                    .into_iter()
+                    .map(|(_, argument)| alloc.copy(argument))
+                    .collect();
+                match self.apply_primitive_recorded(arguments, alloc, &mut ())? {
+                    Some(result) => result,
+                    None => return Ok(rule),
+                }
+            }
+            // A declined jet leaves its definition unfolded in place, as `normalize_in` does.
+            (Rule::JetApplication, Apply { .. }) => {
+                let arguments = self
+                    .spine_arguments()
+                    .into_iter()
+                    .map(|(_, argument)| alloc.copy(argument))
+                    .collect();
+                let mut head = &mut *self;
+                while let Apply { function, .. } = head {
+                    head = function;
+                }
+                let name = match head {
+                    Reference(name) => name,
+                    _ => unreachable!(),
+                };
+                match definitions.apply_jet(name, arguments, alloc) {
+                    Some(result) => result?,
+                    None => {
+                        let definition = definitions.get(name).unwrap();
+                        *head = alloc.reallocating_copy(definition.as_ref());
+                        return Ok(Rule::Unfolding);
+                    }
+                }
+            }
+            _ => unreachable!(),
+        };
+
+        *self = reduct;
+        Ok(rule)
+    }
+
+    fn reduce_at<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        path: Path,
+        rule: Rule,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<Reduction<T, V, A>, NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        let term = self.subterm_mut(&path).unwrap();
+        let redex = alloc.copy(term);
+        let rule = term
+            .contract_in(rule, definitions, alloc)
+            .map_err(|error| error.within(path.directions().iter().copied(), None))?;
+
+        Ok(Reduction {
+            reduct: alloc.copy(term),
+            path,
+            rule,
+            redex,
+        })
+    }
+
+    // Contracts the redex `normalize_in` would contract next, or returns `None` if the term is
+    // already normal. Repeated steps reach the same normal form as `normalize_in`.
+    pub fn step_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Stepped<T, V, A>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        self.next_redex(definitions, alloc)?
+            .map(|(path, rule)| self.reduce_at(path, rule, definitions, alloc))
+            .transpose()
+    }
+
+    // Contracts the redex `weak_normalize_in` would contract next, or returns `None` if the term is
+    // already in weak head normal form.
+    pub fn weak_step_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Stepped<T, V, A>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        self.next_weak_redex(definitions, alloc)?
+            .map(|(path, rule)| self.reduce_at(path, rule, definitions, alloc))
+            .transpose()
+    }
+
+    pub fn step<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+    ) -> Stepped<T, V, A>
+    where
+        T: Clone,
+        V: Clone,
+        A: Zero + Reallocate<T, V, B>,
+    {
+        let alloc = A::zero();
+        self.step_in(definitions, &alloc)
+    }
+
+    pub fn weak_step<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+    ) -> Stepped<T, V, A>
+    where
+        T: Clone,
+        V: Clone,
+        A: Zero + Reallocate<T, V, B>,
+    {
+        let alloc = A::zero();
+        self.weak_step_in(definitions, &alloc)
+    }
+}
@@ -0,0 +1,342 @@
+use std::mem::replace;
+
+use super::super::{
+    alloc::Reallocate, Allocator, Definitions, Direction, Index, Primitives, Term, Zero,
+};
+use super::{NormalizationError, Step, Unfolded};
+
+// How far to reduce a term and in which order. Every strategy unfolds references and strips
+// annotations wherever it reduces.
+//
+// Erasing strategies treat the term as the program it compiles to: puts are stripped and
+// duplications substitute their expression directly, which is only sound for stratified terms.
+// Non-erasing strategies keep the boxes, reducing a duplication only once its expression is a put
+// and floating duplications out of the way of applications and other duplications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReductionStrategy {
+    // Reduces only the head of the term, stopping at a lambda, put, universe, function type,
+    // wrap, primitive, or an application that can't be reduced further. Nothing under a binder or
+    // in an argument is touched. This is what equivalence checking uses at every node.
+    WeakHead { erase: bool },
+    // Reduces to weak head normal form, then continues under every lambda at the head, so the
+    // result is a sequence of lambdas around a term that is weakly normal. Arguments are left
+    // unreduced.
+    Head { erase: bool },
+    // Contracts the leftmost outermost redex first, reaching the normal form whenever one exists.
+    // The erasing variant is `normalize_in`: in addition to boxes it removes erased lambdas and
+    // erased applications, leaving only the computational content. The non-erasing variant keeps
+    // erased lambdas and applications and beta-reduces them like any other. Neither reduces
+    // inside wraps or function types.
+    NormalOrder { erase: bool },
+    // Normalizes the function and argument of every application, and the expression of every
+    // duplication, before contracting it. This copies only normal forms, but may diverge or do
+    // needless work on arguments a normal-order reduction would discard. Primitives are only ever
+    // applied to normal forms. It otherwise reaches the same normal form as `NormalOrder` with the
+    // same erasure.
+    ApplicativeOrder { erase: bool },
+}
+
+impl Default for ReductionStrategy {
+    fn default() -> Self {
+        ReductionStrategy::NormalOrder { erase: true }
+    }
+}
+
+impl ReductionStrategy {
+    pub fn erases(&self) -> bool {
+        use ReductionStrategy::*;
+
+        match self {
+            WeakHead { erase }
+            | Head { erase }
+            | NormalOrder { erase }
+            | ApplicativeOrder { erase } => *erase,
+        }
+    }
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+    pub fn reduce_with<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        strategy: ReductionStrategy,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        use ReductionStrategy::*;
+
+        match strategy {
+            WeakHead { erase } => self.weak_normalize_in_erased(definitions, alloc, erase),
+            Head { erase } => self.head_normalize_in(definitions, alloc, erase),
+            NormalOrder { erase: true } => self.normalize_in(definitions, alloc),
+            NormalOrder { erase: false } => self.normalize_in_unerased(definitions, alloc),
+            ApplicativeOrder { erase } => self.normalize_applicative_in(definitions, alloc, erase),
+        }
+    }
+
+    pub fn reduce<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        strategy: ReductionStrategy,
+        definitions: &U,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Zero + Reallocate<T, V, B>,
+    {
+        let alloc = A::zero();
+        self.reduce_with(strategy, definitions, &alloc)
+    }
+
+    fn head_normalize_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+        alloc: &A,
+        erase: bool,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        let mut term = self;
+
+        loop {
+            term.weak_normalize_in_erased(definitions, alloc, erase)?;
+            match term {
+                Term::Lambda { body, .. } => term = body,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    // Brings every subterm to weak head normal form, outermost first. Once a term is weakly normal
+    // nothing below it can create a redex at its head, so each subterm is visited once.
+    fn normalize_in_unerased<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        use Term::*;
+
+        let mut stack = vec![self];
+
+        while let Some(term) = stack.pop() {
+            term.weak_normalize_in_erased(definitions, alloc, false)?;
+
+            match term {
+                Lambda { body, .. } => stack.push(body),
+                Apply {
+                    function, argument, ..
+                } => {
+                    stack.push(argument);
+                    stack.push(function);
+                }
+                Put(term) => stack.push(term),
+                Duplicate { expression, body } => {
+                    stack.push(body);
+                    stack.push(expression);
+                }
+                Variable(_)
+                | Reference(_)
+                | Primitive(_)
+                | Universe
+                | Wrap(_)
+                | Function { .. }
+                | Annotation { .. } => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn normalize_applicative_in<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &mut self,
+        definitions: &U,
+        alloc: &A,
+        erase: bool,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        let mut stack = vec![];
+        let mut term = self.take();
+        let result = Self::applicative_spine(&mut term, &mut stack, definitions, alloc, erase);
+        *self = Self::reassemble(term, &mut stack);
+        result
+    }
+
+    fn applicative_spine<U: Definitions<T, V, B>, B: Allocator<T, V>>(
+        term: &mut Self,
+        stack: &mut Vec<(Self, Direction)>,
+        definitions: &U,
+        alloc: &A,
+        erase: bool,
+    ) -> Result<(), NormalizationError<T, V, A>>
+    where
+        T: Clone,
+        V: Clone,
+        A: Reallocate<T, V, B>,
+    {
+        use Term::*;
+
+        let mut unfolded = Unfolded::new();
+
+        loop {
+            let direction = match term {
+                Reference(binding) => {
+                    if let Some(definition) = definitions.get(binding) {
+                        unfolded.enter(stack, binding.clone());
+                        *term = alloc.reallocating_copy(definition.as_ref());
+                        continue;
+                    }
+                    None
+                }
+                Annotation { expression, .. } => {
+                    *term = expression.take();
+                    continue;
+                }
+                Put(inner) if erase => {
+                    *term = inner.take();
+                    continue;
+                }
+                Put(_) => Some(Direction::Contents),
+                Lambda { .. } => Some(Direction::Body),
+                Apply { .. } => Some(Direction::Function),
+                Duplicate { .. } => Some(Direction::Expression),
+                Variable(_) | Universe | Primitive(_) | Wrap(_) | Function { .. } => None,
+            };
+
+            if let Some(direction) = direction {
+                let child = term.child_mut(direction).unwrap().take();
+                stack.push((replace(term, child), direction));
+                continue;
+            }
+
+            if Self::saturate(term, stack) {
+                let arguments = term
+                    .normalize_arguments_recorded(definitions, alloc, &mut ())
+                    .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?;
+                unfolded.saturate(stack);
+                let result = term
+                    .apply_primitive_recorded(arguments, alloc, &mut ())
+                    .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?;
+                // A declined application is stuck, and its arguments are already normal.
+                if let Some(result) = result {
+                    *term = result;
+                    continue;
+                }
+            }
+
+            loop {
+                let (mut parent, direction) = match stack.pop() {
+                    Some(frame) => frame,
+                    None => return Ok(()),
+                };
+                *parent.child_mut(direction).unwrap() = term.take();
+
+                let step = match (&mut parent, direction) {
+                    (Lambda { body, erased }, _) => {
+                        if erase && *erased {
+                            body.substitute_top_in(&Variable(Index::top()), alloc);
+                            if body.primitive_head().is_some() {
+                                Step::Reduce(body.take())
+                            } else {
+                                Step::Replace(body.take())
+                            }
+                        } else {
+                            Step::Keep
+                        }
+                    }
+                    (
+                        Apply {
+                            function, erased, ..
+                        },
+                        Direction::Function,
+                    ) => {
+                        if erase && *erased {
+                            Step::Replace(function.take())
+                        } else {
+                            Step::Descend(Direction::Argument)
+                        }
+                    }
+                    (
+                        Apply {
+                            function,
+                            argument,
+                            erased,
+                        },
+                        _,
+                    ) => match &mut **function {
+                        Put(_) => Step::Fail(NormalizationError::InvalidApplication),
+                        Lambda { body, .. } => {
+                            body.substitute_top_in(argument, alloc);
+                            Step::Reduce(body.take())
+                        }
+                        Duplicate { body, expression } => {
+                            let mut argument = argument.take();
+                            argument.shift_top();
+                            let body = alloc.alloc(Apply {
+                                function: alloc.alloc(body.take()),
+                                argument: alloc.alloc(argument),
+                                erased: *erased,
+                            });
+                            Step::Reduce(Duplicate {
+                                expression: alloc.alloc(expression.take()),
+                                body,
+                            })
+                        }
+                        _ => Step::Keep,
+                    },
+                    (Duplicate { body, expression }, Direction::Expression) => {
+                        if erase {
+                            body.substitute_top_in(expression, alloc);
+                            Step::Reduce(body.take())
+                        } else {
+                            match &mut **expression {
+                                Put(term) => {
+                                    body.substitute_top_in(term, alloc);
+                                    Step::Reduce(body.take())
+                                }
+                                Duplicate {
+                                    body: sub_body,
+                                    expression: sub_expression,
+                                } => {
+                                    body.shift(Index::top().child());
+                                    let dup = Duplicate {
+                                        body: alloc.alloc(body.take()),
+                                        expression: alloc.alloc(sub_body.take()),
+                                    };
+                                    Step::Reduce(Duplicate {
+                                        expression: alloc.alloc(sub_expression.take()),
+                                        body: alloc.alloc(dup),
+                                    })
+                                }
+                                _ => Step::Descend(Direction::Body),
+                            }
+                        }
+                    }
+                    _ => Step::Keep,
+                };
+
+                unfolded.leave(stack, &step);
+                if Self::continue_from(term, stack, parent, step, alloc)
+                    .map_err(|error| Self::locate(error, stack, unfolded.current(stack)))?
+                {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    analysis::Empty,
+    term::{alloc::System, Index, Term},
+};
+
+const DEPTH: usize = 1_000_000;
+
+fn lambdas(depth: usize, mut body: Term<String>) -> Term<String> {
+    for _ in 0..depth {
+        body = Term::Lambda {
+            body: Box::new(body),
+            erased: false,
+        };
+    }
+    body
+}
+
+fn applications(depth: usize, mut function: Term<String>, argument: &Term<String>) -> Term<String> {
+    for _ in 0..depth {
+        function = Term::Apply {
+            function: Box::new(function),
+            argument: Box::new(argument.clone()),
+            erased: false,
+        };
+    }
+    function
+}
+
+fn nested_arguments(
+    depth: usize,
+    function: &Term<String>,
+    mut argument: Term<String>,
+) -> Term<String> {
+    for _ in 0..depth {
+        argument = Term::Apply {
+            function: Box::new(function.clone()),
+            argument: Box::new(argument),
+            erased: false,
+        };
+    }
+    argument
+}
+
+fn identity() -> Term<String> {
+    lambdas(1, Term::Variable(Index::top()))
+}
+
+fn std_hash(term: &Term<String>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    term.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn lambda_spine() {
+    let mut term = lambdas(DEPTH, Term::Variable(Index(DEPTH - 1)));
+    let copy = term.clone();
+
+    term.normalize(&Empty).unwrap();
+    assert!(term.equals(&copy));
+    assert_eq!(std_hash(&term), std_hash(&copy));
+    assert_eq!(term.stable_hash(), copy.stable_hash());
+
+    term.weak_normalize(&Empty).unwrap();
+    assert!(term.equals(&copy));
+}
+
+#[test]
+fn lambda_spine_shift() {
+    let mut term = lambdas(DEPTH, Term::Variable(Index(DEPTH)));
+    term.shift_top();
+    assert!(term.equals(&lambdas(DEPTH, Term::Variable(Index(DEPTH + 1)))));
+
+    term.shift_top_by(-1);
+    assert!(term.equals(&lambdas(DEPTH, Term::Variable(Index(DEPTH)))));
+}
+
+#[test]
+fn lambda_spine_substitute() {
+    let mut term = lambdas(DEPTH, Term::Variable(Index(DEPTH)));
+    term.substitute_top(&Term::Variable(Index(3)));
+    assert!(term.equals(&lambdas(DEPTH, Term::Variable(Index(DEPTH + 3)))));
+}
+
+#[test]
+fn application_spine() {
+    let mut term = applications(DEPTH, Term::Variable(Index(0)), &Term::Variable(Index(1)));
+    let copy = term.clone();
+
+    term.weak_normalize(&Empty).unwrap();
+    assert!(term.equals(&copy));
+
+    term.normalize(&Empty).unwrap();
+    assert!(term.equals(&copy));
+    assert_ne!(term.stable_hash(), identity().stable_hash());
+}
+
+#[test]
+fn argument_spine() {
+    let mut term = nested_arguments(DEPTH, &Term::Variable(Index(0)), Term::Variable(Index(1)));
+    let copy = term.clone();
+
+    term.normalize(&Empty).unwrap();
+    assert!(term.equals(&copy));
+    assert_eq!(std_hash(&term), std_hash(&copy));
+}
+
+#[test]
+fn redex_spine() {
+    let mut term = applications(DEPTH, identity(), &identity());
+    term.normalize(&Empty).unwrap();
+    assert!(term.equals(&identity()));
+
+    let mut term = applications(DEPTH, identity(), &identity());
+    term.weak_normalize(&Empty).unwrap();
+    assert!(term.equals(&identity()));
+}
+
+#[test]
+fn argument_spine_map() {
+    let term = nested_arguments(
+        DEPTH,
+        &Term::Reference("f".to_owned()),
+        Term::Reference("x".to_owned()),
+    );
+
+    let term = term.map_reference(|name| Term::Reference(name.len()));
+    let term = term.map_primitive(|primitive| match primitive {});
+    assert!(term.equals(&nested_arguments_by_length(DEPTH)));
+}
+
+fn nested_arguments_by_length(depth: usize) -> Term<usize> {
+    let mut argument = Term::Reference(1);
+    for _ in 0..depth {
+        argument = Term::Apply {
+            function: Box::new(Term::Reference(1)),
+            argument: Box::new(argument),
+            erased: false,
+        };
+    }
+    argument
+}
+
+#[test]
+fn evaluation() {
+    for (term, normal) in [
+        (
+            lambdas(DEPTH, Term::Variable(Index(DEPTH - 1))),
+            lambdas(DEPTH, Term::Variable(Index(DEPTH - 1))),
+        ),
+        (
+            applications(DEPTH, Term::Variable(Index(0)), &Term::Variable(Index(1))),
+            applications(DEPTH, Term::Variable(Index(0)), &Term::Variable(Index(1))),
+        ),
+        (
+            nested_arguments(DEPTH, &Term::Variable(Index(0)), Term::Variable(Index(1))),
+            nested_arguments(DEPTH, &Term::Variable(Index(0)), Term::Variable(Index(1))),
+        ),
+        (applications(DEPTH, identity(), &identity()), identity()),
+    ] {
+        let mut evaluated = term.clone();
+        evaluated.evaluate_in::<_, System>(&Empty, &System).unwrap();
+        assert!(evaluated.equals(&normal));
+
+        let convertible = |other| term.convertible_in::<_, System>(other, &Empty, &System);
+        assert!(convertible(&normal).unwrap());
+        assert!(!convertible(&Term::Universe).unwrap());
+    }
+}
+
+#[test]
+fn stratification_spine() {
+    let mut definitions: HashMap<String, (Term<String>, Term<String>)> = HashMap::new();
+    definitions.insert("f".into(), (Term::Universe, identity()));
+
+    let f = Term::Reference("f".to_owned());
+    let term = lambdas(1, nested_arguments(DEPTH, &f, Term::Variable(Index::top())));
+
+    term.is_sound().unwrap();
+    term.is_stratified().unwrap();
+    assert!(!term.is_recursive_in(&definitions, &System, &System));
+
+    let usage = term.usage();
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].computational_uses(), 1);
+    assert_eq!(usage[0].uses[0].path.directions().len(), DEPTH + 1);
+
+    term.stratified(&definitions).unwrap();
+}
+
+#[test]
+fn box_spine() {
+    let mut term = identity();
+    for _ in 0..DEPTH {
+        term = Term::Put(Box::new(term));
+    }
+
+    term.is_stratified().unwrap();
+    term.is_stratified_across(&Empty).unwrap();
+}
@@ -3,8 +3,12 @@ use crate::{
     term::{NullCache, Primitives, Term},
 };
 
+mod deep;
 mod normalize;
 mod shift;
+mod statistics;
+mod step;
+mod strategy;
 mod substitute;
 
 #[track_caller]
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use crate::{analysis::Empty, term::Term};
+
+use super::{assert_equivalent, parse};
+
+#[test]
+fn beta() {
+    let mut term = parse(r#"(\x (^0 ^0) ^1)"#);
+    let statistics = term.normalize_with_statistics(&Empty).unwrap();
+
+    assert_equivalent(term, parse(r#"(^1 ^1)"#));
+    assert_eq!(statistics.beta_reductions, 1);
+    assert_eq!(statistics.reductions(), 1);
+    assert_eq!(statistics.max_size, 6);
+    assert_eq!(statistics.max_depth, 4);
+}
+
+#[test]
+fn erasure() {
+    let mut term = parse(r#"[/x ^0 ^1]"#);
+    let statistics = term.normalize_with_statistics(&Empty).unwrap();
+
+    assert_equivalent(term, parse(r#"^0"#));
+    assert_eq!(statistics.erased_lambdas, 1);
+    assert_eq!(statistics.erased_applications, 1);
+    assert_eq!(statistics.beta_reductions, 0);
+    assert_eq!(statistics.max_size, 4);
+}
+
+#[test]
+fn duplication() {
+    let mut term = parse(
+        r#"
+        : X = . ^1
+        (^0 ^0)
+    "#,
+    );
+    let statistics = term.normalize_with_statistics(&Empty).unwrap();
+
+    assert_equivalent(term, parse(r#"(^1 ^1)"#));
+    assert_eq!(statistics.duplications, 1);
+    assert_eq!(statistics.puts, 2);
+    assert_eq!(statistics.max_size, 6);
+
+    let mut term = parse(r#". (\x ^0 ^1)"#);
+    let statistics = term.normalize_with_statistics(&Empty).unwrap();
+
+    assert_equivalent(term, parse(r#"^1"#));
+    assert_eq!(statistics.puts, 1);
+    assert_eq!(statistics.beta_reductions, 1);
+}
+
+#[test]
+fn unfoldings() {
+    let mut definitions: HashMap<String, (Term<String>, Term<String>)> = HashMap::new();
+    definitions.insert("id".into(), (Term::Universe, parse(r#"\x ^0"#)));
+    definitions.insert(
+        "twice".into(),
+        (Term::Universe, parse(r#"\f \x (^1 (^1 ^0))"#)),
+    );
+
+    let mut term = parse(r#"(twice id)"#);
+    let statistics = term.normalize_with_statistics(&definitions).unwrap();
+
+    assert_equivalent(term, parse(r#"\x ^0"#));
+    assert_eq!(statistics.unfoldings.get("twice"), Some(&1));
+    assert_eq!(statistics.unfoldings.get("id"), Some(&2));
+    assert_eq!(statistics.total_unfoldings(), 3);
+    assert_eq!(statistics.beta_reductions, 3);
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use crate::{
+    analysis::Empty,
+    term::{Direction, NormalizationError, Path, Rule, Term},
+};
+
+use super::{assert_equivalent, parse};
+
+#[track_caller]
+fn assert_steps_normalize(term: &str, definitions: &HashMap<String, (Term<String>, Term<String>)>) {
+    let mut stepped = parse(term);
+    let mut steps = 0;
+    while stepped.step(definitions).unwrap().is_some() {
+        steps += 1;
+    }
+
+    let mut normalized = parse(term);
+    let statistics = normalized.normalize_with_statistics(definitions).unwrap();
+
+    assert!(stepped.equals(&normalized));
+    assert_eq!(steps, statistics.reductions());
+}
+
+#[test]
+fn beta() {
+    let mut term: Term<String> = parse(r#"\x (\y ^0 ^0)"#);
+    let reduction = term.step(&Empty).unwrap().unwrap();
+
+    assert_eq!(reduction.rule, Rule::Beta);
+    assert_eq!(reduction.path, Path::from(vec![Direction::Body]));
+    assert!(reduction.redex.equals(&parse(r#"(\y ^0 ^0)"#)));
+    assert!(reduction.reduct.equals(&parse(r#"^0"#)));
+    assert_equivalent(term, parse(r#"\x ^0"#));
+}
+
+#[test]
+fn normal_form() {
+    let mut term: Term<String> = parse(r#"\x (^0 \y ^1)"#);
+    assert!(term.step(&Empty).unwrap().is_none());
+    assert!(term.weak_step(&Empty).unwrap().is_none());
+}
+
+#[test]
+fn order() {
+    let mut term: Term<String> = parse(r#"(^0 (\x ^0 ^1) (\x ^0 ^2))"#);
+
+    let reduction = term.step(&Empty).unwrap().unwrap();
+    assert_eq!(
+        reduction.path,
+        Path::from(vec![Direction::Function, Direction::Argument])
+    );
+
+    let reduction = term.step(&Empty).unwrap().unwrap();
+    assert_eq!(reduction.path, Path::from(vec![Direction::Argument]));
+
+    assert!(term.step(&Empty).unwrap().is_none());
+}
+
+#[test]
+fn erasure() {
+    let mut term: Term<String> = parse(r#"[/x ^0 ^1]"#);
+
+    let reduction = term.step(&Empty).unwrap().unwrap();
+    assert_eq!(reduction.rule, Rule::ErasedLambda);
+    assert_eq!(reduction.path, Path::from(vec![Direction::Function]));
+
+    let reduction = term.step(&Empty).unwrap().unwrap();
+    assert_eq!(reduction.rule, Rule::ErasedBeta);
+    assert!(reduction.path.is_root());
+}
+
+#[test]
+fn boxes() {
+    let mut term: Term<String> = parse(
+        r#"
+        : X = . ^1
+        (^0 ^0)
+    "#,
+    );
+    assert_eq!(
+        term.weak_step(&Empty).unwrap().unwrap().rule,
+        Rule::DupOfPut
+    );
+    assert_equivalent(term, parse(r#"(^1 ^1)"#));
+
+    let mut term: Term<String> = parse(
+        r#"
+        : X = . ^1
+        (^0 ^0)
+    "#,
+    );
+    let reduction = term.step(&Empty).unwrap().unwrap();
+    assert_eq!(reduction.rule, Rule::Duplication);
+    let reduction = term.step(&Empty).unwrap().unwrap();
+    assert_eq!(reduction.rule, Rule::PutElimination);
+    assert_eq!(reduction.path, Path::from(vec![Direction::Function]));
+
+    let mut term: Term<String> = parse(
+        r#"
+        : X = : Y = ^0 . ^0
+        ^0
+    "#,
+    );
+    assert_eq!(
+        term.weak_step(&Empty).unwrap().unwrap().rule,
+        Rule::DupOfDup
+    );
+
+    let mut term: Term<String> = parse(r#"(: X = ^0 ^0 ^1)"#);
+    assert_eq!(
+        term.weak_step(&Empty).unwrap().unwrap().rule,
+        Rule::ApplyOfDup
+    );
+}
+
+#[test]
+fn annotation() {
+    let mut term: Term<String> = parse(r#"{ \x ^0 : * }"#);
+    let reduction = term.step(&Empty).unwrap().unwrap();
+
+    assert_eq!(reduction.rule, Rule::AnnotationStripping);
+    assert_equivalent(term, parse(r#"\x ^0"#));
+}
+
+#[test]
+fn invalid_application() {
+    let mut term: Term<String> = parse(r#"(. ^0 ^1)"#);
+    assert!(matches!(
+        term.weak_step(&Empty),
+        Err(NormalizationError::InvalidApplication(_))
+    ));
+
+    let mut term: Term<String> = parse(r#"((. ^0 ^1) ^2)"#);
+    match term.weak_step(&Empty) {
+        Err(NormalizationError::InvalidApplication(redex)) => {
+            assert!(redex.term.equals(&parse(r#"(. ^0 ^1)"#)));
+            assert_eq!(redex.path, Some(vec![Direction::Function].into()));
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn agrees_with_normalize() {
+    let mut definitions: HashMap<String, (Term<String>, Term<String>)> = HashMap::new();
+    definitions.insert("id".into(), (Term::Universe, parse(r#"\x ^0"#)));
+    definitions.insert(
+        "twice".into(),
+        (Term::Universe, parse(r#"\f \x (^1 (^1 ^0))"#)),
+    );
+    definitions.insert(
+        "two".into(),
+        (Term::Universe, parse(r#"\f \x (^1 (^1 ^0))"#)),
+    );
+
+    assert_steps_normalize(r#"(twice id)"#, &definitions);
+    assert_steps_normalize(r#"(two twice ^0)"#, &definitions);
+    assert_steps_normalize(r#"[/x (^0 id) ^0]"#, &definitions);
+    assert_steps_normalize(
+        r#"
+        : X = . twice
+        (^0 ^0 id)
+    "#,
+        &definitions,
+    );
+}
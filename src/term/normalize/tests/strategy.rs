@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use crate::{
+    analysis::Empty,
+    term::{Direction, NormalizationError, ReductionStrategy, Term},
+};
+
+use super::parse;
+
+use ReductionStrategy::*;
+
+#[track_caller]
+fn assert_reduces(strategy: ReductionStrategy, term: &str, target: &str) {
+    let mut term: Term<String> = parse(term);
+    term.reduce(strategy, &Empty).unwrap();
+    let target = parse(target);
+    assert!(
+        term.equals(&target),
+        "{:?} reduced to {:?}, not {:?}",
+        strategy,
+        term,
+        target
+    );
+}
+
+#[test]
+fn weak_head() {
+    for erase in [false, true] {
+        assert_reduces(WeakHead { erase }, r#"\x (\y y x)"#, r#"\x (\y y x)"#);
+        assert_reduces(
+            WeakHead { erase },
+            r#"(\y y \x (\z z x))"#,
+            r#"\x (\z z x)"#,
+        );
+        assert_reduces(WeakHead { erase }, r#"(^0 (\y y ^0))"#, r#"(^0 (\y y ^0))"#);
+    }
+}
+
+#[test]
+fn head() {
+    assert_reduces(
+        Head { erase: true },
+        r#"(\y y \x (\z z (x (\w w x))))"#,
+        r#"\x (x (\w w x))"#,
+    );
+}
+
+#[test]
+fn normal_order() {
+    assert_reduces(
+        NormalOrder { erase: true },
+        r#"(\y y \x (\z z (x (\w w x))))"#,
+        r#"\x (x x)"#,
+    );
+    assert_reduces(
+        NormalOrder { erase: true },
+        r#"/a \x (\y y [x a])"#,
+        r#"\x x"#,
+    );
+    assert_reduces(
+        NormalOrder { erase: false },
+        r#"/a \x (\y y [x a])"#,
+        r#"/a \x [x a]"#,
+    );
+    assert_reduces(
+        NormalOrder { erase: false },
+        r#"(/y \x (x y) *)"#,
+        r#"\x (x *)"#,
+    );
+}
+
+#[test]
+fn applicative_order() {
+    assert_reduces(
+        ApplicativeOrder { erase: true },
+        r#"/a \x (\y y [x a])"#,
+        r#"\x x"#,
+    );
+    assert_reduces(
+        ApplicativeOrder { erase: false },
+        r#"/a \x (\y y [x a])"#,
+        r#"/a \x [x a]"#,
+    );
+}
+
+#[test]
+fn boxes() {
+    for strategy in [
+        NormalOrder { erase: false },
+        ApplicativeOrder { erase: false },
+    ] {
+        assert_reduces(strategy, r#": x = . \y y (x x)"#, r#"\y y"#);
+        assert_reduces(strategy, r#"\a : x = a . (x x)"#, r#"\a : x = a . (x x)"#);
+        assert_reduces(strategy, r#"\a : x = : y = a . y . x"#, r#"\a : y = a . y"#);
+    }
+
+    for strategy in [
+        NormalOrder { erase: true },
+        ApplicativeOrder { erase: true },
+    ] {
+        assert_reduces(strategy, r#"\a : x = a . (x x)"#, r#"\a (a a)"#);
+    }
+}
+
+#[test]
+fn orders_agree() {
+    for term in [
+        r#"(\f \x (f (f x)) \f \x (f (f x)))"#,
+        r#"\a (\x \y (y x) (\z z a))"#,
+        r#"(\n /A \f \x (f [n A]) \y y)"#,
+        r#": n = . \f \x (f (f x)) . (n n)"#,
+        r#"\a (\x [x *] [\y \z y a])"#,
+    ] {
+        for erase in [false, true] {
+            let mut normal: Term<String> = parse(term);
+            let mut applicative: Term<String> = parse(term);
+            normal.reduce(NormalOrder { erase }, &Empty).unwrap();
+            applicative
+                .reduce(ApplicativeOrder { erase }, &Empty)
+                .unwrap();
+            assert!(
+                normal.equals(&applicative),
+                "{:?} and {:?}",
+                normal,
+                applicative
+            );
+        }
+
+        let mut normal: Term<String> = parse(term);
+        let mut normalized: Term<String> = parse(term);
+        normal.reduce(NormalOrder { erase: true }, &Empty).unwrap();
+        normalized.normalize(&Empty).unwrap();
+        assert!(normal.equals(&normalized));
+    }
+}
+
+#[test]
+fn invalid_application() {
+    let mut term: Term<String> = parse(r#"(. \x x *)"#);
+    assert!(matches!(
+        term.reduce(WeakHead { erase: false }, &Empty),
+        Err(NormalizationError::InvalidApplication(_))
+    ));
+
+    let mut term: Term<String> = parse(r#"(. \x x *)"#);
+    term.reduce(WeakHead { erase: true }, &Empty).unwrap();
+    assert!(term.equals(&parse("*")));
+}
+
+#[test]
+fn invalid_application_redex() {
+    let definitions: HashMap<_, (Term<String>, Term<String>)> =
+        [("bad".to_owned(), (parse("*"), parse(r#"\x (. x x)"#)))].into();
+
+    let mut term: Term<String> = parse(r#"((bad *) *)"#);
+    let error = term
+        .reduce(WeakHead { erase: false }, &definitions)
+        .unwrap_err();
+
+    let redex = error.redex();
+    assert!(redex.term.equals(&parse(r#"(. * *)"#)));
+    assert_eq!(redex.path, Some(vec![Direction::Function].into()));
+    assert_eq!(redex.definition.as_deref(), Some("bad"));
+    assert_eq!(
+        error.to_string(),
+        "Application of a put at function while unfolding bad\n    (. * *)"
+    );
+
+    // The term is left as far as it was reduced.
+    assert!(term.equals(&parse(r#"((. * *) *)"#)));
+}
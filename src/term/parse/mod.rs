@@ -9,17 +9,20 @@ pub mod typed;
 pub mod untyped;
 
 use combine::{
+    between, choice,
     easy::{Error, Errors, Info},
+    error::StreamError,
     many, many1, parser,
     parser::{
-        char::{alpha_num, digit, spaces},
+        char::{alpha_num, digit, hex_digit, spaces},
         combinator::no_partial,
     },
-    stream::PointerOffset,
+    satisfy,
+    stream::{PointerOffset, StreamErrorFor},
     token as bare_token, value, EasyParser, Parser, Stream,
 };
 
-use super::{Index, Term};
+use super::{Index, None, Primitives, Term};
 
 pub trait Referent<Input: Stream>: Clone {
     fn as_str(&self) -> Option<&str>;
@@ -40,6 +43,102 @@ impl<Input: Stream<Token = char>> Referent<Input> for String {
     }
 }
 
+// Turns literals in source into primitives. Integer literals are names that start with a digit,
+// optionally preceded by `-`, like `42u8` or `-3i8`. String literals are double-quoted and take the
+// escapes of Rust strings. A primitive can also be referred to by a name prefixed with `#`, like
+// `#u8.add`, which may contain `.` and `_`.
+//
+// A name that starts with a digit is only an integer literal if `integer` returns a result, and is
+// otherwise a reference as it would be without primitives. Returning an error instead fails the
+// parse, so a hook should only claim names it means as literals, or definitions whose names start
+// with a digit can't be referred to. Any other literal the hook doesn't accept is a parse error,
+// which the default methods make of every one.
+pub trait PrimitiveParser<T>: Primitives<T> + Clone {
+    fn integer(_literal: &str) -> Option<Result<Self, String>> {
+        None
+    }
+    fn string(_literal: String) -> Result<Self, String> {
+        Err("string literals aren't supported".into())
+    }
+    fn named(name: &str) -> Result<Self, String> {
+        Err(format!("unknown primitive #{}", name))
+    }
+}
+
+impl<T> PrimitiveParser<T> for None {}
+
+fn primitive<Input: Stream, T, U: PrimitiveParser<T>>(
+    primitive: Result<U, String>,
+) -> Result<Term<T, U>, StreamErrorFor<Input>> {
+    primitive
+        .map(Term::Primitive)
+        .map_err(StreamErrorFor::<Input>::message_format)
+}
+
+fn negative<Input, T, U: PrimitiveParser<T>>() -> impl Parser<Input, Output = Term<T, U>>
+where
+    Input: Stream<Token = char>,
+{
+    many1(alpha_num()).and_then(|literal: String| {
+        let literal = format!("-{}", literal);
+        primitive::<Input, _, _>(
+            U::integer(&literal)
+                .unwrap_or_else(|| Err(format!("invalid integer literal {}", literal))),
+        )
+    })
+}
+
+fn string<Input, T, U: PrimitiveParser<T>>() -> impl Parser<Input, Output = Term<T, U>>
+where
+    Input: Stream<Token = char>,
+{
+    let unicode =
+        between(bare_token('{'), bare_token('}'), many1(hex_digit())).and_then(|digits: String| {
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| {
+                    StreamErrorFor::<Input>::message_format(format!(
+                        "invalid unicode escape \\u{{{}}}",
+                        digits
+                    ))
+                })
+        });
+    let escape = bare_token('\\').with(choice((
+        bare_token('n').map(|_| '\n'),
+        bare_token('r').map(|_| '\r'),
+        bare_token('t').map(|_| '\t'),
+        bare_token('0').map(|_| '\0'),
+        bare_token('\\'),
+        bare_token('\''),
+        bare_token('"'),
+        bare_token('u').with(unicode),
+    )));
+    many(escape.or(satisfy(|c| c != '"' && c != '\\')))
+        .skip(bare_token('"'))
+        .and_then(|literal| primitive::<Input, _, _>(U::string(literal)))
+}
+
+fn named<Input, T, U: PrimitiveParser<T>>() -> impl Parser<Input, Output = Term<T, U>>
+where
+    Input: Stream<Token = char>,
+{
+    many1(satisfy(|c: char| {
+        c.is_alphanumeric() || c == '.' || c == '_'
+    }))
+    .and_then(|name: String| primitive::<Input, _, _>(U::named(&name)))
+}
+
+parser! {
+    fn literal[Input, T, U]()(Input) -> Term<T, U>
+        where [Input: Stream<Token = char>, U: PrimitiveParser<T>]
+    {
+        bare_token('-').with(negative())
+            .or(bare_token('"').with(string()))
+            .or(bare_token('#').with(named()))
+    }
+}
+
 fn name<Input>() -> impl Parser<Input, Output = String>
 where
     Input: Stream<Token = char>,
@@ -61,7 +160,8 @@ where
     spaces().with(bare_token(token))
 }
 
-fn variable<Input, T: Referent<Input>>() -> impl Parser<Input, Output = Term<T>>
+fn variable<Input, T: Referent<Input>, U: PrimitiveParser<T>>(
+) -> impl Parser<Input, Output = Term<T, U>>
 where
     Input: Stream<Token = char>,
 {
@@ -71,8 +171,8 @@ where
 }
 
 parser! {
-    fn lambda[Input, T](erased: bool, ctx: Context)(Input) -> Term<T>
-        where [Input: Stream<Token = char>, T: Referent<Input>]
+    fn lambda[Input, T, U](erased: bool, ctx: Context)(Input) -> Term<T, U>
+        where [Input: Stream<Token = char>, T: Referent<Input>, U: PrimitiveParser<T>]
     {
         let erased = *erased;
         name().then(|name| term(ctx.with(name)).map(Box::new)).map(move |body| Term::Lambda { erased, body })
@@ -80,8 +180,8 @@ parser! {
 }
 
 parser! {
-    fn apply[Input, T](erased: bool, ctx: Context)(Input) -> Term<T>
-        where [Input: Stream<Token = char>, T: Referent<Input>]
+    fn apply[Input, T, U](erased: bool, ctx: Context)(Input) -> Term<T, U>
+        where [Input: Stream<Token = char>, T: Referent<Input>, U: PrimitiveParser<T>]
     {
         let erased = *erased;
         (term(ctx.clone()).map(Box::new), many1(term(ctx.clone()))).map(move |(function, arguments): (_, Vec<_>)| {
@@ -103,42 +203,46 @@ parser! {
     }
 }
 
-fn reference<'a, Input: 'a, T: Referent<Input> + 'a>(
+fn reference<'a, Input: 'a, T: Referent<Input> + 'a, U: PrimitiveParser<T> + 'a>(
     ctx: Context,
-) -> impl Parser<Input, Output = Term<T>> + 'a
+) -> impl Parser<Input, Output = Term<T, U>> + 'a
 where
     Input: Stream<Token = char>,
 {
-    T::parse().map(move |name| {
+    T::parse().and_then(move |name| {
         if let Some(ident) = name.as_str() {
-            ctx.resolve(&ident)
-                .map(Term::Variable)
-                .unwrap_or(Term::Reference(name))
-        } else {
-            Term::Reference(name)
+            if let Some(index) = ctx.resolve(&ident) {
+                return Ok(Term::Variable(index));
+            }
+            if ident.starts_with(|c: char| c.is_ascii_digit()) {
+                if let Some(integer) = U::integer(ident) {
+                    return primitive::<Input, _, _>(integer);
+                }
+            }
         }
+        Ok(Term::Reference(name))
     })
 }
 
 parser! {
-    fn _box[Input, T](ctx: Context)(Input) -> Term<T>
-        where [Input: Stream<Token = char>, T: Referent<Input>]
+    fn _box[Input, T, U](ctx: Context)(Input) -> Term<T, U>
+        where [Input: Stream<Token = char>, T: Referent<Input>, U: PrimitiveParser<T>]
     {
         term(ctx.clone()).map(Box::new).map(Term::Put)
     }
 }
 
 parser! {
-    fn wrap[Input, T](ctx: Context)(Input) -> Term<T>
-        where [Input: Stream<Token = char>, T: Referent<Input>]
+    fn wrap[Input, T, U](ctx: Context)(Input) -> Term<T, U>
+        where [Input: Stream<Token = char>, T: Referent<Input>, U: PrimitiveParser<T>]
     {
         term(ctx.clone()).map(Box::new).map(Term::Wrap)
     }
 }
 
 parser! {
-    fn duplicate[Input, T](ctx: Context)(Input) -> Term<T>
-        where [Input: Stream<Token = char>, T: Referent<Input>]
+    fn duplicate[Input, T, U](ctx: Context)(Input) -> Term<T, U>
+        where [Input: Stream<Token = char>, T: Referent<Input>, U: PrimitiveParser<T>]
     {
         name().skip(token('=')).then(move |binding| {
             (
@@ -156,8 +260,8 @@ parser! {
 }
 
 parser! {
-    fn annotation[Input, T](ctx: Context)(Input) -> Term<T>
-        where [Input: Stream<Token = char>, T: Referent<Input>]
+    fn annotation[Input, T, U](ctx: Context)(Input) -> Term<T, U>
+        where [Input: Stream<Token = char>, T: Referent<Input>, U: PrimitiveParser<T>]
     {
         (term(ctx.clone()).skip(token(':')).map(Box::new), term(ctx.clone()).map(Box::new)).map(|(expression, ty)| {
             Term::Annotation {
@@ -193,8 +297,8 @@ impl Context {
 }
 
 parser! {
-    fn function[Input, T](erased: bool, ctx: Context)(Input) -> Term<T>
-        where [Input: Stream<Token = char>, T: Referent<Input>]
+    fn function[Input, T, U](erased: bool, ctx: Context)(Input) -> Term<T, U>
+        where [Input: Stream<Token = char>, T: Referent<Input>, U: PrimitiveParser<T>]
     {
         let erased = *erased;
 
@@ -220,9 +324,9 @@ parser! {
     }
 }
 
-pub fn term<'a, Input: 'a, T: Referent<Input> + 'a>(
+pub fn term<'a, Input: 'a, T: Referent<Input> + 'a, U: PrimitiveParser<T> + 'a>(
     ctx: Context,
-) -> impl Parser<Input, Output = Term<T>> + 'a
+) -> impl Parser<Input, Output = Term<T, U>> + 'a
 where
     Input: Stream<Token = char>,
 {
@@ -238,11 +342,13 @@ where
     let parser = parser.or(token('*').with(value(Term::Universe)));
     let parser = parser.or(token('!').with(wrap(ctx.clone())));
     let parser = parser.or(token('^').with(variable()));
+    let parser = parser.or(literal());
     let parser = parser.or(reference(ctx));
     spaces().with(parser)
 }
 
-pub fn parse<'a, Input: 'a, T: Referent<Input> + 'a>() -> impl Parser<Input, Output = Term<T>> + 'a
+pub fn parse<'a, Input: 'a, T: Referent<Input> + 'a, U: PrimitiveParser<T> + 'a>(
+) -> impl Parser<Input, Output = Term<T, U>> + 'a
 where
     Input: Stream<Token = char>,
 {
@@ -255,10 +361,15 @@ pub struct ParseError {
     got: String,
     expected: Vec<String>,
     position: usize,
+    // Set when a literal is rejected after it was read, which leaves nothing unexpected.
+    message: Option<String>,
 }
 
 impl Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(message) = &self.message {
+            return write!(f, "{} at position {}", message, self.position);
+        }
         write!(f, "Unexpected {} at position {}", self.got, self.position)?;
         if !self.expected.is_empty() {
             write!(
@@ -288,6 +399,11 @@ impl Display for ParseError {
 
 impl<T: Debug, R: Debug, P: ?Sized> From<Errors<T, R, PointerOffset<P>>> for ParseError {
     fn from(e: Errors<T, R, PointerOffset<P>>) -> Self {
+        let message = e.errors.iter().find_map(|e| match e {
+            Error::Message(Info::Owned(message)) => Some(message.clone()),
+            Error::Message(Info::Static(message)) => Some((*message).to_owned()),
+            _ => None,
+        });
         ParseError {
             position: e.position.0,
             got: e
@@ -300,7 +416,9 @@ impl<T: Debug, R: Debug, P: ?Sized> From<Errors<T, R, PointerOffset<P>>> for Par
                     }),
                     _ => None,
                 })
+                .or_else(|| message.clone())
                 .unwrap(),
+            message,
             expected: {
                 let mut expected: Vec<String> = e
                     .errors
@@ -321,7 +439,7 @@ impl<T: Debug, R: Debug, P: ?Sized> From<Errors<T, R, PointerOffset<P>>> for Par
     }
 }
 
-impl<T> FromStr for Term<T>
+impl<T, U: PrimitiveParser<T>> FromStr for Term<T, U>
 where
     for<'a> T: Referent<combine::easy::Stream<&'a str>>,
 {
@@ -342,6 +460,7 @@ where
                         got: format!("{:?}", remainder),
                         expected: vec!["end of input".into()],
                         position: s.len(),
+                        message: None,
                     })
                 } else {
                     Ok(a)
@@ -1,42 +1,57 @@
-use combine::{many, EasyParser, Parser, Stream};
+use combine::{attempt, many, optional, parser::char::string, EasyParser, Parser, Stream};
+use derivative::Derivative;
 use std::str::FromStr;
 
-use crate::term::Term;
+use crate::term::{None, Primitives, Term};
 
-use super::{term, token, untyped, Context, ParseError, Referent};
+use super::{term, token, untyped, Context, ParseError, PrimitiveParser, Referent};
 
-fn definition<'a, Input: 'a, T: Referent<Input> + 'a>(
+// The type and the term of a definition.
+type Typed<T, U> = (Term<T, U>, Term<T, U>);
+
+fn opaque<Input>() -> impl Parser<Input, Output = bool>
+where
+    Input: Stream<Token = char>,
+{
+    optional(attempt(token('@').with(string("opaque")))).map(|attribute| attribute.is_some())
+}
+
+fn definition<'a, Input: 'a, T: Referent<Input> + 'a, U: PrimitiveParser<T> + 'a>(
     ctx: Context,
-) -> impl Parser<Input, Output = (T, (Term<T>, Term<T>))> + 'a
+) -> impl Parser<Input, Output = (bool, T, Typed<T, U>)> + 'a
 where
     Input: Stream<Token = char>,
 {
     (
+        opaque(),
         T::parse().skip(token(':')),
         term(ctx.clone()).skip(token('=')),
         term(ctx),
     )
-        .map(|(a, b, c)| (a, (b, c)))
+        .map(|(a, b, c, d)| (a, b, (c, d)))
 }
 
-fn definitions<'a, Input: 'a, T: Referent<Input> + 'a>(
+fn definitions<'a, Input: 'a, T: Referent<Input> + 'a, U: PrimitiveParser<T> + 'a>(
     ctx: Context,
-) -> impl Parser<Input, Output = Vec<(T, (Term<T>, Term<T>))>> + 'a
+) -> impl Parser<Input, Output = Vec<(bool, T, Typed<T, U>)>> + 'a
 where
     Input: Stream<Token = char>,
 {
     many(definition(ctx))
 }
 
-#[derive(Clone, Default)]
-pub struct Definitions<T = String> {
-    pub terms: Vec<(T, (Term<T>, Term<T>))>,
+#[derive(Derivative)]
+#[derivative(Clone(bound = "T: Clone, U: Clone"), Default(bound = ""))]
+pub struct Definitions<T = String, U: Primitives<T> = None> {
+    pub terms: Vec<(T, Typed<T, U>)>,
+    pub opaque: Vec<T>,
 }
 
-impl<T> Definitions<T> {
-    pub fn untyped(&self) -> untyped::Definitions<T>
+impl<T, U: Primitives<T>> Definitions<T, U> {
+    pub fn untyped(&self) -> untyped::Definitions<T, U>
     where
         T: Clone,
+        U: Clone,
     {
         untyped::Definitions {
             terms: self
@@ -49,7 +64,7 @@ impl<T> Definitions<T> {
     }
 }
 
-impl<T> FromStr for Definitions<T>
+impl<T, U: PrimitiveParser<T>> FromStr for Definitions<T, U>
 where
     for<'a> T: Referent<combine::easy::Stream<&'a str>>,
 {
@@ -69,15 +84,24 @@ where
                 position = Some(e.position);
                 ParseError::from(e)
             })
-            .and_then(|(terms, remainder)| {
+            .and_then(|(definitions, remainder): (Vec<(bool, T, _)>, _)| {
                 if !remainder.is_empty() {
                     Err(ParseError {
                         got: format!("{:?}", remainder),
                         expected: vec!["end of input".into()],
                         position: s.len(),
+                        message: None,
                     })
                 } else {
-                    Ok(Definitions { terms })
+                    let mut terms = vec![];
+                    let mut opaque = vec![];
+                    for (is_opaque, name, definition) in definitions {
+                        if is_opaque {
+                            opaque.push(name.clone());
+                        }
+                        terms.push((name, definition));
+                    }
+                    Ok(Definitions { terms, opaque })
                 }
             })
             .map_err(|mut e| {
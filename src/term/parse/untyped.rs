@@ -1,34 +1,36 @@
 use combine::{many, EasyParser, Parser, Stream};
+use derivative::Derivative;
 use std::str::FromStr;
 
-use crate::term::Term;
+use crate::term::{None, Primitives, Term};
 
-use super::{term, token, Context, ParseError, Referent};
+use super::{term, token, Context, ParseError, PrimitiveParser, Referent};
 
-fn definition<'a, Input: 'a, T: Referent<Input> + 'a>(
+fn definition<'a, Input: 'a, T: Referent<Input> + 'a, U: PrimitiveParser<T> + 'a>(
     ctx: Context,
-) -> impl Parser<Input, Output = (T, Term<T>)> + 'a
+) -> impl Parser<Input, Output = (T, Term<T, U>)> + 'a
 where
     Input: Stream<Token = char>,
 {
     (T::parse().skip(token('=')), term(ctx))
 }
 
-fn definitions<'a, Input: 'a, T: Referent<Input> + 'a>(
+fn definitions<'a, Input: 'a, T: Referent<Input> + 'a, U: PrimitiveParser<T> + 'a>(
     ctx: Context,
-) -> impl Parser<Input, Output = Vec<(T, Term<T>)>> + 'a
+) -> impl Parser<Input, Output = Vec<(T, Term<T, U>)>> + 'a
 where
     Input: Stream<Token = char>,
 {
     many(definition(ctx))
 }
 
-#[derive(Clone, Default)]
-pub struct Definitions<T = String> {
-    pub terms: Vec<(T, Term<T>)>,
+#[derive(Derivative)]
+#[derivative(Clone(bound = "T: Clone, U: Clone"), Default(bound = ""))]
+pub struct Definitions<T = String, U: Primitives<T> = None> {
+    pub terms: Vec<(T, Term<T, U>)>,
 }
 
-impl<T> FromStr for Definitions<T>
+impl<T, U: PrimitiveParser<T>> FromStr for Definitions<T, U>
 where
     for<'a> T: Referent<combine::easy::Stream<&'a str>>,
 {
@@ -54,6 +56,7 @@ where
                         got: format!("{:?}", remainder),
                         expected: vec!["end of input".into()],
                         position: s.len(),
+                        message: None,
                     })
                 } else {
                     Ok(Definitions { terms })
@@ -0,0 +1,158 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Allocator, Primitives, Term};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    Body,
+    Function,
+    Argument,
+    Contents,
+    Expression,
+    ArgumentType,
+    ReturnType,
+    Type,
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Direction::*;
+
+        write!(
+            f,
+            "{}",
+            match self {
+                Body => "body",
+                Function => "function",
+                Argument => "argument",
+                Contents => "contents",
+                Expression => "expression",
+                ArgumentType => "argument_type",
+                ReturnType => "return_type",
+                Type => "type",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Path(Vec<Direction>);
+
+impl Path {
+    pub fn root() -> Self {
+        Path(vec![])
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn directions(&self) -> &[Direction] {
+        &self.0
+    }
+
+    pub fn push(&mut self, direction: Direction) {
+        self.0.push(direction);
+    }
+
+    pub fn pop(&mut self) -> Option<Direction> {
+        self.0.pop()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn truncate(&mut self, length: usize) {
+        self.0.truncate(length);
+    }
+
+    pub fn child(&self, direction: Direction) -> Self {
+        let mut path = self.clone();
+        path.push(direction);
+        path
+    }
+}
+
+impl From<Vec<Direction>> for Path {
+    fn from(directions: Vec<Direction>) -> Self {
+        Path(directions)
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_root() {
+            return write!(f, "<root>");
+        }
+
+        let mut directions = self.0.iter().peekable();
+        while let Some(direction) = directions.next() {
+            write!(f, "{}", direction)?;
+            if directions.peek().is_some() {
+                write!(f, ".")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+    pub fn child(&self, direction: Direction) -> Option<&Self> {
+        use Term::*;
+
+        Some(match (self, direction) {
+            (Lambda { body, .. }, Direction::Body) | (Duplicate { body, .. }, Direction::Body) => {
+                body
+            }
+            (Apply { function, .. }, Direction::Function) => function,
+            (Apply { argument, .. }, Direction::Argument) => argument,
+            (Put(term), Direction::Contents) | (Wrap(term), Direction::Contents) => term,
+            (Duplicate { expression, .. }, Direction::Expression)
+            | (Annotation { expression, .. }, Direction::Expression) => expression,
+            (Function { argument_type, .. }, Direction::ArgumentType) => argument_type,
+            (Function { return_type, .. }, Direction::ReturnType) => return_type,
+            (Annotation { ty, .. }, Direction::Type) => ty,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn child_mut(&mut self, direction: Direction) -> Option<&mut Self> {
+        use Term::*;
+
+        Some(match (self, direction) {
+            (Lambda { body, .. }, Direction::Body) | (Duplicate { body, .. }, Direction::Body) => {
+                body
+            }
+            (Apply { function, .. }, Direction::Function) => function,
+            (Apply { argument, .. }, Direction::Argument) => argument,
+            (Put(term), Direction::Contents) | (Wrap(term), Direction::Contents) => term,
+            (Duplicate { expression, .. }, Direction::Expression)
+            | (Annotation { expression, .. }, Direction::Expression) => expression,
+            (Function { argument_type, .. }, Direction::ArgumentType) => argument_type,
+            (Function { return_type, .. }, Direction::ReturnType) => return_type,
+            (Annotation { ty, .. }, Direction::Type) => ty,
+            _ => return None,
+        })
+    }
+
+    pub fn subterm(&self, path: &Path) -> Option<&Self> {
+        let mut term = self;
+        for direction in path.directions() {
+            term = term.child(*direction)?;
+        }
+        Some(term)
+    }
+
+    pub(crate) fn subterm_mut(&mut self, path: &Path) -> Option<&mut Self> {
+        let mut term = self;
+        for direction in path.directions() {
+            term = term.child_mut(*direction)?;
+        }
+        Some(term)
+    }
+}
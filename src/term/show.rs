@@ -16,6 +16,31 @@ pub fn debug_reference<T: Show>(data: &T, f: &mut fmt::Formatter<'_>) -> fmt::Re
     Show::fmt(data, f)
 }
 
+pub fn debug_optional_reference<T: Show>(
+    data: &Option<T>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    match data {
+        Some(data) => {
+            write!(f, "Some(")?;
+            Show::fmt(data, f)?;
+            write!(f, ")")
+        }
+        None => write!(f, "None"),
+    }
+}
+
+pub fn debug_references<T: Show>(data: &[T], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "[")?;
+    for (index, data) in data.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        Show::fmt(data, f)?;
+    }
+    write!(f, "]")
+}
+
 impl<T: Show, U: Primitives<T> + Show, A: Allocator<T, U>> Term<T, U, A> {
     fn write(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use Term::*;
@@ -65,3 +90,101 @@ impl<T: Show, U: Primitives<T> + Show, A: Allocator<T, U>> Debug for Term<T, U,
         self.write(f)
     }
 }
+
+pub struct Named<'a, T, U: Primitives<T>, A: Allocator<T, U>>(&'a Term<T, U, A>);
+
+fn binder_name(depth: usize) -> String {
+    let letter = (b'a' + (depth % 26) as u8) as char;
+    match depth / 26 {
+        0 => letter.to_string(),
+        suffix => format!("{}{}", letter, suffix),
+    }
+}
+
+impl<T: Show, U: Primitives<T> + Show, A: Allocator<T, U>> Term<T, U, A> {
+    // Displays the term with a generated name for every binder in place of de Bruijn indices.
+    // Variables free in the term keep their index, relative to the outside of the term.
+    pub fn named(&self) -> Named<'_, T, U, A> {
+        Named(self)
+    }
+
+    fn write_named(&self, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Term::*;
+
+        match &self {
+            Variable(symbol) => {
+                if symbol.0 < depth {
+                    write!(f, "{}", binder_name(depth - 1 - symbol.0))
+                } else {
+                    write!(f, "^{}", symbol.0 - depth)
+                }
+            }
+            Lambda { body, erased } => {
+                write!(
+                    f,
+                    "{}{} ",
+                    if *erased { "/" } else { "\\" },
+                    binder_name(depth)
+                )?;
+                body.write_named(depth + 1, f)
+            }
+            Apply {
+                function,
+                argument,
+                erased,
+            } => {
+                write!(f, "{}", if *erased { "[" } else { "(" })?;
+                function.write_named(depth, f)?;
+                write!(f, " ")?;
+                argument.write_named(depth, f)?;
+                write!(f, "{}", if *erased { "]" } else { ")" })
+            }
+            Put(term) => {
+                write!(f, ". ")?;
+                term.write_named(depth, f)
+            }
+            Reference(name) => name.fmt(f),
+            Duplicate { expression, body } => {
+                write!(f, ": {} = ", binder_name(depth))?;
+                expression.write_named(depth, f)?;
+                write!(f, " ")?;
+                body.write_named(depth + 1, f)
+            }
+            Universe => write!(f, "*"),
+            Wrap(term) => {
+                write!(f, "!")?;
+                term.write_named(depth, f)
+            }
+            Annotation { expression, ty, .. } => {
+                write!(f, "{{ ")?;
+                expression.write_named(depth, f)?;
+                write!(f, " : ")?;
+                ty.write_named(depth, f)?;
+                write!(f, " }}")
+            }
+            Function {
+                argument_type,
+                return_type,
+                erased,
+            } => {
+                write!(
+                    f,
+                    "{}{},{}:",
+                    if *erased { "_" } else { "+" },
+                    binder_name(depth),
+                    binder_name(depth + 1)
+                )?;
+                argument_type.write_named(depth, f)?;
+                write!(f, " ")?;
+                return_type.write_named(depth + 2, f)
+            }
+            Primitive(prim) => prim.fmt(f),
+        }
+    }
+}
+
+impl<'a, T: Show, U: Primitives<T> + Show, A: Allocator<T, U>> Display for Named<'a, T, U, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_named(0, f)
+    }
+}
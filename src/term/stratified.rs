@@ -2,13 +2,17 @@ use derivative::Derivative;
 
 use crate::convert::{NetBuilderExt, NetError};
 
-use std::fmt::Debug;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Display},
+    hash::Hash,
+};
 
 use super::{
-    alloc::{Allocator, Reallocate, System},
-    debug_reference,
-    normalize::NormalizationError,
-    Definitions, Index, None, Primitives, Show, Term,
+    alloc::{Allocator, Reallocate, System, Zero},
+    debug_reference, debug_references,
+    normalize::{NormalizationError, NormalizationStatistics},
+    Definitions, Direction, Index, None, Path, Primitives, Show, Term,
 };
 
 pub struct Stratified<'a, 'b, T, U: Definitions<T, V, A>, V: Primitives<T>, A: Allocator<T, V>>(
@@ -28,7 +32,7 @@ impl<'a, 'b, T: Clone, U: Definitions<T, V, A>, V: Primitives<T> + Clone, A: All
 impl<'a, 'b, T, U: Definitions<T, V, A>, V: Primitives<T>, A: Allocator<T, V>>
     Stratified<'a, 'b, T, U, V, A>
 {
-    pub fn normalize(&mut self) -> Result<(), NormalizationError>
+    pub fn normalize(&mut self) -> Result<(), NormalizationError<T, V, A>>
     where
         T: Clone,
         V: Clone,
@@ -38,6 +42,17 @@ impl<'a, 'b, T, U: Definitions<T, V, A>, V: Primitives<T>, A: Allocator<T, V>>
         Ok(())
     }
 
+    pub fn normalize_with_statistics(
+        &mut self,
+    ) -> Result<NormalizationStatistics<T>, NormalizationError<T, V, A>>
+    where
+        T: Clone + Hash + Eq,
+        V: Clone,
+        A: Reallocate<T, V, A>,
+    {
+        self.0.normalize_in_with_statistics(self.1, self.2)
+    }
+
     pub fn into_inner(self) -> Term<T, V, A> {
         self.0
     }
@@ -54,273 +69,508 @@ impl<'a, 'b, T, U: Definitions<T, None, A>, A: Allocator<T, None>>
 #[derive(Derivative)]
 #[derivative(Debug(bound = "T: Show"))]
 pub enum StratificationError<T> {
-    MultiplicityMismatch,
-    AffineUsedInBox,
-    DupNonUnitBoxMultiplicity,
+    // The variable of the lambda at `binder` is used `uses` times, more than the once allowed.
+    MultiplicityMismatch {
+        binder: Path,
+        uses: usize,
+    },
+    // The variable of the lambda at `binder` occurs at `occurrence` inside `depth` boxes.
+    AffineUsedInBox {
+        binder: Path,
+        occurrence: Path,
+        depth: usize,
+    },
+    // The variable of the duplication at `binder` occurs at `occurrence` inside `depth` boxes
+    // rather than exactly one.
+    DupNonUnitBoxMultiplicity {
+        binder: Path,
+        occurrence: Path,
+        depth: usize,
+    },
     RecursiveDefinition,
+    // A reference, in the term or a definition it reaches, to a name that isn't defined.
     UndefinedReference(#[derivative(Debug(format_with = "debug_reference"))] T),
-    ErasedUsed,
+    // The variable of the erased lambda at `binder` occurs at `occurrence`, which survives
+    // erasure.
+    ErasedUsed {
+        binder: Path,
+        occurrence: Path,
+    },
+    // The argument at `argument` is inside `depth` boxes, but `definition` needs its parameter
+    // inside `expected`.
+    ArgumentDepth {
+        argument: Path,
+        #[derivative(Debug(format_with = "debug_reference"))]
+        definition: T,
+        expected: usize,
+        depth: usize,
+    },
+    // `definition` needs its parameter inside `expected` boxes, but how many the argument at
+    // `argument` is inside can't be worked out.
+    UnknownArgumentDepth {
+        argument: Path,
+        #[derivative(Debug(format_with = "debug_reference"))]
+        definition: T,
+        expected: usize,
+    },
+    // `error` is in the definition reached through the references in `chain`, from the one the
+    // checked term makes on.
+    InDefinition {
+        #[derivative(Debug(format_with = "debug_references"))]
+        chain: Vec<T>,
+        error: Box<StratificationError<T>>,
+    },
+}
+
+fn times(count: usize) -> String {
+    match count {
+        1 => "once".to_owned(),
+        count => format!("{} times", count),
+    }
+}
+
+fn boxes(count: usize) -> String {
+    match count {
+        1 => "1 box".to_owned(),
+        count => format!("{} boxes", count),
+    }
+}
+
+impl<T: Show> Display for StratificationError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use StratificationError::*;
+
+        match self {
+            MultiplicityMismatch { binder, uses } => write!(
+                f,
+                "The variable of the lambda at {} is used {}, but a lambda is linear and may use \
+                 its variable at most once; duplicate a boxed value to use it more often",
+                binder,
+                times(*uses)
+            ),
+            AffineUsedInBox {
+                binder,
+                occurrence,
+                depth,
+            } => write!(
+                f,
+                "The variable of the lambda at {} is used at {} inside {}, but a lambda's variable \
+                 must be used at the box depth of the lambda itself",
+                binder,
+                occurrence,
+                boxes(*depth)
+            ),
+            DupNonUnitBoxMultiplicity {
+                binder,
+                occurrence,
+                depth,
+            } => write!(
+                f,
+                "The variable of the duplication at {} is used at {} inside {}, but a duplicated \
+                 variable must be used inside exactly one more box than the duplication",
+                binder,
+                occurrence,
+                boxes(*depth)
+            ),
+            RecursiveDefinition => write!(
+                f,
+                "The term depends on a recursive definition, which can't be stratified"
+            ),
+            UndefinedReference(name) => {
+                write!(f, "The term refers to ")?;
+                name.fmt(f)?;
+                write!(f, ", which isn't defined")
+            }
+            ErasedUsed { binder, occurrence } => write!(
+                f,
+                "The variable of the erased lambda at {} is used at {}, but an erased variable may \
+                 only appear in erased arguments and types, which don't exist at runtime",
+                binder, occurrence
+            ),
+            ArgumentDepth {
+                argument,
+                definition,
+                expected,
+                depth,
+            } => {
+                write!(
+                    f,
+                    "The argument at {} is inside {}, but ",
+                    argument,
+                    boxes(*depth)
+                )?;
+                definition.fmt(f)?;
+                write!(f, " needs its parameter inside {}", boxes(*expected))
+            }
+            UnknownArgumentDepth {
+                argument,
+                definition,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "The box depth of the argument at {} can't be worked out, but ",
+                    argument
+                )?;
+                definition.fmt(f)?;
+                write!(f, " needs its parameter inside {}", boxes(*expected))
+            }
+            InDefinition { chain, error } => {
+                write!(f, "In ")?;
+                for (index, name) in chain.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " -> ")?;
+                    }
+                    name.fmt(f)?;
+                }
+                write!(f, ": {}", error)
+            }
+        }
+    }
 }
 
 impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
-    fn uses(&self) -> usize {
-        fn uses_helper<T, V: Primitives<T>, A: Allocator<T, V>>(
-            term: &Term<T, V, A>,
-            variable: Index,
-        ) -> usize {
-            use Term::*;
+    // The number of occurrences of the variable bound just outside this term that survive
+    // erasure.
+    pub(super) fn uses(&self) -> usize {
+        self.occurrences(Path::root())
+            .iter()
+            .filter(|usage| !usage.erased)
+            .count()
+    }
+
+    // The path and box depth of the first occurrence of the variable bound just outside this term
+    // whose box depth is `misplaced`, with the path continuing `path`. Only occurrences that
+    // survive erasure are considered, although types, which a term is never erased to, are
+    // searched too.
+    fn find_occurrence(
+        &self,
+        misplaced: impl Fn(usize) -> bool,
+        path: Path,
+    ) -> Option<(Path, usize)> {
+        self.find_occurrence_of(Index::top(), misplaced, path)
+    }
+
+    pub(super) fn find_occurrence_of(
+        &self,
+        variable: Index,
+        misplaced: impl Fn(usize) -> bool,
+        mut path: Path,
+    ) -> Option<(Path, usize)> {
+        use Term::*;
+
+        // Each subterm left to search, with the variable as it's indexed there, the boxes around
+        // it, the length of its parent's path and the direction from there.
+        let mut stack = vec![(self, variable, 0, path.len(), None)];
+        while let Some((term, variable, nestings, parent, direction)) = stack.pop() {
+            path.truncate(parent);
+            if let Some(direction) = direction {
+                path.push(direction);
+            }
+
+            let length = path.len();
+            let mut visit = |term, direction, variable, nestings| {
+                stack.push((term, variable, nestings, length, Some(direction)))
+            };
+
+            // Children are pushed last first, so they're searched in order.
             match term {
+                Reference(_) | Primitive(_) | Universe | Function { .. } => {}
                 Variable(index) => {
-                    if *index == variable {
-                        1
-                    } else {
-                        0
-                    }
-                }
-                Reference(_) | Function { .. } | Universe => 0,
-                Lambda { body, erased } => {
-                    if *erased {
-                        0
-                    } else {
-                        uses_helper(body, variable.child())
+                    if *index == variable && misplaced(nestings) {
+                        return Some((path, nestings));
                     }
                 }
+                Lambda { body, .. } => visit(body, Direction::Body, variable.child(), nestings),
                 Apply {
                     function,
                     argument,
                     erased,
                 } => {
-                    uses_helper(function, variable)
-                        + if *erased {
-                            0
-                        } else {
-                            uses_helper(argument, variable)
-                        }
+                    if !*erased {
+                        visit(argument, Direction::Argument, variable, nestings);
+                    }
+                    visit(function, Direction::Function, variable, nestings);
+                }
+                Put(term) => visit(term, Direction::Contents, variable, nestings + 1),
+                Duplicate { expression, body } => {
+                    visit(body, Direction::Body, variable.child(), nestings);
+                    visit(expression, Direction::Expression, variable, nestings);
                 }
-                Put(term) => uses_helper(term, variable),
-                Duplicate {
-                    expression, body, ..
-                } => uses_helper(expression, variable) + uses_helper(body, variable.child()),
-                Primitive(_) => todo!(),
 
-                Wrap(term) => uses_helper(term, variable),
-                Annotation { expression, ty, .. } => {
-                    uses_helper(expression, variable) + uses_helper(ty, variable)
+                Wrap(term) => visit(term, Direction::Contents, variable, nestings),
+                Annotation { expression, .. } => {
+                    visit(expression, Direction::Expression, variable, nestings)
                 }
             }
         }
 
-        uses_helper(self, Index::top())
+        None
     }
 
-    fn is_boxed_n_times(&self, nestings: usize) -> bool {
+    // The first reference, in this term or in a definition it reaches, to a name that isn't
+    // defined. Only positions that survive erasure are searched, and each definition once.
+    fn undefined_reference_in<D: Definitions<T, V, B>, B: Allocator<T, V>>(
+        &self,
+        definitions: &D,
+    ) -> Option<T>
+    where
+        T: PartialEq + Clone,
+    {
+        let mut seen = vec![];
+        // The references left to follow, last first.
+        let mut references = self.computational_references();
+        references.reverse();
+        while let Some(name) = references.pop() {
+            if seen.contains(&name) {
+                continue;
+            }
+            seen.push(name.clone());
+            match definitions.get(&name) {
+                Some(definition) => {
+                    let mut inner = definition.as_ref().computational_references();
+                    inner.reverse();
+                    references.extend(inner);
+                }
+                None => return Some(name),
+            }
+        }
+
+        None
+    }
+
+    // The references in this term that survive erasure, in order.
+    fn computational_references(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
         use Term::*;
 
-        fn n_boxes_helper<T, V: Primitives<T>, A: Allocator<T, V>>(
-            this: &Term<T, V, A>,
-            variable: Index,
-            nestings: usize,
-            current_nestings: usize,
-        ) -> bool {
-            match this {
-                Reference(_) | Universe | Function { .. } => true,
-                Variable(index) => *index != variable || nestings == current_nestings,
-                Lambda { body, .. } => {
-                    n_boxes_helper(body, variable.child(), nestings, current_nestings)
-                }
+        let mut references = vec![];
+        let mut stack = vec![self];
+        while let Some(term) = stack.pop() {
+            // Children are pushed last first, so references are found in order.
+            match term {
+                Reference(name) => references.push(name.clone()),
+                Lambda { body, .. } | Put(body) => stack.push(body),
                 Apply {
                     function,
                     argument,
                     erased,
                 } => {
-                    n_boxes_helper(function, variable, nestings, current_nestings)
-                        && (*erased
-                            || n_boxes_helper(argument, variable, nestings, current_nestings))
-                }
-                Put(term) => n_boxes_helper(term, variable, nestings, current_nestings + 1),
-                Duplicate { expression, body } => {
-                    n_boxes_helper(expression, variable, nestings, current_nestings)
-                        && n_boxes_helper(body, variable.child(), nestings, current_nestings)
-                }
-                Primitive(_) => todo!(),
-
-                Wrap(term) => n_boxes_helper(term, variable, nestings, current_nestings),
-                Annotation { expression, .. } => {
-                    n_boxes_helper(expression, variable, nestings, current_nestings)
+                    if !*erased {
+                        stack.push(argument);
+                    }
+                    stack.push(function);
                 }
+                Duplicate { expression, body } => stack.extend([&**body, &**expression]),
+                Annotation { expression, .. } => stack.push(expression),
+                Variable(_) | Primitive(_) | Wrap(_) | Function { .. } | Universe => {}
             }
         }
 
-        n_boxes_helper(self, Index::top(), nestings, 0)
+        references
     }
 
-    fn is_recursive_in_helper<'a, D: Definitions<T, V, B>, B: Allocator<T, V>>(
+    fn is_recursive_in_helper<D: Definitions<T, V, B>, B: Allocator<T, V>>(
         &self,
         seen: &mut Vec<T>,
         definitions: &D,
-        alloc: &A,
-        b_alloc: &B,
     ) -> bool
     where
         T: PartialEq + Clone,
     {
         use Term::*;
 
-        match self {
-            Variable(_) | Universe => false,
-            Lambda { body, .. } => body.is_recursive_in_helper(seen, definitions, alloc, b_alloc),
-            Apply {
-                function,
-                argument,
-                erased,
-            } => {
-                (!*erased && argument.is_recursive_in_helper(seen, definitions, alloc, b_alloc))
-                    || function.is_recursive_in_helper(seen, definitions, alloc, b_alloc)
-            }
-            Put(term) => term.is_recursive_in_helper(seen, definitions, alloc, b_alloc),
-            Duplicate { expression, body } => {
-                expression.is_recursive_in_helper(seen, definitions, alloc, b_alloc)
-                    || body.is_recursive_in_helper(seen, definitions, alloc, b_alloc)
-            }
-            Term::Reference(reference) => {
-                if seen.contains(reference) {
-                    true
-                } else {
-                    if let Some(term) = definitions.get(reference) {
-                        seen.push(reference.clone());
-                        let res = term.as_ref().is_recursive_in_helper(
-                            seen,
-                            definitions,
-                            b_alloc,
-                            b_alloc,
-                        );
-                        seen.pop();
-                        res
+        // What's left to do once the term being looked at is settled: look at another in its
+        // place if it isn't recursive, or only if it is.
+        enum Task<'a, T, V: Primitives<T>, A: Allocator<T, V>> {
+            Visit(&'a Term<T, V, A>),
+            Or(&'a Term<T, V, A>),
+            And(&'a Term<T, V, A>),
+        }
+
+        let mut tasks = vec![Task::Visit(self)];
+        let mut recursive = false;
+        while let Some(task) = tasks.pop() {
+            let term = match task {
+                Task::Visit(term) => term,
+                Task::Or(term) if !recursive => term,
+                Task::And(term) if recursive => term,
+                Task::Or(_) | Task::And(_) => continue,
+            };
+
+            match term {
+                Variable(_) | Universe | Primitive(_) => recursive = false,
+                Lambda { body, .. } => tasks.push(Task::Visit(body)),
+                Apply {
+                    function,
+                    argument,
+                    erased,
+                } => {
+                    tasks.push(Task::Or(function));
+                    if *erased {
+                        recursive = false;
                     } else {
-                        false
+                        tasks.push(Task::Visit(argument));
                     }
                 }
+                Put(term) | Wrap(term) => tasks.push(Task::Visit(term)),
+                Duplicate { expression, body } => {
+                    tasks.push(Task::Or(body));
+                    tasks.push(Task::Visit(expression));
+                }
+                Reference(reference) => {
+                    recursive = seen.contains(reference)
+                        || match definitions.get(reference) {
+                            Some(term) => {
+                                seen.push(reference.clone());
+                                let recursive =
+                                    term.as_ref().is_recursive_in_helper(seen, definitions);
+                                seen.pop();
+                                recursive
+                            }
+                            None => false,
+                        };
+                }
+                Function {
+                    argument_type,
+                    return_type,
+                    ..
+                } => {
+                    tasks.push(Task::And(return_type));
+                    tasks.push(Task::Visit(argument_type));
+                }
+                Annotation { expression, .. } => tasks.push(Task::Visit(expression)),
             }
-            Term::Primitive(_) => false,
-            Term::Function {
-                argument_type,
-                return_type,
-                ..
-            } => {
-                argument_type.is_recursive_in_helper(seen, definitions, alloc, b_alloc)
-                    && return_type.is_recursive_in_helper(seen, definitions, alloc, b_alloc)
-            }
-            Term::Annotation { expression, .. } => {
-                expression.is_recursive_in_helper(seen, definitions, alloc, b_alloc)
-            }
-            Term::Wrap(term) => term.is_recursive_in_helper(seen, definitions, alloc, b_alloc),
         }
+
+        recursive
     }
 
     pub fn is_recursive_in<D: Definitions<T, V, B>, B: Allocator<T, V>>(
         &self,
         definitions: &D,
-        alloc: &A,
-        b_alloc: &B,
+        _alloc: &A,
+        _b_alloc: &B,
     ) -> bool
     where
         T: PartialEq + Clone,
     {
-        self.is_recursive_in_helper(&mut vec![], definitions, alloc, b_alloc)
+        self.is_recursive_in_helper(&mut vec![], definitions)
     }
 
-    pub fn is_sound(&self) -> Result<(), StratificationError<T>>
-    where
-        T: Clone,
-    {
+    // Checks that the variable bound just outside this body, by the erased lambda at `binder`, is
+    // only used in positions that are erased.
+    fn is_erased_at(&self, binder: &Path) -> Result<(), StratificationError<T>> {
+        match self.find_occurrence(|_| true, binder.child(Direction::Body)) {
+            Some((occurrence, _)) => Err(StratificationError::ErasedUsed {
+                binder: binder.clone(),
+                occurrence,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // Visits this term and each of its subterms that survive erasure, outermost first, with its
+    // path, stopping at the first error.
+    fn try_walk<E>(&self, mut visit: impl FnMut(&Self, &Path) -> Result<(), E>) -> Result<(), E> {
         use Term::*;
 
-        match &self {
-            Lambda { body, erased } => {
-                if *erased {
-                    if body.uses() > 0 {
-                        return Err(StratificationError::MultiplicityMismatch);
+        let mut path = Path::root();
+        // Each subterm left to visit, with the length of its parent's path and the direction from
+        // there, which the term itself doesn't have.
+        let mut stack = vec![(self, 0, None)];
+        while let Some((term, parent, direction)) = stack.pop() {
+            path.truncate(parent);
+            if let Some(direction) = direction {
+                path.push(direction);
+            }
+
+            visit(term, &path)?;
+
+            let length = path.len();
+            let mut visit = |term, direction| stack.push((term, length, Some(direction)));
+
+            // Children are pushed last first, so they're visited in order.
+            match term {
+                Lambda { body, .. } => visit(body, Direction::Body),
+                Apply {
+                    function,
+                    argument,
+                    erased,
+                } => {
+                    if !*erased {
+                        visit(argument, Direction::Argument);
                     }
+                    visit(function, Direction::Function);
                 }
-
-                body.is_sound()?;
-            }
-            Apply {
-                function,
-                argument,
-                erased,
-            } => {
-                function.is_sound()?;
-                if !*erased {
-                    argument.is_sound()?;
+                Put(term) | Wrap(term) => visit(term, Direction::Contents),
+                Duplicate { body, expression } => {
+                    visit(body, Direction::Body);
+                    visit(expression, Direction::Expression);
                 }
-            }
-            Put(term) => {
-                term.is_sound()?;
-            }
-            Duplicate { body, expression } => {
-                expression.is_sound()?;
-                body.is_sound()?;
-            }
-            Variable(_) | Reference(_) | Function { .. } | Universe => {}
-            Primitive(_) => todo!(),
-
-            Wrap(term) => term.is_sound()?,
-            Annotation { expression, .. } => {
-                expression.is_sound()?;
+                Annotation { expression, .. } => visit(expression, Direction::Expression),
+                Variable(_) | Reference(_) | Primitive(_) | Function { .. } | Universe => {}
             }
         }
 
         Ok(())
     }
 
+    pub fn is_sound(&self) -> Result<(), StratificationError<T>>
+    where
+        T: Clone,
+    {
+        self.try_walk(|term, path| match term {
+            Term::Lambda { body, erased: true } => body.is_erased_at(path),
+            _ => Ok(()),
+        })
+    }
+
     pub fn is_stratified(&self) -> Result<(), StratificationError<T>>
     where
         T: Clone,
     {
         use Term::*;
 
-        match &self {
-            Lambda { body, erased } => {
-                if body.uses() > if *erased { 0 } else { 1 } {
-                    return Err(StratificationError::MultiplicityMismatch);
-                }
-                if !body.is_boxed_n_times(0) {
-                    return Err(StratificationError::AffineUsedInBox);
-                }
-
-                body.is_stratified()?;
-            }
-            Apply {
-                function,
-                argument,
-                erased,
-            } => {
-                function.is_stratified()?;
-                if !*erased {
-                    argument.is_stratified()?;
+        self.try_walk(|term, path| {
+            match term {
+                Lambda { body, erased: true } => body.is_erased_at(path)?,
+                Lambda { body, .. } => {
+                    let uses = body.uses();
+                    if uses > 1 {
+                        return Err(StratificationError::MultiplicityMismatch {
+                            binder: path.clone(),
+                            uses,
+                        });
+                    }
+                    if let Some((occurrence, depth)) =
+                        body.find_occurrence(|depth| depth != 0, path.child(Direction::Body))
+                    {
+                        return Err(StratificationError::AffineUsedInBox {
+                            binder: path.clone(),
+                            occurrence,
+                            depth,
+                        });
+                    }
                 }
-            }
-            Put(term) => {
-                term.is_stratified()?;
-            }
-            Duplicate { body, expression } => {
-                if !body.is_boxed_n_times(1) {
-                    return Err(StratificationError::DupNonUnitBoxMultiplicity);
+                Duplicate { body, .. } => {
+                    if let Some((occurrence, depth)) =
+                        body.find_occurrence(|depth| depth != 1, path.child(Direction::Body))
+                    {
+                        return Err(StratificationError::DupNonUnitBoxMultiplicity {
+                            binder: path.clone(),
+                            occurrence,
+                            depth,
+                        });
+                    }
                 }
-                expression.is_stratified()?;
-                body.is_stratified()?;
+                _ => {}
             }
-            Variable(_) | Reference(_) | Function { .. } | Universe => {}
-            Primitive(_) => todo!(),
 
-            Wrap(term) => term.is_stratified()?,
-            Annotation { expression, .. } => {
-                expression.is_stratified()?;
-            }
-        }
-
-        Ok(())
+            Ok(())
+        })
     }
 
     pub fn stratified_in<'a, 'b, U: Definitions<T, V, A>>(
@@ -330,18 +580,24 @@ impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
     ) -> Result<Stratified<'a, 'b, T, U, V, A>, StratificationError<T>>
     where
         T: Clone + PartialEq,
+        V: Clone,
+        A: Reallocate<T, V, A>,
     {
         self.is_stratified()?;
         if self.is_recursive_in(definitions, allocator, allocator) {
             Err(StratificationError::RecursiveDefinition)?;
         }
+        if let Some(name) = self.undefined_reference_in(definitions) {
+            Err(StratificationError::UndefinedReference(name))?;
+        }
+        self.is_stratified_across_in(definitions, allocator)?;
         Ok(Stratified(self, definitions, allocator))
     }
 }
 
 static SYSTEM: &'static System = &System;
 
-impl<T: Debug, V: Primitives<T>> Term<T, V, System> {
+impl<T: Debug, V: Primitives<T> + Clone> Term<T, V, System> {
     pub fn stratified<'a, 'b, U: Definitions<T, V, System>>(
         self,
         definitions: &'a U,
@@ -352,3 +608,658 @@ impl<T: Debug, V: Primitives<T>> Term<T, V, System> {
         self.stratified_in(definitions, SYSTEM)
     }
 }
+
+// The boxes around a value, and whether they hold a function. Of a parameter, it's what the
+// definition needs: that many boxes around a function, if `function` is set, or around anything.
+// Of an argument, it's what is known: that many boxes at least, around a function if `function` is
+// set and around something unknown otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Depth {
+    boxes: usize,
+    function: bool,
+}
+
+const UNKNOWN: Depth = Depth {
+    boxes: 0,
+    function: false,
+};
+
+impl Depth {
+    fn boxed(self, boxes: usize) -> Self {
+        Depth {
+            boxes: self.boxes + boxes,
+            ..self
+        }
+    }
+
+    // What is inside the outermost box, if one is known to be there.
+    fn unboxed(self) -> Self {
+        match self.boxes {
+            0 => UNKNOWN,
+            boxes => Depth {
+                boxes: boxes - 1,
+                ..self
+            },
+        }
+    }
+}
+
+// Whether an argument of depth `have` fits a parameter that needs `need`, as far as can be told.
+enum Fit {
+    Fits,
+    Misfits,
+    Unknown,
+}
+
+fn fit(have: Depth, need: Depth) -> Fit {
+    if have.boxes > need.boxes {
+        if need.function {
+            Fit::Misfits
+        } else {
+            Fit::Fits
+        }
+    } else if have.boxes < need.boxes || (need.function && !have.function) {
+        if have.function {
+            Fit::Misfits
+        } else {
+            Fit::Unknown
+        }
+    } else {
+        Fit::Fits
+    }
+}
+
+// What a definition needs of the argument for each lambda it starts with, and the depth of what it
+// returns once applied to all of them.
+#[derive(Clone)]
+struct Measure {
+    parameters: Vec<Option<Depth>>,
+    result: Depth,
+}
+
+// The definitions measured so far.
+type Measured<T> = Vec<(T, Measure)>;
+
+// A step of the walk that checks arguments, which binds variables as it goes.
+enum Visit<'a, T, V: Primitives<T>, A: Allocator<T, V>> {
+    Term(&'a Term<T, V, A>),
+    Enter(Direction),
+    Leave,
+    Bind(Depth),
+    Unbind,
+}
+
+impl<T, V: Primitives<T> + Clone, A: Allocator<T, V>> Term<T, V, A> {
+    // Checks stratification of the whole program this term builds a net from: the term and every
+    // definition it reaches outside of erased positions, each once, with saturated jet
+    // applications replaced where building the net replaces them. A definition is inlined whole,
+    // so its own stratification holds at whatever box depth it's used at, but each argument it's
+    // applied to must be at the box depth it needs. What a definition needs of a parameter follows
+    // from the first use of it: duplicating it needs a box around whatever the duplicated variable
+    // needs, applying it needs a function, and passing it on needs what the definition it's passed
+    // to needs. The depth of a closed argument is read off its weak head normal form, and that of
+    // any other is worked out from what it's built of, taking the parameter of a definition or
+    // lambda to be at the depth its body needs, since each argument a definition is applied to is
+    // checked. Arguments to a function held in a variable aren't, as the function isn't known
+    // here. An argument whose depth can't be worked out is reported rather than assumed to fit. A
+    // violation in a definition is reported with the chain of references leading to it.
+    // Definitions must not be recursive.
+    pub fn is_stratified_across_in<U: Definitions<T, V, A>>(
+        &self,
+        definitions: &U,
+        alloc: &A,
+    ) -> Result<(), StratificationError<T>>
+    where
+        T: Clone + PartialEq,
+        A: Reallocate<T, V, A>,
+    {
+        let mut measured = vec![];
+        let mut checked = vec![];
+        // The definitions leading to the one being checked, and for it and each of them, the
+        // references in it left to follow, last first.
+        let mut chain = vec![];
+        let mut references =
+            vec![self.is_stratified_alone_in(definitions, alloc, &chain, &mut measured)?];
+        while let Some(rest) = references.last_mut() {
+            let name = match rest.pop() {
+                Some(name) => name,
+                None => {
+                    references.pop();
+                    chain.pop();
+                    continue;
+                }
+            };
+            if checked.contains(&name) {
+                continue;
+            }
+            checked.push(name.clone());
+            if let Some(definition) = definitions.get(&name) {
+                chain.push(name);
+                let inner = definition.as_ref().is_stratified_alone_in(
+                    definitions,
+                    alloc,
+                    &chain,
+                    &mut measured,
+                )?;
+                references.push(inner);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_stratified_across<U: Definitions<T, V, A>>(
+        &self,
+        definitions: &U,
+    ) -> Result<(), StratificationError<T>>
+    where
+        T: Clone + PartialEq,
+        A: Zero + Reallocate<T, V, A>,
+    {
+        let alloc = A::zero();
+
+        self.is_stratified_across_in(definitions, &alloc)
+    }
+
+    // Checks this term, which `chain` leads to, with its saturated jet applications replaced, and
+    // returns the references in it that survive erasure, last first.
+    fn is_stratified_alone_in<U: Definitions<T, V, A>>(
+        &self,
+        definitions: &U,
+        alloc: &A,
+        chain: &[T],
+        measured: &mut Measured<T>,
+    ) -> Result<Vec<T>, StratificationError<T>>
+    where
+        T: Clone + PartialEq,
+        A: Reallocate<T, V, A>,
+    {
+        let mut built = alloc.copy(self);
+        built.normalize_jets_in(definitions, alloc);
+        built
+            .is_stratified()
+            .and_then(|_| built.arguments_fit_in(definitions, alloc, measured))
+            .map_err(|error| {
+                if chain.is_empty() {
+                    error
+                } else {
+                    StratificationError::InDefinition {
+                        chain: chain.to_vec(),
+                        error: Box::new(error),
+                    }
+                }
+            })?;
+
+        let mut references = built.computational_references();
+        references.reverse();
+        Ok(references)
+    }
+
+    // Checks each argument a definition is applied to in this term against the depth the
+    // definition needs of it.
+    fn arguments_fit_in<U: Definitions<T, V, A>>(
+        &self,
+        definitions: &U,
+        alloc: &A,
+        measured: &mut Measured<T>,
+    ) -> Result<(), StratificationError<T>>
+    where
+        T: Clone + PartialEq,
+        A: Reallocate<T, V, A>,
+    {
+        use Term::*;
+
+        let mut environment = vec![];
+        let mut path = Path::root();
+
+        let mut stack = vec![Visit::Term(self)];
+        let visit = |stack: &mut Vec<_>, term, direction| {
+            stack.extend([Visit::Leave, Visit::Term(term), Visit::Enter(direction)])
+        };
+
+        while let Some(step) = stack.pop() {
+            let term = match step {
+                Visit::Term(term) => term,
+                Visit::Enter(direction) => {
+                    path.push(direction);
+                    continue;
+                }
+                Visit::Leave => {
+                    path.pop();
+                    continue;
+                }
+                Visit::Bind(depth) => {
+                    environment.push(depth);
+                    continue;
+                }
+                Visit::Unbind => {
+                    environment.pop();
+                    continue;
+                }
+            };
+
+            if let Apply {
+                argument,
+                erased: false,
+                ..
+            } = term
+            {
+                let (head, position) = term.head();
+                if let Reference(name) = head {
+                    let need = Self::measure_in(name, definitions, alloc, measured)
+                        .parameters
+                        .get(position)
+                        .copied()
+                        .flatten();
+                    if let Some(need) = need {
+                        let have =
+                            argument.depth_in(&mut environment, definitions, alloc, measured);
+                        let argument = path.child(Direction::Argument);
+                        match fit(have, need) {
+                            Fit::Fits => {}
+                            Fit::Misfits => {
+                                return Err(StratificationError::ArgumentDepth {
+                                    argument,
+                                    definition: name.clone(),
+                                    expected: need.boxes,
+                                    depth: have.boxes,
+                                })
+                            }
+                            Fit::Unknown => {
+                                return Err(StratificationError::UnknownArgumentDepth {
+                                    argument,
+                                    definition: name.clone(),
+                                    expected: need.boxes,
+                                })
+                            }
+                        }
+                    }
+                }
+            }
+
+            match term {
+                Lambda { body, erased } => {
+                    let bound = if *erased {
+                        None
+                    } else {
+                        body.need_of(Index::top(), definitions, alloc, measured)
+                    };
+                    stack.push(Visit::Unbind);
+                    visit(&mut stack, body, Direction::Body);
+                    stack.push(Visit::Bind(bound.unwrap_or(UNKNOWN)));
+                }
+                Apply {
+                    function,
+                    argument,
+                    erased,
+                } => {
+                    if !*erased {
+                        visit(&mut stack, argument, Direction::Argument);
+                    }
+                    visit(&mut stack, function, Direction::Function);
+                }
+                Put(term) => visit(&mut stack, term, Direction::Contents),
+                Duplicate { expression, body } => {
+                    let bound = expression
+                        .depth_in(&mut environment, definitions, alloc, measured)
+                        .unboxed();
+                    stack.push(Visit::Unbind);
+                    visit(&mut stack, body, Direction::Body);
+                    stack.push(Visit::Bind(bound));
+                    visit(&mut stack, expression, Direction::Expression);
+                }
+                Annotation { expression, .. } => {
+                    visit(&mut stack, expression, Direction::Expression)
+                }
+                Variable(_)
+                | Reference(_)
+                | Primitive(_)
+                | Wrap(_)
+                | Function { .. }
+                | Universe => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    // The head of the application spine this term is the top of, and how many arguments, erased
+    // or not, it is applied to beneath the outermost.
+    fn head(&self) -> (&Self, usize) {
+        let mut term = self;
+        let mut arguments: usize = 0;
+        while let Term::Apply { function, .. } = term {
+            term = function;
+            arguments += 1;
+        }
+        (term, arguments.saturating_sub(1))
+    }
+
+    // What the definition `name` needs of its arguments and returns, measured once.
+    fn measure_in<'a, U: Definitions<T, V, A>>(
+        name: &T,
+        definitions: &U,
+        alloc: &A,
+        measured: &'a mut Measured<T>,
+    ) -> &'a Measure
+    where
+        T: Clone + PartialEq,
+        A: Reallocate<T, V, A>,
+    {
+        let unknown = || Measure {
+            parameters: vec![],
+            result: UNKNOWN,
+        };
+
+        let index = match measured.iter().position(|(other, _)| other == name) {
+            Some(index) => index,
+            None => {
+                // A placeholder, so a recursive definition measures as unknown rather than looping.
+                measured.push((name.clone(), unknown()));
+                let measure = match definitions.get(name) {
+                    Some(definition) => definition.as_ref().measure(definitions, alloc, measured),
+                    None => unknown(),
+                };
+                let index = measured
+                    .iter()
+                    .position(|(other, _)| other == name)
+                    .unwrap();
+                measured[index].1 = measure;
+                index
+            }
+        };
+        &measured[index].1
+    }
+
+    // What this term needs of the argument for each lambda it starts with, and the depth of what
+    // it returns once applied to all of them.
+    fn measure<U: Definitions<T, V, A>>(
+        &self,
+        definitions: &U,
+        alloc: &A,
+        measured: &mut Measured<T>,
+    ) -> Measure
+    where
+        T: Clone + PartialEq,
+        A: Reallocate<T, V, A>,
+    {
+        let mut erased = vec![];
+        let mut body = self;
+        while let Term::Lambda {
+            body: inner,
+            erased: lambda_erased,
+        } = body
+        {
+            erased.push(*lambda_erased);
+            body = inner;
+        }
+
+        let count = erased.len();
+        let parameters: Vec<_> = erased
+            .into_iter()
+            .enumerate()
+            .map(|(position, erased)| {
+                if erased {
+                    None
+                } else {
+                    body.need_of(Index(count - 1 - position), definitions, alloc, measured)
+                }
+            })
+            .collect();
+        let mut environment = parameters
+            .iter()
+            .map(|need| need.unwrap_or(UNKNOWN))
+            .collect();
+        let result = body.depth_in(&mut environment, definitions, alloc, measured);
+
+        Measure { parameters, result }
+    }
+
+    // What the first use of `variable` in this term needs of its value: a box around whatever the
+    // variable it's duplicated into needs, a function if it's applied, or what a definition it's
+    // passed to needs.
+    fn need_of<U: Definitions<T, V, A>>(
+        &self,
+        variable: Index,
+        definitions: &U,
+        alloc: &A,
+        measured: &mut Measured<T>,
+    ) -> Option<Depth>
+    where
+        T: Clone + PartialEq,
+        A: Reallocate<T, V, A>,
+    {
+        use Term::*;
+
+        let is_variable =
+            |term: &Self, variable: Index| matches!(term, Variable(index) if *index == variable);
+
+        // A use that duplicates the variable adds a box to what the first use of the variable it's
+        // duplicated into needs, so the search starts over in the duplication's body.
+        let mut boxes = 0;
+        let mut stack = vec![(self, variable)];
+        while let Some((term, variable)) = stack.pop() {
+            match term {
+                Apply {
+                    function,
+                    argument,
+                    erased,
+                } => {
+                    let (head, position) = term.head();
+                    if is_variable(head, variable) {
+                        return Some(Depth {
+                            boxes,
+                            function: true,
+                        });
+                    }
+                    stack.push((function, variable));
+                    if *erased {
+                        continue;
+                    }
+                    if !is_variable(argument, variable) {
+                        stack.push((argument, variable));
+                    } else if let Reference(name) = head {
+                        let need = Self::measure_in(name, definitions, alloc, measured)
+                            .parameters
+                            .get(position)
+                            .copied()
+                            .flatten();
+                        if let Some(need) = need {
+                            return Some(need.boxed(boxes));
+                        }
+                    }
+                }
+                Duplicate { expression, body } => {
+                    if is_variable(expression, variable) {
+                        boxes += 1;
+                        stack = vec![(body, Index::top())];
+                        continue;
+                    }
+                    stack.push((body, variable.child()));
+                    stack.push((expression, variable));
+                }
+                Lambda { body, .. } => stack.push((body, variable.child())),
+                Put(term) => stack.push((term, variable)),
+                Annotation { expression, .. } => stack.push((expression, variable)),
+                Variable(_)
+                | Reference(_)
+                | Primitive(_)
+                | Wrap(_)
+                | Function { .. }
+                | Universe => {}
+            }
+        }
+
+        // Without a need of its own, a duplicated variable can hold anything.
+        if boxes > 0 {
+            Some(UNKNOWN.boxed(boxes))
+        } else {
+            None
+        }
+    }
+
+    // The depth of this term as a value, where the variables bound outside it are at the depths in
+    // `environment`, innermost last.
+    fn depth_in<U: Definitions<T, V, A>>(
+        &self,
+        environment: &mut Vec<Depth>,
+        definitions: &U,
+        alloc: &A,
+        measured: &mut Measured<T>,
+    ) -> Depth
+    where
+        T: Clone + PartialEq,
+        A: Reallocate<T, V, A>,
+    {
+        use Term::*;
+
+        let bound = environment.len();
+        // The duplications whose expressions are being worked out, each with the boxes around it.
+        let mut duplications = vec![];
+        let mut boxes = 0;
+        let mut term = self;
+        let mut entered = true;
+
+        let depth = loop {
+            let depth = if entered && term.is_closed() {
+                term.closed_depth(definitions, alloc)
+            } else {
+                entered = false;
+                match term {
+                    Put(inner) => {
+                        boxes += 1;
+                        term = inner;
+                        continue;
+                    }
+                    Duplicate { expression, body } => {
+                        duplications.push((body, boxes));
+                        boxes = 0;
+                        term = expression;
+                        entered = true;
+                        continue;
+                    }
+                    Annotation { expression, .. } => {
+                        term = expression;
+                        continue;
+                    }
+                    Lambda { .. } => Depth {
+                        boxes: 0,
+                        function: true,
+                    },
+                    Variable(Index(index)) => environment
+                        .len()
+                        .checked_sub(index + 1)
+                        .map_or(UNKNOWN, |index| environment[index]),
+                    Apply { .. } | Reference(_) => {
+                        // A definition applied to all its parameters returns what it measures as,
+                        // and to fewer is a function.
+                        let mut arguments = 0;
+                        let mut head = term;
+                        while let Apply { function, .. } = head {
+                            arguments += 1;
+                            head = function;
+                        }
+                        match head {
+                            Reference(name) => {
+                                let measure = Self::measure_in(name, definitions, alloc, measured);
+                                match arguments.cmp(&measure.parameters.len()) {
+                                    Ordering::Less => Depth {
+                                        boxes: 0,
+                                        function: true,
+                                    },
+                                    Ordering::Equal => measure.result,
+                                    Ordering::Greater => UNKNOWN,
+                                }
+                            }
+                            _ => UNKNOWN,
+                        }
+                    }
+                    Primitive(_) | Wrap(_) | Function { .. } | Universe => UNKNOWN,
+                }
+            }
+            .boxed(boxes);
+
+            match duplications.pop() {
+                Some((body, outer)) => {
+                    environment.push(depth.unboxed());
+                    boxes = outer;
+                    term = body;
+                }
+                None => break depth,
+            }
+        };
+
+        environment.truncate(bound);
+        depth
+    }
+
+    // The depth of this closed term, read off its weak head normal form and those of what its
+    // boxes hold.
+    fn closed_depth<U: Definitions<T, V, A>>(&self, definitions: &U, alloc: &A) -> Depth
+    where
+        T: Clone,
+        A: Reallocate<T, V, A>,
+    {
+        let mut term = alloc.copy(self);
+        let mut boxes = 0;
+
+        loop {
+            if term
+                .weak_normalize_in_erased::<_, A>(definitions, alloc, false)
+                .is_err()
+            {
+                return UNKNOWN.boxed(boxes);
+            }
+            term = match &mut term {
+                Term::Put(inner) => inner.take(),
+                Term::Lambda { .. } => {
+                    return Depth {
+                        boxes,
+                        function: true,
+                    }
+                }
+                _ => return UNKNOWN.boxed(boxes),
+            };
+            boxes += 1;
+        }
+    }
+
+    // Replaces saturated jet applications with what building the net puts in their place (see
+    // `jet_result`). A jet that fails is left for building the net to report.
+    fn normalize_jets_in<U: Definitions<T, V, A>>(&mut self, definitions: &U, alloc: &A)
+    where
+        T: Clone,
+        A: Reallocate<T, V, A>,
+    {
+        use Term::*;
+
+        let mut stack = vec![self];
+        while let Some(term) = stack.pop() {
+            if let Ok(Some(result)) = term.jet_result(definitions, alloc) {
+                *term = result;
+            }
+
+            match term {
+                Lambda { body, .. } | Put(body) => stack.push(body),
+                Apply {
+                    function,
+                    argument,
+                    erased,
+                } => {
+                    stack.push(function);
+                    if !*erased {
+                        stack.push(argument);
+                    }
+                }
+                Duplicate { expression, body } => stack.extend([&mut **expression, &mut **body]),
+                Annotation { expression, .. } => stack.push(expression),
+                Variable(_)
+                | Reference(_)
+                | Primitive(_)
+                | Wrap(_)
+                | Function { .. }
+                | Universe => {}
+            }
+        }
+    }
+}
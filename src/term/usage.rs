@@ -0,0 +1,172 @@
+use super::{Allocator, Direction, Index, Path, Primitives, Term};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinderKind {
+    Lambda,
+    ErasedLambda,
+    Duplication,
+}
+
+// An occurrence of a bound variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Use {
+    pub path: Path,
+    // The number of boxes between the binder and the occurrence.
+    pub depth: usize,
+    // Whether the occurrence is in an erased argument or a type, which don't exist at runtime.
+    pub erased: bool,
+}
+
+// How the variable of the binder at `binder` is used, with uses in the order they appear.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+    pub binder: Path,
+    pub kind: BinderKind,
+    pub uses: Vec<Use>,
+}
+
+impl Usage {
+    pub fn computational_uses(&self) -> usize {
+        self.uses.iter().filter(|usage| !usage.erased).count()
+    }
+
+    pub fn erased_uses(&self) -> usize {
+        self.uses.iter().filter(|usage| usage.erased).count()
+    }
+}
+
+impl<T, V: Primitives<T>, A: Allocator<T, V>> Term<T, V, A> {
+    // The usage of every lambda, erased lambda and duplication in this term, outermost first.
+    pub fn usage(&self) -> Vec<Usage> {
+        let mut report = vec![];
+        let mut path = Path::root();
+        // Each subterm left to visit, with the length of its parent's path and the direction from
+        // there, which the term itself doesn't have.
+        let mut stack = vec![(self, 0, None)];
+        while let Some((term, parent, direction)) = stack.pop() {
+            path.truncate(parent);
+            if let Some(direction) = direction {
+                path.push(direction);
+            }
+
+            let kind = match term {
+                Term::Lambda { erased: false, .. } => Some(BinderKind::Lambda),
+                Term::Lambda { erased: true, .. } => Some(BinderKind::ErasedLambda),
+                Term::Duplicate { .. } => Some(BinderKind::Duplication),
+                _ => None,
+            };
+            if let (Some(kind), Some(body)) = (kind, term.child(Direction::Body)) {
+                report.push(Usage {
+                    binder: path.clone(),
+                    kind,
+                    uses: body.occurrences(path.child(Direction::Body)),
+                });
+            }
+
+            // Children are pushed last first, so they're visited in order.
+            for direction in [
+                Direction::Type,
+                Direction::ReturnType,
+                Direction::ArgumentType,
+                Direction::Contents,
+                Direction::Body,
+                Direction::Expression,
+                Direction::Argument,
+                Direction::Function,
+            ] {
+                if let Some(child) = term.child(direction) {
+                    stack.push((child, path.len(), Some(direction)));
+                }
+            }
+        }
+
+        report
+    }
+
+    // Every occurrence of the variable bound just outside this term, with paths continuing
+    // `path`. The bodies of erased lambdas survive erasure, so only erased arguments and types
+    // make an occurrence erased.
+    pub(super) fn occurrences(&self, mut path: Path) -> Vec<Use> {
+        use Term::*;
+
+        let mut uses = vec![];
+        // Each subterm left to visit, with the variable as it's indexed there, the boxes around it,
+        // whether it's erased, the length of its parent's path and the direction from there.
+        let mut stack = vec![(self, Index::top(), 0, false, path.len(), None)];
+        while let Some((term, variable, depth, erased, parent, direction)) = stack.pop() {
+            path.truncate(parent);
+            if let Some(direction) = direction {
+                path.push(direction);
+            }
+
+            let length = path.len();
+            let mut visit = |term, direction, variable, depth, erased| {
+                stack.push((term, variable, depth, erased, length, Some(direction)))
+            };
+
+            // Children are pushed last first, so uses are found in order.
+            match term {
+                Variable(index) => {
+                    if *index == variable {
+                        uses.push(Use {
+                            path: path.clone(),
+                            depth,
+                            erased,
+                        });
+                    }
+                }
+                Lambda { body, .. } => {
+                    visit(body, Direction::Body, variable.child(), depth, erased)
+                }
+                Apply {
+                    function,
+                    argument,
+                    erased: erased_argument,
+                } => {
+                    visit(
+                        argument,
+                        Direction::Argument,
+                        variable,
+                        depth,
+                        erased || *erased_argument,
+                    );
+                    visit(function, Direction::Function, variable, depth, erased);
+                }
+                Put(term) => visit(term, Direction::Contents, variable, depth + 1, erased),
+                Duplicate { expression, body } => {
+                    visit(body, Direction::Body, variable.child(), depth, erased);
+                    visit(expression, Direction::Expression, variable, depth, erased);
+                }
+                Wrap(term) => visit(term, Direction::Contents, variable, depth, erased),
+                Annotation { expression, ty, .. } => {
+                    visit(ty, Direction::Type, variable, depth, true);
+                    visit(expression, Direction::Expression, variable, depth, erased);
+                }
+                Function {
+                    argument_type,
+                    return_type,
+                    ..
+                } => {
+                    // The return type binds the function itself and its argument.
+                    visit(
+                        return_type,
+                        Direction::ReturnType,
+                        variable.child().child(),
+                        depth,
+                        true,
+                    );
+                    visit(
+                        argument_type,
+                        Direction::ArgumentType,
+                        variable,
+                        depth,
+                        true,
+                    );
+                }
+                Reference(_) | Primitive(_) | Universe => {}
+            }
+        }
+
+        uses
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use welkin_core::term::{
+    typed::Definitions, CacheStatistics, ConcurrentEqualityCache, EqualityCache, FileCache,
+    InstrumentedCache, LruCache, MapCache, None, OpaqueDefinitions, SharedCache, Term, TermHash,
+};
+
+use crate::parse;
+
+type Typed = HashMap<String, (Term<String>, Term<String>)>;
+
+fn temporary_path() -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut path = std::env::temp_dir();
+    path.push(format!(
+        "welkin-core-cache-{}-{}.json",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn missing_file() {
+    let path = temporary_path();
+
+    let cache = FileCache::load(&path, TermHash(0)).unwrap();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn round_trip() {
+    let path = temporary_path();
+
+    let mut cache = FileCache::load(&path, TermHash(0)).unwrap();
+    cache.register(TermHash(1), TermHash(2), true);
+    cache.register(TermHash(3), TermHash(u128::MAX), false);
+    cache.save().unwrap();
+
+    let cache = FileCache::load(&path, TermHash(0)).unwrap();
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.check(TermHash(1), TermHash(2)), Some(true));
+    assert_eq!(cache.check(TermHash(3), TermHash(u128::MAX)), Some(false));
+    assert_eq!(cache.check(TermHash(2), TermHash(1)), None);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn stale_version() {
+    let path = temporary_path();
+
+    let mut cache = FileCache::load(&path, TermHash(0)).unwrap();
+    cache.register(TermHash(1), TermHash(2), true);
+    cache.save().unwrap();
+
+    // Only the version differs from a file this version would load.
+    let saved = fs::read_to_string(&path).unwrap();
+    let version = serde_json::from_str::<serde_json::Value>(&saved).unwrap()["version"]
+        .as_u64()
+        .unwrap();
+    let stale = saved.replace(
+        &format!(r#""version":{}"#, version),
+        &format!(r#""version":{}"#, version + 1),
+    );
+    assert_ne!(stale, saved);
+    fs::write(&path, stale).unwrap();
+
+    let cache = FileCache::load(&path, TermHash(0)).unwrap();
+    assert!(cache.is_empty());
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn concurrent_saves() {
+    let path = temporary_path();
+
+    let threads: Vec<_> = (0..8)
+        .map(|index| {
+            let path = path.clone();
+            thread::spawn(move || {
+                let mut cache = FileCache::load(&path, TermHash(0)).unwrap();
+                cache.register(TermHash(index), TermHash(index), true);
+                cache.save()
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap().unwrap();
+    }
+
+    // Whichever save came last is read back whole, and no temporary file is left behind.
+    let cache = FileCache::load(&path, TermHash(0)).unwrap();
+    assert!(!cache.is_empty());
+    let name = path.file_name().unwrap().to_str().unwrap();
+    let leftover = fs::read_dir(path.parent().unwrap()).unwrap().any(|entry| {
+        let entry = entry.unwrap().file_name();
+        let entry = entry.to_str().unwrap();
+        entry.starts_with(name) && entry.ends_with(".tmp")
+    });
+    assert!(!leftover);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn corrupt_file() {
+    let path = temporary_path();
+
+    fs::write(&path, "not a cache").unwrap();
+
+    let cache = FileCache::load(&path, TermHash(0)).unwrap();
+    assert!(cache.is_empty());
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn records_equivalences() {
+    let path = temporary_path();
+
+    let a: Term<String, None> = parse(r#"\f \x (f x)"#);
+    let b: Term<String, None> = parse(r#"\f f"#);
+
+    let definitions = HashMap::new();
+
+    let mut cache = FileCache::load(&path, TermHash(0)).unwrap();
+    assert!(a.equivalent(&b, &definitions, &mut cache).unwrap());
+    cache.save().unwrap();
+
+    let mut cache = FileCache::load(&path, TermHash(0)).unwrap();
+    assert_eq!(cache.check(a.stable_hash(), b.stable_hash()), Some(true));
+    assert!(a.equivalent(&b, &definitions, &mut cache).unwrap());
+
+    fs::remove_file(&path).unwrap();
+}
+
+fn definitions(source: &str) -> OpaqueDefinitions<String, Typed> {
+    let definitions: Definitions = source.trim().parse().unwrap();
+
+    let opaque: HashSet<_> = definitions.opaque.into_iter().collect();
+    let definitions: HashMap<_, _> = definitions.terms.into_iter().collect();
+    OpaqueDefinitions::new(definitions, opaque)
+}
+
+#[test]
+fn changed_definitions() {
+    let path = temporary_path();
+
+    // Equalities are cached for weak head normal forms, which only unfold references at the head.
+    let unit: Term<String, None> = parse(r#"\x Unit"#);
+    let universe: Term<String, None> = parse(r#"\x *"#);
+
+    // Each run loads the cache for the definitions it checks against, as `--cache` does.
+    let run = |source: &str| {
+        let definitions = definitions(source);
+        let mut cache = FileCache::load(&path, definitions.fingerprint()).unwrap();
+        let reused = cache.len();
+        let equivalent = unit
+            .equivalent(&universe, &definitions, &mut cache)
+            .unwrap();
+        cache.save().unwrap();
+        (reused, equivalent)
+    };
+
+    assert_eq!(run("Unit : * = *"), (0, true));
+    assert!(run("Unit : * = *").0 > 0);
+
+    // The equality saved for the old body must not be read back for the new one.
+    assert_eq!(run(r#"Unit : * = \x x"#), (0, false));
+
+    // Opacity changes what type checking may unfold, so it starts afresh as well.
+    run("Unit : * = *");
+    assert_eq!(run("@opaque Unit : * = *").0, 0);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn lru_evicts_least_recent() {
+    let mut cache = LruCache::new(2);
+
+    cache.register(TermHash(1), TermHash(1), true);
+    cache.register(TermHash(2), TermHash(2), true);
+    assert_eq!(cache.check(TermHash(1), TermHash(1)), Some(true));
+
+    cache.register(TermHash(3), TermHash(3), false);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.check(TermHash(1), TermHash(1)), Some(true));
+    assert_eq!(cache.check(TermHash(2), TermHash(2)), None);
+    assert_eq!(cache.check(TermHash(3), TermHash(3)), Some(false));
+}
+
+#[test]
+fn lru_overwrites() {
+    let mut cache = LruCache::new(1);
+
+    cache.register(TermHash(1), TermHash(2), false);
+    cache.register(TermHash(1), TermHash(2), true);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.check(TermHash(1), TermHash(2)), Some(true));
+}
+
+#[test]
+fn lru_zero_capacity() {
+    let mut cache = LruCache::new(0);
+
+    cache.register(TermHash(1), TermHash(2), true);
+    assert!(cache.is_empty());
+    assert_eq!(cache.check(TermHash(1), TermHash(2)), None);
+}
+
+#[test]
+fn instrumented() {
+    let mut cache = InstrumentedCache::new(MapCache::new());
+
+    assert_eq!(cache.check(TermHash(1), TermHash(2)), None);
+    cache.register(TermHash(1), TermHash(2), true);
+    assert_eq!(cache.check(TermHash(1), TermHash(2)), Some(true));
+    assert_eq!(cache.check(TermHash(1), TermHash(2)), Some(true));
+
+    let statistics = cache.statistics();
+    assert_eq!(
+        statistics,
+        CacheStatistics {
+            hits: 2,
+            misses: 1,
+            registrations: 1,
+        }
+    );
+    assert_eq!(
+        statistics.to_string(),
+        "2 hits, 1 misses (66.7% hit rate), 1 registrations"
+    );
+
+    cache.reset();
+    assert_eq!(cache.statistics(), CacheStatistics::default());
+}
+
+#[test]
+fn instrumented_equivalence() {
+    let a: Term<String, None> = parse(r#"\f \x (f x)"#);
+    let b: Term<String, None> = parse(r#"\f f"#);
+    let definitions = HashMap::new();
+
+    let mut cache = InstrumentedCache::new(LruCache::new(16));
+    assert!(a.equivalent(&b, &definitions, &mut cache).unwrap());
+    assert!(a.equivalent(&b, &definitions, &mut cache).unwrap());
+
+    let statistics = cache.statistics();
+    assert!(statistics.hits >= 1);
+    assert!(statistics.registrations >= 1);
+}
+
+#[test]
+fn shared() {
+    let cache = Arc::new(SharedCache::new());
+
+    let threads: Vec<_> = (0..8)
+        .map(|i| {
+            let mut cache = cache.clone();
+            thread::spawn(move || {
+                let a: Term<String, None> = parse(&format!(r#"\f \x (f x Type{})"#, i));
+                let b: Term<String, None> = parse(&format!(r#"\f (f Type{})"#, i));
+                let definitions = HashMap::new();
+
+                assert!(!a.equivalent(&b, &definitions, &mut cache).unwrap());
+                assert!(!a.equivalent(&b, &definitions, &mut &*cache).unwrap());
+                (a.stable_hash(), b.stable_hash())
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        let (a, b) = thread.join().unwrap();
+        assert_eq!(cache.check_shared(a, b), Some(false));
+    }
+    assert_eq!(cache.len(), 8);
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use welkin_core::term::{AnalysisError, Direction, Index, None, NullCache, Term, TermHash};
+
+use crate::{check_all, parse};
+
+#[test]
+fn eta_lambda() {
+    check_all(
+        r#"
+eta : _,G:+,:+,:* * * _,F:+,:* * +,:(G F) (G \x (F x)) = /G /F \a a
+"#,
+    );
+}
+
+#[test]
+fn eta_lambda_reversed() {
+    check_all(
+        r#"
+eta : _,G:+,:+,:* * * _,F:+,:* * +,:(G \x (F x)) (G F) = /G /F \a a
+"#,
+    );
+}
+
+#[test]
+fn eta_erased_lambda() {
+    check_all(
+        r#"
+eta : _,G:+,:_,:* * * _,F:_,:* * +,:(G F) (G /x [F x]) = /G /F \a a
+"#,
+    );
+}
+
+#[test]
+fn eta_nested() {
+    check_all(
+        r#"
+eta : _,G:+,:+,:* +,:* * * _,F:+,:* +,:* * +,:(G F) (G \x \y (F x y)) = /G /F \a a
+"#,
+    );
+}
+
+#[test]
+#[should_panic]
+fn eta_erasure_mismatch() {
+    check_all(
+        r#"
+eta : _,G:+,:+,:* * * _,F:+,:* * +,:(G F) (G /x (F x)) = /G /F \a a
+"#,
+    );
+}
+
+#[test]
+fn stable_hash() {
+    let term: Term<String, None> = parse(r#"\x (x /y Unit)"#);
+
+    assert_eq!(term.stable_hash(), term.clone().stable_hash());
+    assert_eq!(
+        term.stable_hash(),
+        TermHash(259646438351659454690975116095301848390)
+    );
+    assert_ne!(
+        term.stable_hash(),
+        parse::<None>(r#"\x (x \y Unit)"#).stable_hash()
+    );
+}
+
+#[test]
+fn mismatch_witness() {
+    let a: Term<String, None> = parse(r#"\x (x *)"#);
+    let b: Term<String, None> = parse(r#"\x (x x)"#);
+    let definitions = HashMap::new();
+
+    let mismatch = a.mismatch(&b, &definitions).unwrap().unwrap();
+    assert_eq!(
+        mismatch.path,
+        vec![Direction::Body, Direction::Argument].into()
+    );
+    assert_eq!(mismatch.path.to_string(), "body.argument");
+    assert!(!mismatch.normalized);
+    assert!(mismatch.left.equals(&Term::Universe));
+    assert!(mismatch.right.equals(&Term::Variable(Index(0))));
+    assert!(a.subterm(&mismatch.path).unwrap().equals(&Term::Universe));
+
+    assert!(a.mismatch(&a, &definitions).unwrap().is_none());
+}
+
+#[test]
+fn unfolded_mismatch_witness() {
+    let a: Term<String, None> = Term::Reference("apply".to_owned());
+    let b: Term<String, None> = parse(r#"\x (x x)"#);
+    let mut definitions = HashMap::new();
+    definitions.insert("apply".to_owned(), (Term::Universe, parse(r#"\x (x *)"#)));
+
+    let mismatch = a.mismatch(&b, &definitions).unwrap().unwrap();
+    assert_eq!(mismatch.path.to_string(), "body.argument");
+    assert!(mismatch.normalized);
+    assert!(a.subterm(&mismatch.path).is_none());
+}
+
+#[test]
+fn type_error_witness() {
+    let ty: Term<String, None> = parse(r#"_,F:+,:* * _,G:+,:* * +,:(F *) (G *)"#);
+    let term: Term<String, None> = parse(r#"/F /G \x x"#);
+    let definitions = HashMap::new();
+
+    ty.check(&Term::Universe, &definitions, &mut NullCache)
+        .unwrap();
+
+    match term.check(&ty, &definitions, &mut NullCache) {
+        Err(AnalysisError::TypeError {
+            mismatch: Some(mismatch),
+            ..
+        }) => {
+            assert_eq!(mismatch.path, vec![Direction::Function].into());
+            // The inferred type still carries the annotation on `F`, which normalization drops.
+            assert!(mismatch.normalized);
+        }
+        other => panic!("expected type error, got {:?}", other),
+    }
+}
@@ -0,0 +1,339 @@
+use std::{cell::Cell, collections::HashMap, fmt::Display};
+
+use welkin_core::term::{
+    alloc::{Allocator, System},
+    typed::Definitions,
+    Index, Normalizer, NullCache, PrimitiveError, Primitives, Session, Term,
+};
+
+use crate::parse;
+
+type Typed = HashMap<String, (Term<String>, Term<String>)>;
+
+fn example() -> Typed {
+    let definitions: Definitions = include_str!("../../example.wc").parse().unwrap();
+    definitions.terms.into_iter().collect()
+}
+
+#[track_caller]
+fn agrees(term: &Term<String>, definitions: &Typed) {
+    let mut substituted = term.clone();
+    let mut evaluated = term.clone();
+
+    substituted.normalize(definitions).unwrap();
+    evaluated.evaluate(definitions).unwrap();
+
+    assert!(
+        substituted.equals(&evaluated),
+        "{:?} normalized to {:?} by substitution but {:?} by evaluation",
+        term,
+        substituted,
+        evaluated
+    );
+}
+
+fn equivalent(
+    a: &Term<String>,
+    b: &Term<String>,
+    normalizer: Normalizer,
+    definitions: &Typed,
+) -> bool {
+    a.equivalent_with_in(b, normalizer, definitions, &System, &mut NullCache)
+        .unwrap()
+}
+
+#[test]
+fn example_definitions() {
+    let definitions = example();
+
+    for (ty, term) in definitions.values() {
+        agrees(ty, &definitions);
+        agrees(term, &definitions);
+    }
+}
+
+#[test]
+fn example_types() {
+    let definitions = example();
+
+    for (a, _) in definitions.values() {
+        assert!(equivalent(a, a, Normalizer::Evaluation, &definitions));
+        for (b, _) in definitions.values() {
+            assert_eq!(
+                equivalent(a, b, Normalizer::Substitution, &definitions),
+                equivalent(a, b, Normalizer::Evaluation, &definitions),
+                "{:?} and {:?}",
+                a,
+                b
+            );
+        }
+    }
+}
+
+#[test]
+fn example_applications() {
+    let definitions = example();
+
+    for term in [
+        "(not true)",
+        "(not (not false))",
+        "(succ (succ zero))",
+        "[[cons Bool] true [nil Bool]]",
+        "\\x (not x)",
+    ] {
+        agrees(&parse(term), &definitions);
+    }
+}
+
+#[test]
+fn free_variables() {
+    let definitions = HashMap::new();
+
+    for term in [
+        "^0",
+        "(^1 ^0)",
+        "\\x (x ^0)",
+        "/x (x ^1)",
+        ": x = ^0 \\y (x y)",
+    ] {
+        agrees(&parse(term), &definitions);
+    }
+}
+
+#[test]
+fn frozen_types() {
+    let definitions = HashMap::new();
+
+    for term in [
+        "(\\x !(x x) *)",
+        "(\\x +,:x x \\y y)",
+        "\\a (\\x !(x a) \\y y)",
+    ] {
+        agrees(&parse(term), &definitions);
+    }
+}
+
+#[test]
+fn conversion() {
+    let definitions = HashMap::new();
+
+    for (a, b, equal) in [
+        ("\\x x", "(\\f f \\y y)", true),
+        ("\\x (^1 x)", "^0", true),
+        ("/x [^1 x]", "^0", true),
+        ("\\x (^1 x)", "/x [^1 x]", false),
+        ("+,:* *", "+,:(\\x x *) *", true),
+        ("+,:* *", "_,:* *", false),
+        ("!(\\x x *)", "!*", true),
+        (". \\x x", ": y = . \\x x y", true),
+        ("\\x \\y x", "\\x \\y y", false),
+    ] {
+        let (a, b) = (parse(a), parse(b));
+        assert_eq!(
+            equivalent(&a, &b, Normalizer::Evaluation, &definitions),
+            equal,
+            "{:?} and {:?}",
+            a,
+            b
+        );
+        assert_eq!(
+            equivalent(&a, &b, Normalizer::Substitution, &definitions),
+            equal,
+            "{:?} and {:?}",
+            a,
+            b
+        );
+    }
+}
+
+#[test]
+fn session() {
+    let definitions = example();
+    let session = Session::new(&definitions);
+
+    for (_, term) in definitions.values() {
+        let mut substituted = term.clone();
+        let mut evaluated = term.clone();
+
+        substituted.normalize(&definitions).unwrap();
+        evaluated.evaluate_in_session(&System, &session).unwrap();
+
+        assert!(substituted.equals(&evaluated));
+    }
+
+    let normalized = session.len();
+    assert!(session.contains(&"true".to_owned()));
+    assert!(normalized <= definitions.len());
+
+    for (_, term) in definitions.values() {
+        term.clone().evaluate_in_session(&System, &session).unwrap();
+    }
+
+    assert_eq!(session.len(), normalized);
+}
+
+thread_local! {
+    static APPLICATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// Counts how many times it is applied, and otherwise behaves as the identity.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Count;
+
+impl Display for Count {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "count")
+    }
+}
+
+impl Primitives<String> for Count {
+    fn ty<A: Allocator<String, Self>>(&self, alloc: &A) -> Term<String, Self, A> {
+        Term::Function {
+            erased: false,
+            argument_type: alloc.alloc(Term::Universe),
+            return_type: alloc.alloc(Term::Universe),
+        }
+    }
+
+    fn apply<A: Allocator<String, Self>>(
+        &self,
+        arguments: Vec<Term<String, Self, A>>,
+        _: &A,
+    ) -> Option<Result<Term<String, Self, A>, PrimitiveError>> {
+        APPLICATIONS.with(|applications| applications.set(applications.get() + 1));
+        Some(Ok(arguments.into_iter().next().unwrap()))
+    }
+}
+
+#[test]
+fn shared_arguments() {
+    let definitions = HashMap::new();
+    let term = Term::Apply {
+        function: Box::new(parse::<Count>("\\x \\f ((f x) x)")),
+        argument: Box::new(Term::Apply {
+            function: Box::new(Term::Primitive(Count)),
+            argument: Box::new(Term::Universe),
+            erased: false,
+        }),
+        erased: false,
+    };
+    let applications = |normalizer| {
+        APPLICATIONS.with(|applications| applications.set(0));
+        let mut term = term.clone();
+        term.normalize_with_in(normalizer, &definitions, &System)
+            .unwrap();
+        assert!(term.equals(&parse("\\f ((f *) *)")));
+        APPLICATIONS.with(|applications| applications.get())
+    };
+
+    assert_eq!(applications(Normalizer::Substitution), 2);
+    assert_eq!(applications(Normalizer::Evaluation), 1);
+}
+
+// A small linear congruential generator, so the random terms are the same on every run.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % bound as u64) as usize
+    }
+
+    fn term(&mut self, binders: usize, size: usize) -> Term<String> {
+        let boxed = |term| Box::new(term);
+
+        if size <= 1 {
+            return match self.below(binders + 2) {
+                0 => Term::Universe,
+                1 => Term::Reference(["true", "false", "not", "zero"][self.below(4)].into()),
+                _ => Term::Variable(Index(self.below(binders))),
+            };
+        }
+
+        let size = size - 1;
+        let split = self.below(size) + 1;
+
+        match self.below(9) {
+            0 | 1 => Term::Lambda {
+                body: boxed(self.term(binders + 1, size)),
+                erased: self.below(3) == 0,
+            },
+            2..=4 => Term::Apply {
+                function: boxed(self.term(binders, split)),
+                argument: boxed(self.term(binders, size - split)),
+                erased: self.below(3) == 0,
+            },
+            5 => Term::Put(boxed(self.term(binders, size))),
+            6 => Term::Duplicate {
+                expression: boxed(self.term(binders, split)),
+                body: boxed(self.term(binders + 1, size - split)),
+            },
+            7 => Term::Wrap(boxed(self.term(binders, size))),
+            _ => Term::Annotation {
+                checked: false,
+                expression: boxed(self.term(binders, split)),
+                ty: boxed(self.term(binders, size - split)),
+            },
+        }
+    }
+}
+
+#[test]
+fn random_terms() {
+    let definitions = example();
+    let mut random = Random(0x5eed);
+    let mut checked = 0;
+
+    while checked < 500 {
+        let size = random.below(24) + 1;
+        let term = random.term(0, size);
+
+        // Stratified terms are guaranteed to normalize, so neither normalizer can diverge.
+        if term.is_stratified().is_err() || term.clone().normalize(&definitions).is_err() {
+            continue;
+        }
+
+        agrees(&term, &definitions);
+        checked += 1;
+    }
+}
+
+#[test]
+fn random_equivalences() {
+    let definitions = example();
+    let mut random = Random(0xe0);
+    let mut checked = 0;
+
+    while checked < 500 {
+        let (a_size, b_size) = (random.below(12) + 1, random.below(12) + 1);
+        let a = random.term(0, a_size);
+        let b = random.term(0, b_size);
+
+        if a.is_stratified().is_err() || b.is_stratified().is_err() {
+            continue;
+        }
+
+        let substitution = a.equivalent_with_in(
+            &b,
+            Normalizer::Substitution,
+            &definitions,
+            &System,
+            &mut NullCache,
+        );
+        let evaluation = a.equivalent_with_in(
+            &b,
+            Normalizer::Evaluation,
+            &definitions,
+            &System,
+            &mut NullCache,
+        );
+
+        if let (Ok(substitution), Ok(evaluation)) = (substitution, evaluation) {
+            assert_eq!(substitution, evaluation, "{:?} and {:?}", a, b);
+            checked += 1;
+        }
+    }
+}
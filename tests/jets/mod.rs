@@ -0,0 +1,369 @@
+use std::{cell::Cell, collections::HashMap};
+
+use welkin_core::{
+    net::{Index as NetIndex, Net, VisitNetExt},
+    term::{
+        alloc::Allocator, content_hash, typed::Definitions, Index, Jet, JetDefinitions, None,
+        NormalizationError, Rule, Term, TermHash,
+    },
+};
+
+use crate::parse;
+
+type Typed = HashMap<String, (Term<String>, Term<String>)>;
+
+// Church numerals, since arithmetic on `Nat` needs recursion, which normalization unfolds forever.
+const ARITHMETIC: &str = r#"
+Church : * = _,A:* +,:+,:A A +,:A A
+add : +,:Church +,:Church Church = \a \b /A \s \z ([a A] s ([b A] s z))
+mul : +,:Church +,:Church Church = \a \b /A \s ([a A] ([b A] s))
+"#;
+
+fn definitions() -> Typed {
+    let source = format!("{}\n{}", include_str!("../../example.wc"), ARITHMETIC);
+    let definitions: Definitions = source.parse().unwrap();
+    definitions.terms.into_iter().collect()
+}
+
+fn hash(definitions: &Typed, name: &str) -> TermHash {
+    content_hash(definitions, &name.to_owned()).unwrap()
+}
+
+// Reads the erased normal form of a Church numeral, `\s \z (s (s .. z))`.
+fn decode<A: Allocator<String, None>>(term: &Term<String, None, A>) -> Option<u64> {
+    let mut term = match term {
+        Term::Lambda { body, .. } => match &**body {
+            Term::Lambda { body, .. } => &**body,
+            _ => return Option::None,
+        },
+        _ => return Option::None,
+    };
+    let mut value = 0;
+
+    loop {
+        match term {
+            Term::Variable(Index(0)) => return Some(value),
+            Term::Apply {
+                function, argument, ..
+            } if matches!(**function, Term::Variable(Index(1))) => {
+                value += 1;
+                term = argument;
+            }
+            _ => return Option::None,
+        }
+    }
+}
+
+fn encode<A: Allocator<String, None>>(value: u64, alloc: &A) -> Term<String, None, A> {
+    let mut term = Term::Variable(Index(0));
+    for _ in 0..value {
+        term = Term::Apply {
+            function: alloc.alloc(Term::Variable(Index(1))),
+            argument: alloc.alloc(term),
+            erased: false,
+        };
+    }
+    let lambda = |body| Term::Lambda {
+        body: alloc.alloc(body),
+        erased: false,
+    };
+    lambda(lambda(term))
+}
+
+thread_local! {
+    static APPLICATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+#[derive(Clone, Copy)]
+enum Arithmetic {
+    Add,
+    Multiply,
+    Not,
+}
+
+impl Jet<String, None> for Arithmetic {
+    fn arity(&self) -> usize {
+        match self {
+            Arithmetic::Add | Arithmetic::Multiply => 2,
+            Arithmetic::Not => 1,
+        }
+    }
+
+    fn apply<A: Allocator<String, None>>(
+        &self,
+        arguments: &[Term<String, None, A>],
+        alloc: &A,
+    ) -> Option<Term<String, None, A>> {
+        APPLICATIONS.with(|applications| applications.set(applications.get() + 1));
+
+        match self {
+            Arithmetic::Add => Some(encode(
+                decode(&arguments[0])? + decode(&arguments[1])?,
+                alloc,
+            )),
+            Arithmetic::Multiply => Some(encode(
+                decode(&arguments[0])? * decode(&arguments[1])?,
+                alloc,
+            )),
+            // Swaps the arguments of a `Bool`, which is what `not` does to `true` and `false`.
+            Arithmetic::Not => match &arguments[0] {
+                Term::Lambda { body, .. } => match &**body {
+                    Term::Lambda { body, .. } => match &**body {
+                        Term::Variable(Index(index)) => {
+                            let lambda = |body| Term::Lambda {
+                                body: alloc.alloc(body),
+                                erased: false,
+                            };
+                            Some(lambda(lambda(Term::Variable(Index(1 - *index)))))
+                        }
+                        _ => Option::None,
+                    },
+                    _ => Option::None,
+                },
+                _ => Option::None,
+            },
+        }
+    }
+}
+
+fn numeral(value: u64) -> String {
+    let body = (0..value).fold("z".to_owned(), |term, _| format!("(s {})", term));
+    format!("/A \\s \\z {}", body)
+}
+
+fn with_jets(
+    definitions: Typed,
+    jets: &[(&str, Arithmetic)],
+) -> JetDefinitions<String, Typed, Arithmetic> {
+    let jets = jets
+        .iter()
+        .map(|(name, jet)| (hash(&definitions, name), *jet))
+        .collect();
+    JetDefinitions::new(definitions, jets)
+}
+
+#[test]
+fn arithmetic() {
+    let term = parse::<None>(&format!(
+        "(mul {} (add {} {}))",
+        numeral(3),
+        numeral(4),
+        numeral(2)
+    ));
+
+    let mut expected = term.clone();
+    let unfolded = expected.normalize_with_statistics(&definitions()).unwrap();
+    assert_eq!(decode(&expected), Some(18));
+
+    let jets = with_jets(
+        definitions(),
+        &[("add", Arithmetic::Add), ("mul", Arithmetic::Multiply)],
+    );
+    let mut jetted = term.clone();
+    let statistics = jetted.normalize_with_statistics(&jets).unwrap();
+    assert!(jetted.equals(&expected));
+    assert_eq!(statistics.jet_applications, 2);
+    assert!(statistics.reductions() < unfolded.reductions());
+
+    let jets = jets.cross_checked();
+    let mut jetted = term;
+    jetted.normalize(&jets).unwrap();
+    assert!(jetted.equals(&expected));
+}
+
+#[test]
+fn declined() {
+    let jets = with_jets(definitions(), &[("add", Arithmetic::Add)]);
+
+    // The jet can't decode a variable, so `add` is unfolded around it.
+    let term = parse::<None>(&format!("\\n (add {} n)", numeral(2)));
+    let mut expected = term.clone();
+    expected.normalize(&definitions()).unwrap();
+
+    let mut jetted = term;
+    let statistics = jetted.normalize_with_statistics(&jets).unwrap();
+    assert!(jetted.equals(&expected));
+    assert_eq!(statistics.jet_applications, 0);
+    assert_eq!(statistics.unfoldings["add"], 1);
+
+    // A partial application isn't saturated, so the definition is unfolded as well.
+    let mut jetted = parse::<None>(&format!("(add {})", numeral(1)));
+    let statistics = jetted.normalize_with_statistics(&jets).unwrap();
+    assert_eq!(statistics.jet_applications, 0);
+}
+
+#[test]
+fn hashes() {
+    let original = definitions();
+    let jets = with_jets(original.clone(), &[("add", Arithmetic::Add)]);
+
+    // The same name with a different term doesn't get the jet.
+    let mut changed = original;
+    changed.get_mut("add").unwrap().1 = parse(r#"\a \b /A \s \z ([b A] s ([a A] s z))"#);
+    let changed = JetDefinitions::new(changed, jets.jets);
+
+    let mut term = parse::<None>(&format!("(add {} {})", numeral(2), numeral(1)));
+    let statistics = term.normalize_with_statistics(&changed).unwrap();
+    assert_eq!(decode(&term), Some(3));
+    assert_eq!(statistics.jet_applications, 0);
+}
+
+#[test]
+fn dependencies() {
+    let original = definitions();
+    let jets = with_jets(original.clone(), &[("not", Arithmetic::Not)]);
+
+    // `not` itself is unchanged, but it refers to `false`, which now means what `true` does.
+    let mut changed = original;
+    changed.get_mut("false").unwrap().1 = parse(r#"/prop \t \f t"#);
+    let changed = JetDefinitions::new(changed, jets.jets);
+
+    let mut term = parse::<None>("(not true)");
+    let statistics = term.normalize_with_statistics(&changed).unwrap();
+    let mut expected = parse::<None>("true");
+    expected.normalize(&definitions()).unwrap();
+    assert!(term.equals(&expected));
+    assert_eq!(statistics.jet_applications, 0);
+}
+
+#[test]
+fn cross_check() {
+    let definitions = definitions();
+    let add = hash(&definitions, "add");
+    let wrong = JetDefinitions::new(definitions, [(add, Arithmetic::Multiply)].into());
+
+    let term = parse::<None>(&format!("(add {} {})", numeral(2), numeral(3)));
+
+    // Unchecked, the jet is trusted.
+    let mut jetted = term.clone();
+    jetted.normalize(&wrong).unwrap();
+    assert_eq!(decode(&jetted), Some(6));
+
+    let wrong = wrong.cross_checked();
+    let mut jetted = term;
+    assert!(matches!(
+        jetted.normalize(&wrong),
+        Err(NormalizationError::JetMismatch(hash, _)) if hash == add
+    ));
+}
+
+#[test]
+fn net() {
+    let jets = with_jets(definitions(), &[("not", Arithmetic::Not)]);
+
+    let term = parse::<None>("(not (not true))");
+    let mut expected = term.clone();
+    expected.normalize(&definitions()).unwrap();
+
+    let stratified = term.stratified(&jets).unwrap();
+    APPLICATIONS.with(|applications| applications.set(0));
+    let mut net = stratified.into_net::<Net<u32>>().unwrap();
+    assert_eq!(APPLICATIONS.with(|applications| applications.get()), 2);
+
+    net.reduce_all();
+    assert!(net.read_term(NetIndex(0)).equals(&expected));
+}
+
+#[test]
+fn net_declined() {
+    // Church numerals as elementary affine logic has them, with the function they iterate boxed,
+    // so they're stratified. The jet answers without boxes, which isn't.
+    let mut definitions = Typed::new();
+    for (name, term) in [
+        ("two", r#"\f : g = f . \x (g (g x))"#),
+        ("three", r#"\f : g = f . \x (g (g (g x)))"#),
+        (
+            "plus",
+            r#"\m \n \f : g = f : x = (m . g) : y = (n . g) . \z (x (y z))"#,
+        ),
+    ] {
+        definitions.insert(name.into(), (Term::Universe, parse(term)));
+    }
+    let jets = with_jets(definitions.clone(), &[("plus", Arithmetic::Add)]);
+
+    let term = parse::<None>("(plus two three)");
+    let mut expected = term.clone();
+    expected.normalize(&definitions).unwrap();
+    assert_eq!(decode(&expected), Some(5));
+
+    // The net is built from the definition instead, and still reaches the jet's answer.
+    let stratified = term.stratified(&jets).unwrap();
+    APPLICATIONS.with(|applications| applications.set(0));
+    let mut net = stratified.into_net::<Net<u32>>().unwrap();
+    assert_eq!(APPLICATIONS.with(|applications| applications.get()), 1);
+
+    net.reduce_all();
+    assert!(net.read_term(NetIndex(0)).equals(&expected));
+
+    // A jet isn't given arguments it can't see the values of.
+    let term = parse::<None>(r#"\n (plus two n)"#);
+    let mut expected = term.clone();
+    expected.normalize(&definitions).unwrap();
+    APPLICATIONS.with(|applications| applications.set(0));
+    let mut net = term
+        .stratified(&jets)
+        .unwrap()
+        .into_net::<Net<u32>>()
+        .unwrap();
+    assert_eq!(APPLICATIONS.with(|applications| applications.get()), 0);
+
+    net.reduce_all();
+    assert!(net.read_term(NetIndex(0)).equals(&expected));
+}
+
+#[test]
+fn normalizers() {
+    let jets = with_jets(
+        definitions(),
+        &[("add", Arithmetic::Add), ("mul", Arithmetic::Multiply)],
+    );
+
+    for (term, applications) in [
+        (
+            format!("(mul {} (add {} {}))", numeral(3), numeral(4), numeral(2)),
+            2,
+        ),
+        (format!("\\n (add {} n)", numeral(2)), 1),
+        (format!("(add {})", numeral(1)), 0),
+        (
+            format!("(\\f \\a \\b (f a b) add {} {})", numeral(1), numeral(2)),
+            1,
+        ),
+    ] {
+        let term = parse::<None>(&term);
+
+        APPLICATIONS.with(|applications| applications.set(0));
+        let mut substituted = term.clone();
+        let statistics = substituted.normalize_with_statistics(&jets).unwrap();
+        assert_eq!(
+            APPLICATIONS.with(|applications| applications.get()),
+            applications
+        );
+
+        // Evaluation gives the jets the same arguments.
+        APPLICATIONS.with(|applications| applications.set(0));
+        let mut evaluated = term.clone();
+        evaluated.evaluate(&jets).unwrap();
+        assert!(evaluated.equals(&substituted));
+        assert_eq!(
+            APPLICATIONS.with(|applications| applications.get()),
+            applications
+        );
+
+        // Stepping applies the jets with the same reductions, one per step.
+        let mut stepped = term;
+        let mut rules = vec![];
+        while let Some(reduction) = stepped.step(&jets).unwrap() {
+            rules.push(reduction.rule);
+        }
+        assert!(stepped.equals(&substituted));
+        assert_eq!(rules.len(), statistics.reductions());
+        assert_eq!(
+            rules
+                .iter()
+                .filter(|rule| **rule == Rule::JetApplication)
+                .count(),
+            statistics.jet_applications
+        );
+    }
+}
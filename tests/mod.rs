@@ -1,16 +1,31 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
 
-use welkin_core::term::{typed::Definitions, NullCache, Primitives, Show, Term, TypedDefinitions};
+use welkin_core::term::{
+    typed::Definitions, NullCache, OpaqueDefinitions, Primitives, Show, Term, TypedDefinitions,
+};
 
+mod cache;
+mod equivalence;
+mod evaluate;
+mod jets;
+#[cfg(feature = "native")]
+mod native;
 mod net;
+mod opaque;
 mod primitives;
+mod stratification;
 
 #[allow(dead_code)]
 fn check_all(terms: &str) {
     let definitions: Definitions = terms.trim().parse().unwrap();
 
+    let opaque: HashSet<_> = definitions.opaque.into_iter().collect();
     let definitions: HashMap<_, _> = definitions.terms.into_iter().collect();
-    for (_, def) in &definitions {
+    let definitions = OpaqueDefinitions::new(definitions, opaque);
+    for (_, def) in &definitions.definitions {
         def.1.is_stratified().unwrap();
         def.0
             .check(&Term::Universe, &definitions, &mut NullCache)
@@ -25,8 +40,10 @@ fn parse<V: Primitives<String>>(term: &str) -> Term<String, V> {
     term.map_primitive(|_| panic!())
 }
 
-fn check<V: Primitives<String> + Clone + Hash>(ty: Term<String, V>, term: Term<String, V>)
-where
+fn check<V: Primitives<String> + Clone + Hash + PartialEq>(
+    ty: Term<String, V>,
+    term: Term<String, V>,
+) where
     V: Show,
 {
     let definitions = HashMap::new();
@@ -38,7 +55,7 @@ where
     term.check(&ty, &definitions, &mut NullCache).unwrap();
 }
 
-fn check_with<V: Primitives<String> + Clone + Hash, D: TypedDefinitions<String, V>>(
+fn check_with<V: Primitives<String> + Clone + Hash + PartialEq, D: TypedDefinitions<String, V>>(
     ty: Term<String, V>,
     term: Term<String, V>,
     definitions: &D,
@@ -52,7 +69,7 @@ fn check_with<V: Primitives<String> + Clone + Hash, D: TypedDefinitions<String,
     term.check(&ty, definitions, &mut NullCache).unwrap();
 }
 
-fn normalizes_to<V: Primitives<String> + Clone + Hash, D: TypedDefinitions<String, V>>(
+fn normalizes_to<V: Primitives<String> + Clone + Hash + PartialEq, D: TypedDefinitions<String, V>>(
     mut term: Term<String, V>,
     target: Term<String, V>,
     definitions: &D,
@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+
+use welkin_core::term::{
+    native::{
+        IntegerOperation, IntegerType, Native, NativeType, Operation, SequenceOperation, MAX_LENGTH,
+    },
+    typed::Definitions,
+    Index, NormalizationError, NullCache, Term,
+};
+
+use crate::check_with;
+
+type Typed = HashMap<String, (Term<String, Native>, Term<String, Native>)>;
+
+fn example() -> Typed {
+    let definitions: Definitions<String, Native> =
+        include_str!("../../example.wc").parse().unwrap();
+    definitions.terms.into_iter().collect()
+}
+
+#[track_caller]
+fn parse(term: &str) -> Term<String, Native> {
+    term.trim().parse().unwrap()
+}
+
+fn apply(function: Native, arguments: Vec<Native>) -> Term<String, Native> {
+    arguments
+        .into_iter()
+        .fold(Term::Primitive(function), |function, argument| {
+            Term::Apply {
+                function: Box::new(function),
+                argument: Box::new(Term::Primitive(argument)),
+                erased: false,
+            }
+        })
+}
+
+fn integer(ty: IntegerType, operation: IntegerOperation) -> Native {
+    Native::Operation(Operation::Integer(ty, operation))
+}
+
+fn normalize(
+    mut term: Term<String, Native>,
+) -> Result<Term<String, Native>, NormalizationError<String, Native>> {
+    term.normalize(&example())?;
+    Ok(term)
+}
+
+#[track_caller]
+fn normalizes_to(term: Term<String, Native>, expected: Term<String, Native>) {
+    let mut expected = expected;
+    expected.normalize(&example()).unwrap();
+    let term = normalize(term).unwrap();
+    assert!(term.equals(&expected), "{:?} isn't {:?}", term, expected);
+}
+
+#[test]
+fn arithmetic() {
+    use IntegerOperation::*;
+    use IntegerType::*;
+
+    for (operation, a, b, result) in [
+        (Add, 2u8, 3u8, 5u8),
+        (Subtract, 7, 3, 4),
+        (Multiply, 6, 7, 42),
+        (Divide, 7, 2, 3),
+        (Remainder, 7, 2, 1),
+    ] {
+        normalizes_to(
+            apply(integer(U8, operation), vec![a.into(), b.into()]),
+            Term::Primitive(result.into()),
+        );
+    }
+
+    normalizes_to(
+        apply(integer(I32, Subtract), vec![2i32.into(), 5i32.into()]),
+        Term::Primitive((-3i32).into()),
+    );
+    normalizes_to(
+        apply(integer(I64, Divide), vec![(-7i64).into(), 2i64.into()]),
+        Term::Primitive((-3i64).into()),
+    );
+    normalizes_to(
+        apply(integer(U64, Add), vec![u64::MAX.into(), 0u64.into()]),
+        Term::Primitive(u64::MAX.into()),
+    );
+}
+
+#[test]
+fn failures() {
+    use IntegerOperation::*;
+    use IntegerType::*;
+
+    for term in [
+        apply(integer(U8, Add), vec![250u8.into(), 10u8.into()]),
+        apply(integer(U16, Subtract), vec![1u16.into(), 2u16.into()]),
+        apply(integer(I8, Multiply), vec![(-128i8).into(), (-1i8).into()]),
+        apply(integer(I32, Divide), vec![1i32.into(), 0i32.into()]),
+        apply(integer(U32, Remainder), vec![1u32.into(), 0u32.into()]),
+        apply(integer(U8, Add), vec![1u16.into(), 2u8.into()]),
+        apply(
+            Native::Operation(Operation::String(SequenceOperation::Concat)),
+            vec!["a".into(), vec![0u8].into()],
+        ),
+        apply(1u8.into(), vec![2u8.into()]),
+    ] {
+        assert!(
+            matches!(
+                normalize(term),
+                Err(NormalizationError::PrimitiveFailed(..))
+            ),
+            "should fail"
+        );
+    }
+
+    for (term, error) in [
+        (
+            apply(integer(I32, Divide), vec![1i32.into(), 0i32.into()]),
+            "division by zero",
+        ),
+        (
+            apply(integer(U32, Remainder), vec![1u32.into(), 0u32.into()]),
+            "division by zero",
+        ),
+        (
+            apply(integer(U8, Add), vec![250u8.into(), 10u8.into()]),
+            "#u8.add of 250 and 10 is out of range",
+        ),
+        (
+            apply(
+                Native::Operation(Operation::String(SequenceOperation::Length)),
+                vec!["a".repeat(MAX_LENGTH + 1).into()],
+            ),
+            "length 65537 is longer than 65536",
+        ),
+    ] {
+        match normalize(term) {
+            Err(NormalizationError::PrimitiveFailed(message, _)) => {
+                assert!(message.to_string().starts_with(error), "{}", message)
+            }
+            other => panic!("expected {}, got {:?}", error, other.err()),
+        }
+    }
+}
+
+#[test]
+fn free_variables() {
+    let definitions = example();
+
+    // An operation waiting on a variable is stuck rather than failed, whichever way it's reduced.
+    for term in [
+        r#"\x (#u8.add x 1u8)"#,
+        r#"\x (#u8.divide 1u8 (#u8.multiply x 0u8))"#,
+    ] {
+        let term = parse(term);
+        assert!(normalize(term.clone()).unwrap().equals(&term));
+
+        let mut evaluated = term.clone();
+        evaluated.evaluate(&definitions).unwrap();
+        assert!(evaluated.equals(&term));
+    }
+
+    // A literal of the wrong type still fails beside a variable.
+    assert!(matches!(
+        normalize(parse(r#"\x (#u8.add x 1u16)"#)),
+        Err(NormalizationError::PrimitiveFailed(..))
+    ));
+
+    let a = parse(r#"\x (#u8.add x 1u8)"#);
+    let b = parse(r#"\y (#u8.add 1u8 y)"#);
+    assert!(!a.equivalent(&b, &definitions, &mut NullCache).unwrap());
+    assert!(a.equivalent(&a, &definitions, &mut NullCache).unwrap());
+}
+
+#[test]
+fn partial_application() {
+    let term = apply(
+        integer(IntegerType::U8, IntegerOperation::Add),
+        vec![1u8.into()],
+    );
+    assert!(normalize(term.clone()).unwrap().equals(&term));
+}
+
+#[test]
+fn comparisons() {
+    use IntegerOperation::*;
+    use IntegerType::*;
+
+    let boolean = |value: bool| Term::Reference(value.to_string());
+
+    for (operation, a, b, result) in [
+        (Equal, 3i16, 3i16, true),
+        (Equal, 3, -3, false),
+        (Less, -1, 2, true),
+        (Less, 2, 2, false),
+        (LessOrEqual, 2, 2, true),
+        (Greater, 2, -1, true),
+        (GreaterOrEqual, -1, 2, false),
+    ] {
+        normalizes_to(
+            apply(integer(I16, operation), vec![a.into(), b.into()]),
+            boolean(result),
+        );
+    }
+
+    normalizes_to(
+        apply(
+            Native::Operation(Operation::String(SequenceOperation::Equal)),
+            vec!["welkin".into(), "welkin".into()],
+        ),
+        boolean(true),
+    );
+    normalizes_to(
+        apply(
+            Native::Operation(Operation::Bytes(SequenceOperation::Equal)),
+            vec![vec![1u8].into(), vec![2u8].into()],
+        ),
+        boolean(false),
+    );
+}
+
+#[test]
+fn sequences() {
+    let nat = |value: usize| {
+        (0..value).fold(Term::Reference("zero".to_owned()), |pred, _| Term::Apply {
+            function: Box::new(Term::Reference("succ".to_owned())),
+            argument: Box::new(pred),
+            erased: false,
+        })
+    };
+
+    normalizes_to(
+        apply(
+            Native::Operation(Operation::String(SequenceOperation::Concat)),
+            vec!["wel".into(), "kin".into()],
+        ),
+        Term::Primitive("welkin".into()),
+    );
+    normalizes_to(
+        apply(
+            Native::Operation(Operation::String(SequenceOperation::Length)),
+            vec!["héllo".into()],
+        ),
+        nat(5),
+    );
+    normalizes_to(
+        apply(
+            Native::Operation(Operation::Bytes(SequenceOperation::Concat)),
+            vec![vec![1u8, 2].into(), vec![3u8].into()],
+        ),
+        Term::Primitive(vec![1u8, 2, 3].into()),
+    );
+    normalizes_to(
+        apply(
+            Native::Operation(Operation::Bytes(SequenceOperation::Length)),
+            vec!["héllo".as_bytes().to_vec().into()],
+        ),
+        nat(6),
+    );
+    normalizes_to(
+        apply(
+            Native::Operation(Operation::String(SequenceOperation::Length)),
+            vec!["".into()],
+        ),
+        nat(0),
+    );
+}
+
+#[test]
+fn types() {
+    let definitions = example();
+    let native = |ty| Term::Primitive(Native::Type(ty));
+    let reference = |name: &str| Term::Reference(name.to_owned());
+
+    for ty in IntegerType::ALL {
+        let (a, b): (Native, Native) = match ty {
+            IntegerType::U8 => (1u8.into(), 2u8.into()),
+            IntegerType::U16 => (1u16.into(), 2u16.into()),
+            IntegerType::U32 => (1u32.into(), 2u32.into()),
+            IntegerType::U64 => (1u64.into(), 2u64.into()),
+            IntegerType::I8 => (1i8.into(), 2i8.into()),
+            IntegerType::I16 => (1i16.into(), 2i16.into()),
+            IntegerType::I32 => (1i32.into(), 2i32.into()),
+            IntegerType::I64 => (1i64.into(), 2i64.into()),
+        };
+
+        for operation in IntegerOperation::ALL {
+            let result = match operation {
+                IntegerOperation::Add
+                | IntegerOperation::Subtract
+                | IntegerOperation::Multiply
+                | IntegerOperation::Divide
+                | IntegerOperation::Remainder => native(NativeType::Integer(ty)),
+                _ => reference("Bool"),
+            };
+            let term = apply(integer(ty, operation), vec![a.clone(), b.clone()]);
+            check_with(result, term, &definitions);
+        }
+    }
+
+    for (operand, ty, a) in [
+        (
+            NativeType::String,
+            Operation::String as fn(SequenceOperation) -> Operation,
+            Native::from("a"),
+        ),
+        (NativeType::Bytes, Operation::Bytes, Native::from(vec![0u8])),
+    ] {
+        check_with(
+            native(operand),
+            apply(
+                Native::Operation(ty(SequenceOperation::Concat)),
+                vec![a.clone(), a.clone()],
+            ),
+            &definitions,
+        );
+        check_with(
+            reference("Bool"),
+            apply(
+                Native::Operation(ty(SequenceOperation::Equal)),
+                vec![a.clone(), a.clone()],
+            ),
+            &definitions,
+        );
+        check_with(
+            reference("Nat"),
+            apply(
+                Native::Operation(ty(SequenceOperation::Length)),
+                vec![a.clone()],
+            ),
+            &definitions,
+        );
+        check_with(Term::Universe, native(operand), &definitions);
+    }
+}
+
+#[test]
+fn show() {
+    let term = apply(
+        integer(IntegerType::I8, IntegerOperation::Add),
+        vec![(-1i8).into(), 2i8.into()],
+    );
+    assert_eq!(format!("{:?}", term), "((#i8.add -1i8) 2i8)");
+
+    let term = apply(
+        Native::Operation(Operation::String(SequenceOperation::Length)),
+        vec!["say \"hi\"".into()],
+    );
+    assert_eq!(format!("{:?}", term), r#"(#string.length "say \"hi\"")"#);
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            Term::<String, _>::Primitive(Native::from(vec![0u8, b'a']))
+        ),
+        r#"b"\x00a""#
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            Term::<String, _>::Primitive(Native::Type(NativeType::Bytes))
+        ),
+        "#bytes"
+    );
+}
+
+#[test]
+fn serde() {
+    let term = apply(
+        Native::Operation(Operation::Bytes(SequenceOperation::Concat)),
+        vec![vec![1u8].into(), "text".as_bytes().to_vec().into()],
+    );
+    let json = serde_json::to_string(&term).unwrap();
+    let parsed: Term<String, Native> = serde_json::from_str(&json).unwrap();
+    assert!(parsed.equals(&term));
+
+    let integer = Native::from(i64::MIN);
+    let json = serde_json::to_string(&integer).unwrap();
+    assert_eq!(serde_json::from_str::<Native>(&json).unwrap(), integer);
+
+    let json = serde_json::to_string(&Native::from(255u16)).unwrap();
+    assert!(serde_json::from_str::<Native>(&json.replace("U16", "U8")).is_ok());
+    assert!(
+        serde_json::from_str::<Native>(&json.replace("255", "256").replace("U16", "U8")).is_err()
+    );
+}
+
+#[test]
+fn literals() {
+    normalizes_to(parse("(#u8.add 2u8 3u8)"), Term::Primitive(5u8.into()));
+    normalizes_to(
+        parse("(#i8.subtract -1i8 2i8)"),
+        Term::Primitive((-3i8).into()),
+    );
+    normalizes_to(
+        parse(r#"(#string.concat "say " "\"hi\"\n")"#),
+        Term::Primitive("say \"hi\"\n".into()),
+    );
+    normalizes_to(parse(r#"(#string.equal "\u{e9}" "é")"#), parse("true"));
+
+    // Whatever is shown can be parsed back, except byte strings.
+    for term in [
+        "((#i8.add -1i8) 2i8)",
+        r#"(#string.length "say \"hi\"")"#,
+        "\\x ((#u64.less_or_equal ^0) 18446744073709551615u64)",
+        "#bytes",
+        "#bytes.concat",
+        "#i64",
+    ] {
+        assert_eq!(format!("{:?}", parse(term)), term);
+    }
+
+    // A literal is only a name if it is bound.
+    assert!(parse("\\x \\3u8 3u8").equals(&Term::Lambda {
+        body: Box::new(Term::Lambda {
+            body: Box::new(Term::Variable(Index(0))),
+            erased: false,
+        }),
+        erased: false,
+    }));
+
+    // Without a type suffix, a name that starts with a digit is a reference.
+    for name in ["42", "2fold", "3u8x"] {
+        assert!(parse(name).equals(&Term::Reference(name.to_owned())));
+    }
+
+    for (term, error) in [
+        ("256u8", "256 is out of range for u8"),
+        ("-1u32", "-1 is out of range for u32"),
+        ("-42", "invalid integer literal -42"),
+        ("#u8.concat", "unknown primitive #u8.concat"),
+        ("#float", "unknown primitive #float"),
+        (r#""\u{110000}""#, "invalid unicode escape \\u{110000}"),
+    ] {
+        let message = term
+            .parse::<Term<String, Native>>()
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(message.starts_with(error), "{}: {}", term, message);
+    }
+}
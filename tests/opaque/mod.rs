@@ -0,0 +1,78 @@
+use std::collections::{HashMap, HashSet};
+
+use welkin_core::term::{typed::Definitions, AnalysisError, NullCache, OpaqueDefinitions, Term};
+
+use crate::{check_all, normalizes_to, parse};
+
+type Typed = HashMap<String, (Term<String>, Term<String>)>;
+
+fn opaque_definitions(source: &str) -> OpaqueDefinitions<String, Typed> {
+    let definitions: Definitions = source.trim().parse().unwrap();
+
+    let opaque: HashSet<_> = definitions.opaque.into_iter().collect();
+    let definitions: HashMap<_, _> = definitions.terms.into_iter().collect();
+    OpaqueDefinitions::new(definitions, opaque)
+}
+
+#[test]
+fn parse_attribute() {
+    let definitions: Definitions = r#"
+@opaque Id : +,:* * = \A A
+Unit : * = *
+"#
+    .trim()
+    .parse()
+    .unwrap();
+
+    assert_eq!(definitions.terms.len(), 2);
+    assert_eq!(definitions.opaque, vec!["Id".to_owned()]);
+}
+
+#[test]
+fn transparent_unfolds() {
+    check_all(
+        r#"
+Id : +,:* * = \A A
+id : +,A:* +,:(Id A) A = \A \x x
+"#,
+    );
+}
+
+#[test]
+fn opaque_does_not_unfold() {
+    let definitions = opaque_definitions(
+        r#"
+@opaque Id : +,:* * = \A A
+id : +,A:* +,:(Id A) A = \A \x x
+"#,
+    );
+
+    let (ty, term) = &definitions.definitions["id"];
+    ty.check(&Term::Universe, &definitions, &mut NullCache)
+        .unwrap();
+    assert!(matches!(
+        term.check(ty, &definitions, &mut NullCache),
+        Err(AnalysisError::TypeError { .. })
+    ));
+}
+
+#[test]
+fn opaque_compares_by_name() {
+    check_all(
+        r#"
+@opaque Id : +,:* * = \A A
+id : +,A:* +,:(Id A) (Id A) = \A \x x
+"#,
+    );
+}
+
+#[test]
+fn opaque_normalizes() {
+    let definitions = opaque_definitions(
+        r#"
+@opaque Id : +,:* * = \A A
+"#,
+    );
+
+    normalizes_to::<welkin_core::term::None, _>(parse("(Id *)"), Term::Universe, &definitions);
+}
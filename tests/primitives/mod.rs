@@ -1,5 +1,10 @@
 use std::{collections::HashMap, fmt::Display};
-use welkin_core::term::{alloc::Allocator, Primitives, Term};
+use welkin_core::term::{
+    alloc::{Allocator, System},
+    typed::Definitions,
+    Direction, Index, NormalizationError, NullCache, Path, PrimitiveError, PrimitiveParser,
+    Primitives, ReductionStrategy, Rule, StratificationError, Term,
+};
 
 use crate::{check, check_with, normalizes_to, parse};
 
@@ -25,9 +30,9 @@ fn ty_id() {
 
         fn apply<A: Allocator<String, Self>>(
             &self,
-            _: &Term<String, Self, A>,
+            _: Vec<Term<String, Self, A>>,
             _: &A,
-        ) -> Term<String, Self, A>
+        ) -> Option<Result<Term<String, Self, A>, PrimitiveError>>
         where
             Self: Sized,
         {
@@ -80,13 +85,13 @@ fn unit_id() {
 
         fn apply<A: Allocator<String, Self>>(
             &self,
-            term: &Term<String, Self, A>,
-            alloc: &A,
-        ) -> Term<String, Self, A>
+            arguments: Vec<Term<String, Self, A>>,
+            _: &A,
+        ) -> Option<Result<Term<String, Self, A>, PrimitiveError>>
         where
             Self: Sized,
         {
-            alloc.copy(term)
+            Some(Ok(arguments.into_iter().next().unwrap()))
         }
     }
 
@@ -100,5 +105,395 @@ fn unit_id() {
 
     check_with(parse("Unit"), term.clone(), &definitions);
 
-    normalizes_to(term, unit, &definitions);
+    // The primitive is given the erased normal form of its argument.
+    normalizes_to(term, parse(r#" \x x "#), &definitions);
+}
+
+// Returns its argument unchanged, whatever the type of references.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Identity;
+
+impl Display for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "identity")
+    }
+}
+
+impl<T: Clone> Primitives<T> for Identity {
+    fn ty<A: Allocator<T, Self>>(&self, alloc: &A) -> Term<T, Self, A> {
+        Term::Function {
+            erased: false,
+            argument_type: alloc.alloc(Term::Universe),
+            return_type: alloc.alloc(Term::Universe),
+        }
+    }
+
+    fn apply<A: Allocator<T, Self>>(
+        &self,
+        arguments: Vec<Term<T, Self, A>>,
+        _: &A,
+    ) -> Option<Result<Term<T, Self, A>, PrimitiveError>> {
+        Some(Ok(arguments.into_iter().next().unwrap()))
+    }
+}
+
+fn with_identity(term: &str) -> Term<String, Identity> {
+    parse::<Identity>(term).map_reference(|name| {
+        if name == "identity" {
+            Term::Primitive(Identity)
+        } else {
+            Term::Reference(name)
+        }
+    })
+}
+
+#[test]
+fn substitution() {
+    let mut term = with_identity(r#"(\x (x identity) \y y)"#);
+    term.normalize(&HashMap::new()).unwrap();
+    assert!(term.equals(&Term::Primitive(Identity)));
+
+    let mut term = with_identity(r#"\a (\x \y ((x identity) y) \f \b (f b))"#);
+    term.normalize(&HashMap::new()).unwrap();
+    assert!(term.equals(&parse(r#"\a \y y"#)));
+
+    let mut term = with_identity(r#"\a \b identity"#);
+    term.substitute(Index(0), &with_identity("(identity ^3)"));
+    assert!(term.equals(&with_identity(r#"\a \b identity"#)));
+}
+
+#[test]
+fn stratification() {
+    with_identity(r#"\x (identity x)"#).is_stratified().unwrap();
+    with_identity(r#"\a : x = a . (identity x)"#)
+        .is_stratified()
+        .unwrap();
+    with_identity(r#"/x identity"#).is_sound().unwrap();
+
+    assert!(matches!(
+        with_identity(r#"\x ((identity x) x)"#).is_stratified(),
+        Err(StratificationError::MultiplicityMismatch { uses: 2, .. })
+    ));
+    assert!(matches!(
+        with_identity(r#"\a : x = a (identity x)"#).is_stratified(),
+        Err(StratificationError::DupNonUnitBoxMultiplicity { depth: 0, .. })
+    ));
+}
+
+#[test]
+fn recursion() {
+    let mut definitions = HashMap::new();
+    definitions.insert(
+        "apply".to_owned(),
+        (with_identity("*"), with_identity(r#"\x (identity x)"#)),
+    );
+
+    let term = with_identity(r#"(apply identity)"#);
+    assert!(!term.is_recursive_in(&definitions, &System, &System));
+
+    let mut term = term.stratified(&definitions).unwrap().into_inner();
+    term.normalize(&definitions).unwrap();
+    assert!(term.equals(&Term::Primitive(Identity)));
+}
+
+#[test]
+fn map_reference() {
+    let term = with_identity(r#"\x (identity (rename x))"#)
+        .map_reference(|name| Term::Reference(name.len()));
+
+    let apply = |function, argument| Term::Apply {
+        function: Box::new(function),
+        argument: Box::new(argument),
+        erased: false,
+    };
+    let expected = Term::Lambda {
+        body: Box::new(apply(
+            Term::Primitive(Identity),
+            apply(Term::Reference(6), Term::Variable(Index(0))),
+        )),
+        erased: false,
+    };
+
+    assert!(term.equals(&expected));
+}
+
+// `constant` takes two arguments and returns the first, and `expect` fails unless its argument is
+// the universe, or is stuck on a variable.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Builtin {
+    Constant,
+    Expect,
+}
+
+impl Display for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Builtin::Constant => write!(f, "constant"),
+            Builtin::Expect => write!(f, "expect"),
+        }
+    }
+}
+
+impl Primitives<String> for Builtin {
+    fn ty<A: Allocator<String, Self>>(&self, alloc: &A) -> Term<String, Self, A> {
+        let function = |return_type| Term::Function {
+            erased: false,
+            argument_type: alloc.alloc(Term::Universe),
+            return_type: alloc.alloc(return_type),
+        };
+        match self {
+            Builtin::Constant => function(function(Term::Universe)),
+            Builtin::Expect => function(Term::Universe),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Builtin::Constant => 2,
+            Builtin::Expect => 1,
+        }
+    }
+
+    fn apply<A: Allocator<String, Self>>(
+        &self,
+        arguments: Vec<Term<String, Self, A>>,
+        _: &A,
+    ) -> Option<Result<Term<String, Self, A>, PrimitiveError>> {
+        let mut arguments = arguments.into_iter();
+        let argument = arguments.next().unwrap();
+        match (self, &argument) {
+            (Builtin::Expect, Term::Universe) | (Builtin::Constant, _) => Some(Ok(argument)),
+            (Builtin::Expect, Term::Variable(_)) => None,
+            (Builtin::Expect, _) => Some(Err("expected a universe".into())),
+        }
+    }
+}
+
+impl PrimitiveParser<String> for Builtin {
+    fn named(name: &str) -> Result<Self, String> {
+        match name {
+            "constant" => Ok(Builtin::Constant),
+            "expect" => Ok(Builtin::Expect),
+            _ => Err(format!("no builtin named {}", name)),
+        }
+    }
+}
+
+#[track_caller]
+fn with_builtins(term: &str) -> Term<String, Builtin> {
+    term.trim().parse().unwrap()
+}
+
+// Normalizes the term by substitution, by evaluation, by applicative order and step by step, which
+// must all agree.
+#[track_caller]
+fn normalize_builtins(
+    term: &str,
+) -> Result<Term<String, Builtin>, NormalizationError<String, Builtin>> {
+    let definitions = HashMap::new();
+    let term = with_builtins(term);
+
+    let mut substituted = term.clone();
+    let substitution = substituted.normalize(&definitions);
+
+    let mut evaluated = term.clone();
+    let evaluation = evaluated.evaluate(&definitions);
+
+    let mut applicative = term.clone();
+    let applicative_order = applicative.reduce(
+        ReductionStrategy::ApplicativeOrder { erase: true },
+        &definitions,
+    );
+
+    let mut counted = term.clone();
+    let statistics = counted.normalize_with_statistics(&definitions);
+
+    let mut stepped = term.clone();
+    let mut count = 0;
+    let steps = loop {
+        match stepped.step(&definitions) {
+            Ok(Some(_)) => count += 1,
+            Ok(None) => break Ok(()),
+            Err(error) => break Err(error),
+        }
+    };
+
+    assert_eq!(substitution.is_ok(), evaluation.is_ok());
+    assert_eq!(substitution.is_ok(), applicative_order.is_ok());
+    assert_eq!(substitution.is_ok(), steps.is_ok());
+    if let (Err(substitution), Err(steps)) = (&substitution, &steps) {
+        assert_eq!(substitution.redex().path, steps.redex().path);
+    }
+    substitution?;
+
+    // Each step is a single reduction, including those in a primitive's arguments.
+    assert_eq!(count, statistics.unwrap().reductions());
+    assert!(substituted.equals(&evaluated));
+    assert!(substituted.equals(&applicative));
+    assert!(substituted.equals(&stepped));
+    Ok(substituted)
+}
+
+#[test]
+fn partial_application() {
+    let term = normalize_builtins(r#"(#constant *)"#).unwrap();
+    assert!(term.equals(&with_builtins(r#"(#constant *)"#)));
+
+    let term = normalize_builtins(r#"\x ((#constant x) *)"#).unwrap();
+    assert!(term.equals(&with_builtins(r#"\x x"#)));
+
+    let term = normalize_builtins(r#"(\f (f \y y) (#constant *))"#).unwrap();
+    assert!(term.equals(&Term::Universe));
+
+    let term = normalize_builtins(r#"(/t (#constant *) \x x)"#).unwrap();
+    assert!(term.equals(&Term::Universe));
+
+    // Erased arguments don't count towards the arity.
+    let term = normalize_builtins(r#"(([#constant *] \x x) *)"#).unwrap();
+    assert!(term.equals(&with_builtins(r#"\x x"#)));
+}
+
+#[test]
+fn normal_arguments() {
+    let term = normalize_builtins(r#"(#expect (\x x *))"#).unwrap();
+    assert!(term.equals(&Term::Universe));
+
+    let term = normalize_builtins(r#"((#constant : x = . * x) \y y)"#).unwrap();
+    assert!(term.equals(&Term::Universe));
+}
+
+#[test]
+fn argument_steps() {
+    let definitions = HashMap::new();
+    let mut term = with_builtins(r#"(#expect (\x x *))"#);
+
+    let reduction = term.step(&definitions).unwrap().unwrap();
+    assert_eq!(reduction.rule, Rule::Beta);
+    assert_eq!(reduction.path, vec![Direction::Argument].into());
+
+    let reduction = term.step(&definitions).unwrap().unwrap();
+    assert_eq!(reduction.rule, Rule::PrimitiveApplication);
+    assert!(reduction.path.is_root());
+    assert!(term.equals(&Term::Universe));
+
+    // A stuck application is passed over once its arguments are normal.
+    let mut term = with_builtins(r#"\x ((#expect x) (\y y *))"#);
+    let reduction = term.step(&definitions).unwrap().unwrap();
+    assert_eq!(reduction.rule, Rule::Beta);
+    let path: Path = vec![Direction::Body, Direction::Argument].into();
+    assert_eq!(reduction.path, path);
+    assert!(term.step(&definitions).unwrap().is_none());
+}
+
+#[test]
+fn stuck_arguments() {
+    let term = normalize_builtins(r#"\x (#expect x)"#).unwrap();
+    assert!(term.equals(&with_builtins(r#"\x (#expect x)"#)));
+
+    // The arguments of a stuck application are still normalized, and it can be an argument.
+    let term = normalize_builtins(r#"\x ((#constant (#expect (\y y x))) *)"#).unwrap();
+    assert!(term.equals(&with_builtins(r#"\x (#expect x)"#)));
+
+    let term = normalize_builtins(r#"\x (\f (f (#expect x)) \y y)"#).unwrap();
+    assert!(term.equals(&with_builtins(r#"\x (#expect x)"#)));
+
+    // Stuck applications are compared like any other neutral term.
+    let definitions = HashMap::new();
+    let stuck = with_builtins(r#"\x (#expect x)"#);
+    let equivalent = |other: &str| {
+        stuck
+            .equivalent(&with_builtins(other), &definitions, &mut NullCache)
+            .unwrap()
+    };
+    assert!(equivalent(r#"\x ((#constant (#expect x)) x)"#));
+    assert!(!equivalent(r#"\x x"#));
+}
+
+#[test]
+fn failure() {
+    assert!(matches!(
+        normalize_builtins(r#"(#expect \x x)"#),
+        Err(NormalizationError::PrimitiveFailed(..))
+    ));
+    match normalize_builtins(r#"\x ((#constant (#expect \y x)) *)"#) {
+        Err(NormalizationError::PrimitiveFailed(_, redex)) => {
+            assert!(redex.term.equals(&with_builtins(r#"(#expect \y ^1)"#)));
+            let path: Path = vec![Direction::Body, Direction::Function, Direction::Argument].into();
+            assert_eq!(redex.path, Some(path));
+            assert_eq!(redex.definition, None);
+        }
+        _ => panic!(),
+    }
+
+    // A failure in an argument that is discarded is only reached by applicative order.
+    let definitions = HashMap::new();
+    let term = with_builtins(r#"(\x * (#expect \x x))"#);
+    term.clone().normalize(&definitions).unwrap();
+    term.clone().evaluate(&definitions).unwrap();
+    assert!(matches!(
+        term.clone().reduce(
+            ReductionStrategy::ApplicativeOrder { erase: true },
+            &definitions
+        ),
+        Err(NormalizationError::PrimitiveFailed(..))
+    ));
+}
+
+#[test]
+fn literals() {
+    let definitions: Definitions<String, Builtin> =
+        "unit : * = (#expect *)\nfirst : * = (#constant unit 42)"
+            .parse()
+            .unwrap();
+    let definitions: HashMap<_, _> = definitions.terms.into_iter().collect();
+    let mut term = with_builtins("first");
+    term.normalize(&definitions).unwrap();
+    assert!(term.equals(&Term::Universe));
+
+    // Without primitives, names that start with a digit are references and other literals are
+    // rejected.
+    assert!(parse::<Builtin>("42").equals(&Term::Reference("42".into())));
+    for (term, error) in [
+        ("#constant", "unknown primitive #constant"),
+        (r#""text""#, "string literals aren't supported"),
+        ("-1", "invalid integer literal -1"),
+    ] {
+        let message = term.parse::<Term<String>>().err().unwrap().to_string();
+        assert!(message.starts_with(error), "{}: {}", term, message);
+    }
+
+    let message = "#identity".parse::<Term<String, Builtin>>().err().unwrap();
+    assert!(message.to_string().starts_with("no builtin named identity"));
+}
+
+#[test]
+fn failure_after_unfolding() {
+    let definitions: Definitions<String, Builtin> =
+        "id : * = \\x x\nfail : * = \\z (#expect \\x x)"
+            .parse()
+            .unwrap();
+    let definitions: HashMap<_, _> = definitions.terms.into_iter().collect();
+    let strategies = [
+        ReductionStrategy::NormalOrder { erase: true },
+        ReductionStrategy::ApplicativeOrder { erase: true },
+    ];
+
+    // `id` is left behind once its reduct is stuck, so the failure in the argument beside it is
+    // the term's own.
+    for strategy in strategies {
+        let error = with_builtins(r#"\y ((id y) (#expect \x x))"#)
+            .reduce(strategy, &definitions)
+            .unwrap_err();
+        assert!(matches!(error, NormalizationError::PrimitiveFailed(..)));
+        let path: Path = vec![Direction::Body, Direction::Argument].into();
+        assert_eq!(error.redex().path, Some(path));
+        assert_eq!(error.redex().definition, None);
+    }
+
+    for strategy in strategies {
+        let error = with_builtins(r#"\y ((fail y) *)"#)
+            .reduce(strategy, &definitions)
+            .unwrap_err();
+        assert_eq!(error.redex().definition.as_deref(), Some("fail"));
+    }
 }
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use welkin_core::{
+    net::{Index as NetIndex, Net, VisitNetExt},
+    term::{
+        alloc::Allocator, content_hash, BinderKind, Direction, Index, Jet, JetDefinitions, None,
+        Path, StratificationError, Term, Usage, Use,
+    },
+};
+
+use crate::parse;
+
+use Direction::*;
+
+fn path(directions: &[Direction]) -> Path {
+    directions.to_vec().into()
+}
+
+fn stratification_error(term: &str) -> StratificationError<String> {
+    parse::<None>(term).is_stratified().unwrap_err()
+}
+
+#[test]
+fn multiplicity() {
+    match stratification_error(r#"\f \x (f x x)"#) {
+        StratificationError::MultiplicityMismatch { binder, uses } => {
+            assert_eq!(binder, path(&[Body]));
+            assert_eq!(uses, 2);
+        }
+        error => panic!("{:?}", error),
+    }
+
+    assert_eq!(
+        stratification_error(r#"\x (x x)"#).to_string(),
+        "The variable of the lambda at <root> is used 2 times, but a lambda is linear and may use \
+         its variable at most once; duplicate a boxed value to use it more often"
+    );
+}
+
+#[test]
+fn erased_used() {
+    for (term, binder, occurrence) in [
+        (
+            r#"\f /x (f x)"#,
+            path(&[Body]),
+            path(&[Body, Body, Argument]),
+        ),
+        // The body of an erased lambda survives erasure, so uses inside it count.
+        (r#"/x /y x"#, path(&[]), path(&[Body, Body])),
+        (
+            r#"/x : y = . x y"#,
+            path(&[]),
+            path(&[Body, Expression, Contents]),
+        ),
+    ] {
+        match stratification_error(term) {
+            StratificationError::ErasedUsed {
+                binder: found_binder,
+                occurrence: found_occurrence,
+            } => {
+                assert_eq!(found_binder, binder, "{}", term);
+                assert_eq!(found_occurrence, occurrence, "{}", term);
+            }
+            error => panic!("{}: {:?}", term, error),
+        }
+    }
+
+    for term in [r#"/x \f [f x]"#, r#"/A \x { x : A }"#, r#"/x /y \z z"#] {
+        parse::<None>(term).is_stratified().unwrap();
+    }
+
+    // Soundness only checks erasure.
+    parse::<None>(r#"/x [^1 x]"#).is_sound().unwrap();
+    assert!(matches!(
+        parse::<None>(r#"\y (y /x (y x))"#).is_sound(),
+        Err(StratificationError::ErasedUsed { binder, .. }) if binder == path(&[Body, Argument])
+    ));
+}
+
+#[test]
+fn affine_in_box() {
+    match stratification_error(r#"\f \x (f . x)"#) {
+        StratificationError::AffineUsedInBox {
+            binder,
+            occurrence,
+            depth,
+        } => {
+            assert_eq!(binder, path(&[Body]));
+            assert_eq!(occurrence, path(&[Body, Body, Argument, Contents]));
+            assert_eq!(depth, 1);
+        }
+        error => panic!("{:?}", error),
+    }
+}
+
+#[test]
+fn duplication_depth() {
+    match stratification_error(r#"\a : x = a . . x"#) {
+        StratificationError::DupNonUnitBoxMultiplicity {
+            binder,
+            occurrence,
+            depth,
+        } => {
+            assert_eq!(binder, path(&[Body]));
+            assert_eq!(occurrence, path(&[Body, Body, Contents, Contents]));
+            assert_eq!(depth, 2);
+        }
+        error => panic!("{:?}", error),
+    }
+
+    let error = stratification_error(r#"\a : x = a x"#);
+    assert_eq!(
+        error.to_string(),
+        "The variable of the duplication at body is used at body.body inside 0 boxes, but a \
+         duplicated variable must be used inside exactly one more box than the duplication"
+    );
+}
+
+#[test]
+fn undefined_reference() {
+    let mut definitions: HashMap<String, (Term<String>, Term<String>)> = HashMap::new();
+    // Types aren't looked at, so they're left out.
+    for (name, term) in [("broken", r#"\x (missing x)"#), ("id", r#"\x x"#)] {
+        definitions.insert(name.into(), (Term::Universe, parse(term)));
+    }
+
+    for (term, name) in [("missing", "missing"), ("(id broken)", "missing")] {
+        match parse::<None>(term).stratified(&definitions) {
+            Err(StratificationError::UndefinedReference(found)) => assert_eq!(found, name),
+            Err(error) => panic!("{}: {:?}", term, error),
+            Ok(_) => panic!("{} stratified", term),
+        }
+    }
+
+    // A reference in an erased argument never reaches the net.
+    let net = parse::<None>(r#"[id missing]"#)
+        .stratified(&definitions)
+        .unwrap()
+        .into_net::<Net<u32>>();
+    assert!(net.is_ok());
+}
+
+#[test]
+fn infer_boxes() {
+    for (term, boxed) in [
+        (r#"\x x"#, r#"\x x"#),
+        (r#"\f \x (f (f x))"#, r#"\f : g = f . \x (g (g x))"#),
+        (r#"\x (x x)"#, r#"\x : y = x . (y y)"#),
+        (
+            r#"\x \y \z ((x z) (y z))"#,
+            r#"\x \y \z : w = z ((x . w) (y . w))"#,
+        ),
+        (r#"\f \x [(f (f x)) f]"#, r#"\f : g = f . \x [(g (g x)) g]"#),
+        (r#"\a \x : y = x (a . y)"#, r#"\a \x : y = x (a . y)"#),
+    ] {
+        let mut term = parse::<None>(term);
+        term.infer_boxes().unwrap();
+        assert!(term.equals(&parse(boxed)), "{:?}", term);
+        term.is_stratified().unwrap();
+    }
+
+    // Existing boxes are kept, and a use applied to a lambda variable can't be boxed.
+    for (term, depth) in [(r#"\a : x = a . . x"#, 2), (r#"\f \x ((x f) x)"#, 0)] {
+        let mut inferred = parse::<None>(term);
+        match inferred.infer_boxes() {
+            Err(StratificationError::DupNonUnitBoxMultiplicity {
+                depth: found_depth, ..
+            }) => assert_eq!(found_depth, depth, "{}", term),
+            result => panic!("{}: {:?}", term, result),
+        }
+    }
+
+    assert!(matches!(
+        parse::<None>(r#"/x x"#).infer_boxes(),
+        Err(StratificationError::ErasedUsed { .. })
+    ));
+}
+
+#[test]
+fn complexity() {
+    let mut definitions: HashMap<String, (Term<String>, Term<String>)> = HashMap::new();
+    definitions.insert(
+        "two".into(),
+        (Term::Universe, parse(r#"\f : g = f . \x (g (g x))"#)),
+    );
+
+    for (term, sizes, bound) in [
+        (r#"\x x"#, vec![2], Some(2)),
+        (r#". \x x"#, vec![0, 2], Some(16)),
+        ("two", vec![2, 6], None),
+        // A reference is measured at the depth it's used at.
+        (r#"(\x x . two)"#, vec![3, 2, 6], None),
+        (r#"[\x x two]"#, vec![2], Some(2)),
+    ] {
+        let complexity = parse::<None>(term)
+            .stratified(&definitions)
+            .unwrap()
+            .complexity();
+        assert_eq!(complexity.sizes, sizes, "{}", term);
+        assert_eq!(complexity.bound(), bound, "{}", term);
+    }
+
+    let complexity = parse::<None>("two")
+        .stratified(&definitions)
+        .unwrap()
+        .complexity();
+    assert_eq!(
+        complexity.to_string(),
+        "size 8 at depth 1\n    depth 0: 2\n    depth 1: 6\nrewrites and net size at most 2^2^8"
+    );
+}
+
+#[test]
+fn across_definitions() {
+    let mut definitions: HashMap<String, (Term<String>, Term<String>)> = HashMap::new();
+    for (name, term) in [
+        ("broken", r#"\x (x x)"#),
+        ("middle", r#"\y (broken y)"#),
+        ("id", r#"\x x"#),
+    ] {
+        definitions.insert(name.into(), (Term::Universe, parse(term)));
+    }
+
+    let term = parse::<None>(r#"(middle id)"#);
+    // On its own the term is stratified.
+    term.is_stratified().unwrap();
+    let error = term.is_stratified_across(&definitions).unwrap_err();
+    match &error {
+        StratificationError::InDefinition { chain, error } => {
+            assert_eq!(chain, &["middle", "broken"]);
+            assert!(matches!(
+                **error,
+                StratificationError::MultiplicityMismatch { uses: 2, .. }
+            ));
+        }
+        error => panic!("{:?}", error),
+    }
+    assert!(error
+        .to_string()
+        .starts_with("In middle -> broken: The variable of the lambda at <root> is used 2 times"));
+
+    // Nothing in an erased position reaches the net.
+    parse::<None>(r#"[id middle]"#)
+        .is_stratified_across(&definitions)
+        .unwrap();
+}
+
+#[test]
+fn argument_shapes() {
+    let mut definitions: HashMap<String, (Term<String>, Term<String>)> = HashMap::new();
+    for (name, term) in [
+        ("two", r#"\f : g = f . \x (g (g x))"#),
+        ("twice", r#"\f (two f)"#),
+        ("apply", r#"\f \x (f x)"#),
+        ("caller", r#"\y (twice \x x)"#),
+        ("lift", r#"\x . \y y"#),
+    ] {
+        definitions.insert(name.into(), (Term::Universe, parse(term)));
+    }
+
+    // Each term is stratified on its own, but passes an argument at a box depth other than the
+    // one the definition needs, directly, through another definition, or as what an application
+    // returns.
+    for (term, expected, depth, definition) in [
+        (r#"(two \y y)"#, 1, 0, "two"),
+        (r#"(twice \y y)"#, 1, 0, "twice"),
+        (r#"(apply . \y y)"#, 0, 1, "apply"),
+        (r#"(apply (lift *))"#, 0, 1, "apply"),
+    ] {
+        let term = parse::<None>(term);
+        term.is_stratified().unwrap();
+        match term.is_stratified_across(&definitions) {
+            Err(StratificationError::ArgumentDepth {
+                argument,
+                definition: name,
+                expected: needed,
+                depth: found,
+            }) => {
+                assert_eq!(argument, path(&[Argument]));
+                assert_eq!(name, definition);
+                assert_eq!((needed, found), (expected, depth));
+            }
+            result => panic!("{:?}: {:?}", term, result),
+        }
+        assert!(term.stratified(&definitions).is_err());
+    }
+
+    // A violation in a definition is reported with the chain leading to it.
+    match parse::<None>("caller").is_stratified_across(&definitions) {
+        Err(StratificationError::InDefinition { chain, error }) => {
+            assert_eq!(chain, &["caller"]);
+            assert!(matches!(*error, StratificationError::ArgumentDepth { .. }));
+        }
+        result => panic!("{:?}", result),
+    }
+
+    // An argument whose depth can't be worked out isn't taken to fit.
+    match parse::<None>(r#"\f (two (f *))"#).is_stratified_across(&definitions) {
+        Err(StratificationError::UnknownArgumentDepth {
+            argument,
+            definition,
+            expected,
+        }) => {
+            assert_eq!(argument, path(&[Body, Argument]));
+            assert_eq!(definition, "two");
+            assert_eq!(expected, 1);
+        }
+        result => panic!("{:?}", result),
+    }
+
+    // Arguments at the depth needed, including parameters passed on and values a duplication or
+    // an application yields, are accepted.
+    for term in [
+        r#"(two . \y y)"#,
+        r#"\z (twice z)"#,
+        r#"\z : w = z . (two w)"#,
+        r#"((apply \y y) *)"#,
+        r#"(two (lift *))"#,
+        r#"(apply \x (twice x))"#,
+    ] {
+        parse::<None>(term).stratified(&definitions).unwrap();
+    }
+}
+
+// Answers `\x (x x)`, which isn't stratified, whatever it's applied to.
+struct Unstratified;
+
+impl Jet<String, None> for Unstratified {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn apply<A: Allocator<String, None>>(
+        &self,
+        _: &[Term<String, None, A>],
+        alloc: &A,
+    ) -> Option<Term<String, None, A>> {
+        Some(Term::Lambda {
+            body: alloc.alloc(Term::Apply {
+                function: alloc.alloc(Term::Variable(Index(0))),
+                argument: alloc.alloc(Term::Variable(Index(0))),
+                erased: false,
+            }),
+            erased: false,
+        })
+    }
+}
+
+#[test]
+fn across_jets() {
+    let mut definitions: HashMap<String, (Term<String>, Term<String>)> = HashMap::new();
+    definitions.insert("id".into(), (Term::Universe, parse(r#"\x x"#)));
+    let hash = content_hash(&definitions, &"id".to_owned()).unwrap();
+    let jets = JetDefinitions::new(definitions, [(hash, Unstratified)].into());
+
+    // The jet's answer isn't stratified, so the saturated application is built from the
+    // definition rather than replaced by it.
+    let term = parse::<None>(r#"(id \z z)"#);
+    term.is_stratified_across(&jets).unwrap();
+    let mut net = term
+        .stratified(&jets)
+        .unwrap()
+        .into_net::<Net<u32>>()
+        .unwrap();
+    net.reduce_all();
+    assert!(net.read_term(NetIndex(0)).equals(&parse(r#"\z z"#)));
+
+    parse::<None>("id").is_stratified_across(&jets).unwrap();
+}
+
+#[test]
+fn usage() {
+    let uses = |uses: &[(&[Direction], usize, bool)]| {
+        uses.iter()
+            .map(|(directions, depth, erased)| Use {
+                path: path(directions),
+                depth: *depth,
+                erased: *erased,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        parse::<None>(r#"/A \f : g = f . [g A]"#).usage(),
+        vec![
+            Usage {
+                binder: path(&[]),
+                kind: BinderKind::ErasedLambda,
+                uses: uses(&[(&[Body, Body, Body, Contents, Argument], 1, true)]),
+            },
+            Usage {
+                binder: path(&[Body]),
+                kind: BinderKind::Lambda,
+                uses: uses(&[(&[Body, Body, Expression], 0, false)]),
+            },
+            Usage {
+                binder: path(&[Body, Body]),
+                kind: BinderKind::Duplication,
+                uses: uses(&[(&[Body, Body, Body, Contents, Function], 1, false)]),
+            },
+        ]
+    );
+
+    // Types are erased, but the body of an erased lambda isn't.
+    let usage = parse::<None>(r#"\x /y { (x x) : x }"#).usage();
+    assert_eq!(usage[0].computational_uses(), 2);
+    assert_eq!(usage[0].erased_uses(), 1);
+    assert!(matches!(
+        parse::<None>(r#"\x /y { (x x) : x }"#).is_stratified(),
+        Err(StratificationError::MultiplicityMismatch { uses: 2, .. })
+    ));
+}